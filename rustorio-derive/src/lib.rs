@@ -2,9 +2,9 @@ use proc_macro_crate::FoundCrate;
 use proc_macro2::{Span, TokenStream};
 use quote::{ToTokens, quote};
 use syn::{
-    Attribute, DeriveInput, Generics, Ident, ItemStruct, LitInt, Token, Type, parenthesized,
+    Attribute, DeriveInput, Expr, Generics, Ident, ItemStruct, LitInt, Token, Type, parenthesized,
     parse::{Parse, ParseStream},
-    parse_macro_input,
+    parse_macro_input, parse_quote,
     punctuated::Punctuated,
 };
 
@@ -15,7 +15,9 @@ impl ToTokens for Crate {
         let found_crate =
             proc_macro_crate::crate_name("rustorio-engine").expect("Failed to get crate name");
         match found_crate {
-            FoundCrate::Itself => quote! {crate}.to_tokens(tokens),
+            // The engine names itself with `extern crate self as rustorio_engine;`,
+            // so the same path works there and in its doctests, which are separate crates.
+            FoundCrate::Itself => quote! {::rustorio_engine}.to_tokens(tokens),
             FoundCrate::Name(name) => {
                 let crate_ident = Ident::new(&name, Span::call_site());
                 quote! {::#crate_ident}.to_tokens(tokens);
@@ -92,7 +94,12 @@ impl RecipeItemList {
         Self::new(attr, "recipe_outputs", "Outputs", "OUTPUT_AMOUNTS")
     }
 
-    fn generate_recipe_direction(&self, amount_type_name: &str) -> TokenStream {
+    fn generate_recipe_direction(
+        &self,
+        amount_type_name: &str,
+        amount_fn_name: &str,
+        count_const_name: &str,
+    ) -> TokenStream {
         let RecipeItemList {
             item_list,
             item_type_ident,
@@ -100,8 +107,14 @@ impl RecipeItemList {
         } = self;
 
         let amount_type_ident = Ident::new(amount_type_name, Span::call_site());
+        let amount_fn_ident = Ident::new(amount_fn_name, Span::call_site());
+        let count_const_ident = Ident::new(count_const_name, Span::call_site());
+        let count = item_list.len();
         let amount_types = item_list.iter().map(|_| quote! {u32}).collect::<Vec<_>>();
-        let amounts = item_list.iter().map(|(amount, _)| amount);
+        let amounts = item_list
+            .iter()
+            .map(|(amount, _)| amount)
+            .collect::<Vec<_>>();
 
         let recipe_items = item_list
             .iter()
@@ -112,6 +125,12 @@ impl RecipeItemList {
 
             type #amount_type_ident = (#(#amount_types,)*);
             const #amount_const_ident: (#(#amount_types,)*) = (#(#amounts,)*);
+            const #count_const_ident: usize = #count;
+
+            fn #amount_fn_ident(i: usize) -> ::core::option::Option<u32> {
+                const AMOUNTS: &[u32] = &[#(#amounts,)*];
+                AMOUNTS.get(i).copied()
+            }
         }
     }
 
@@ -205,6 +224,24 @@ impl RecipeItemList {
             (#(#bundle_items,)*)
         }
     }
+
+    /// Like [`generate_bundle_type`](RecipeItemList::generate_bundle_type), but for `N` cycles.
+    fn generate_scaled_bundle_type(&self) -> TokenStream {
+        let bundle_items = self.scaled_bundle_items();
+
+        quote! {
+            (#(#bundle_items,)*)
+        }
+    }
+
+    /// The bundle types of [`generate_scaled_bundle_type`](RecipeItemList::generate_scaled_bundle_type),
+    /// which also serve as the where clauses the scaled amounts need to be well-formed.
+    fn scaled_bundle_items(&self) -> Vec<TokenStream> {
+        self.item_list
+            .iter()
+            .map(|(amount, ty)| quote! {#Crate::resources::Bundle<#ty, { #amount * N }>})
+            .collect()
+    }
 }
 
 struct RecipeDetails {
@@ -214,6 +251,7 @@ struct RecipeDetails {
     inputs: RecipeItemList,
     outputs: RecipeItemList,
     ticks: LitInt,
+    output_scale: Option<LitInt>,
 }
 
 impl RecipeDetails {
@@ -225,6 +263,7 @@ impl RecipeDetails {
         let mut inputs = None;
         let mut outputs = None;
         let mut ticks = None;
+        let mut output_scale = None;
         for attr in attrs {
             if attr.path().is_ident("recipe_inputs") {
                 inputs = Some(RecipeItemList::new_inputs(attr));
@@ -235,6 +274,11 @@ impl RecipeDetails {
                     attr.parse_args::<LitInt>()
                         .expect("Invalid \"recipe_ticks\" value"),
                 );
+            } else if attr.path().is_ident("recipe_output_scale") {
+                output_scale = Some(
+                    attr.parse_args::<LitInt>()
+                        .expect("Invalid \"recipe_output_scale\" value"),
+                );
             }
         }
         let inputs = inputs.expect("Missing \"recipe_inputs\" attribute");
@@ -247,6 +291,7 @@ impl RecipeDetails {
             inputs,
             outputs,
             ticks,
+            output_scale,
         }
     }
 
@@ -259,9 +304,14 @@ impl RecipeDetails {
             doc_lines.push(format!("- [`{type_str}`] :  {amount}\n"));
         }
         doc_lines.push("### Output".to_string());
+        let scale = self
+            .output_scale
+            .as_ref()
+            .map(|scale| format!("/{scale}"))
+            .unwrap_or_default();
         for (amount, ty) in &self.outputs.item_list {
             let type_str = quote! { #ty }.to_string();
-            doc_lines.push(format!("- [`{type_str}`] :  {amount}\n"));
+            doc_lines.push(format!("- [`{type_str}`] :  {amount}{scale}\n"));
         }
         doc_lines.push("### Time".to_string());
 
@@ -272,8 +322,16 @@ impl RecipeDetails {
 
     fn recipe_impl(&self) -> TokenStream {
         let implementing_trait_path = quote! {#Crate::recipe::Recipe};
-        let inputs_stream = self.inputs.generate_recipe_direction("InputAmountsType");
-        let outputs_stream = self.outputs.generate_recipe_direction("OutputAmountsType");
+        let inputs_stream = self.inputs.generate_recipe_direction(
+            "InputAmountsType",
+            "input_amount",
+            "INPUT_COUNT",
+        );
+        let outputs_stream = self.outputs.generate_recipe_direction(
+            "OutputAmountsType",
+            "output_amount",
+            "OUTPUT_COUNT",
+        );
 
         let new_inputs_method_stream = self
             .inputs
@@ -286,9 +344,14 @@ impl RecipeDetails {
 
         let name = &self.name;
         let ticks = &self.ticks;
+        let output_scale = self
+            .output_scale
+            .as_ref()
+            .map(|scale| quote! { const OUTPUT_SCALE: u32 = #scale; });
         quote! {
             impl #impl_generics #Crate::recipe::Recipe for #name #ty_generics #where_clause {
                 const TIME: u64 = #ticks;
+                #output_scale
 
                 #new_inputs_method_stream
                 #new_outputs_method_stream
@@ -303,6 +366,13 @@ impl RecipeDetails {
         let implementing_trait_path = quote! {#Crate::recipe::Recipe};
         let input_bundle_type = self.inputs.generate_bundle_type();
         let output_bundle_type = self.outputs.generate_bundle_type();
+        let scaled_input_bundle_type = self.inputs.generate_scaled_bundle_type();
+        let scaled_output_bundle_type = self.outputs.generate_scaled_bundle_type();
+        let scaled_output_bundles = self
+            .outputs
+            .item_list
+            .iter()
+            .map(|(amount, ty)| quote! {#Crate::resources::bundle::<#ty, { #amount * N }>()});
         let new_output_bundle_method_stream = self
             .outputs
             .generate_recipe_new_bundle_method("new_output_bundle");
@@ -314,19 +384,48 @@ impl RecipeDetails {
             .generate_recipe_iter_method("iter_outputs", implementing_trait_path.clone());
         let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
         let name = &self.name;
+
+        let mut bundles_generics = self.generics.clone();
+        bundles_generics.params.push(parse_quote!(const N: u32));
+        let (bundles_impl_generics, _, _) = bundles_generics.split_for_impl();
+        let bundles_predicates = where_clause
+            .map(|where_clause| where_clause.predicates.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let scaled_bundle_items = self
+            .inputs
+            .scaled_bundle_items()
+            .into_iter()
+            .chain(self.outputs.scaled_bundle_items());
         quote! {
             impl #impl_generics #Crate::recipe::RecipeEx for #name #ty_generics #where_clause {
+                const NAME: &'static str = stringify!(#name);
                 type InputBundle = #input_bundle_type;
                 type OutputBundle = #output_bundle_type;
                 #new_output_bundle_method_stream
                 #iter_inputs_method_stream
                 #iter_outputs_method_stream
             }
+
+            impl #bundles_impl_generics #Crate::recipe::RecipeBundles<N> for #name #ty_generics
+            where
+                #(#bundles_predicates,)*
+                #(#scaled_bundle_items:,)*
+            {
+                type InputBundles = #scaled_input_bundle_type;
+                type OutputBundles = #scaled_output_bundle_type;
+
+                fn new_output_bundles() -> Self::OutputBundles {
+                    (#(#scaled_output_bundles,)*)
+                }
+            }
         }
     }
 }
 
-#[proc_macro_derive(Recipe, attributes(recipe_inputs, recipe_outputs, recipe_ticks))]
+#[proc_macro_derive(
+    Recipe,
+    attributes(recipe_inputs, recipe_outputs, recipe_ticks, recipe_output_scale)
+)]
 pub fn derive_recipe(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let recipe_info = RecipeDetails::from_input(input);
@@ -334,7 +433,10 @@ pub fn derive_recipe(input: proc_macro::TokenStream) -> proc_macro::TokenStream
     proc_macro::TokenStream::from(output)
 }
 
-#[proc_macro_derive(RecipeEx, attributes(recipe_inputs, recipe_outputs, recipe_ticks))]
+#[proc_macro_derive(
+    RecipeEx,
+    attributes(recipe_inputs, recipe_outputs, recipe_ticks, recipe_output_scale)
+)]
 pub fn derive_recipe_ex(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let recipe_info = RecipeDetails::from_input(input);
@@ -369,7 +471,7 @@ struct TechnologyDetails {
     generics: Generics,
     research_inputs: RecipeItemList,
     point_recipe_time: LitInt,
-    research_point_cost: LitInt,
+    research_point_cost: Expr,
 }
 
 impl TechnologyDetails {
@@ -386,7 +488,7 @@ impl TechnologyDetails {
                 research_inputs = Some(RecipeItemList::new_inputs(attr));
             } else if attr.path().is_ident("research_point_cost") {
                 research_point_cost = Some(
-                    attr.parse_args::<LitInt>()
+                    attr.parse_args::<Expr>()
                         .expect("Invalid \"research_point_cost\" value"),
                 );
             } else if attr.path().is_ident("research_ticks") {
@@ -421,9 +523,10 @@ impl TechnologyDetails {
 
         doc_lines.push(format!("**Ticks**: {}\n", self.point_recipe_time));
 
+        let research_point_cost = &self.research_point_cost;
         doc_lines.push(format!(
             "**Research points required**: {}",
-            self.research_point_cost
+            quote! { #research_point_cost }
         ));
 
         doc_lines.join("\n")
@@ -433,11 +536,19 @@ impl TechnologyDetails {
         let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
         let name = &self.name;
 
-        let inputs_stream = self
-            .research_inputs
-            .generate_recipe_direction("InputAmountsType");
+        let inputs_stream = self.research_inputs.generate_recipe_direction(
+            "InputAmountsType",
+            "input_amount",
+            "INPUT_COUNT",
+        );
         let research_point_cost = &self.research_point_cost;
         let point_recipe_time = &self.point_recipe_time;
+        let science_packs_per_point = self
+            .research_inputs
+            .item_list
+            .iter()
+            .map(|(amount, _)| amount)
+            .sum::<u32>();
 
         let input_bundle_type = self.research_inputs.generate_bundle_type();
 
@@ -456,6 +567,7 @@ impl TechnologyDetails {
                 #inputs_stream
                 const POINT_RECIPE_TIME: u64 = #point_recipe_time;
                 const REQUIRED_RESEARCH_POINTS_EX: u32 = #research_point_cost;
+                const SCIENCE_PACKS_PER_POINT: u32 = #science_packs_per_point;
                 type InputBundle = #input_bundle_type;
 
                 #new_inputs_method_stream
@@ -0,0 +1,282 @@
+//! Opt-in accounting of every resource the game creates and destroys, as a safety net for the engine and for mods.
+//! Only available with the `debug_conservation` feature, and compiled out entirely without it.
+//!
+//! Mining, hand crafting and machines report the resources they create and destroy to a [`Ledger`] kept by the [`Tick`].
+//! However resources are moved around in between, the amount of a resource in existence
+//! must then equal how much of it was created minus how much was destroyed.
+//! [`Tick::assert_conserved`] checks that against the amount the caller holds,
+//! so a resource that appears or vanishes anywhere else, e.g. through an accounting bug in a machine, is caught.
+//!
+//! Resources spent outside of recipes, like the cost of building a machine, aren't reported,
+//! so only resources whose every use the caller knows about can be checked.
+//!
+//! [`run_gamemode`](crate::run_gamemode) also checks that the victory resources were produced during the game,
+//! so a save file can't win with resources created through an engine helper that a mod shouldn't have exposed.
+//! Starting resources count as produced as long as the game mode creates them with
+//! [`starting_resource`](crate::gamemodes::starting_resource) or [`starting_bundle`](crate::gamemodes::starting_bundle).
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+};
+use core::fmt::{self, Display};
+
+use crate::{gamemodes::VictoryCondition, tick::Tick};
+
+/// How much of each resource has been created and destroyed over a whole game.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Ledger {
+    resources: BTreeMap<String, ResourceLedger>,
+}
+
+/// How much of one resource has been created and destroyed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLedger {
+    /// Amount created by mining, recipes and hand crafting.
+    pub created: u64,
+    /// Amount consumed by recipes and hand crafting.
+    pub destroyed: u64,
+}
+
+impl ResourceLedger {
+    /// How much of the resource should exist.
+    /// Negative if more was destroyed than created, which means some of it appeared out of nowhere.
+    pub fn expected(&self) -> i128 {
+        i128::from(self.created) - i128::from(self.destroyed)
+    }
+}
+
+/// Error returned by [`Ledger::check`] when a resource appeared or vanished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConservationError {
+    /// Name of the resource.
+    pub resource: String,
+    /// How much of the resource should exist.
+    pub expected: i128,
+    /// How much of the resource actually exists.
+    pub held: u64,
+}
+
+impl Display for ConservationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let held = i128::from(self.held);
+        let (verb, difference) = if held > self.expected {
+            ("appeared", held - self.expected)
+        } else {
+            ("vanished", self.expected - held)
+        };
+        write!(
+            f,
+            "{difference} {} {verb}: expected {}, but {} exist",
+            self.resource, self.expected, self.held
+        )
+    }
+}
+
+impl core::error::Error for ConservationError {}
+
+impl Ledger {
+    pub(crate) const fn new() -> Self {
+        Self {
+            resources: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn created(&mut self, resource: &str, amount: u32) {
+        if amount > 0 {
+            self.resource_mut(resource).created += u64::from(amount);
+        }
+    }
+
+    pub(crate) fn destroyed(&mut self, resource: &str, amount: u32) {
+        if amount > 0 {
+            self.resource_mut(resource).destroyed += u64::from(amount);
+        }
+    }
+
+    fn resource_mut(&mut self, resource: &str) -> &mut ResourceLedger {
+        self.resources.entry(resource.to_string()).or_default()
+    }
+
+    /// How much of `resource` has been created and destroyed.
+    pub fn resource(&self, resource: &str) -> ResourceLedger {
+        self.resources.get(resource).copied().unwrap_or_default()
+    }
+
+    /// Checks that `held` is exactly how much of `resource` should exist.
+    pub fn check(&self, resource: &str, held: u64) -> Result<(), ConservationError> {
+        let expected = self.resource(resource).expected();
+        if expected == i128::from(held) {
+            Ok(())
+        } else {
+            Err(ConservationError {
+                resource: resource.to_string(),
+                expected,
+                held,
+            })
+        }
+    }
+
+    /// Checks that no more of each resource in `victory_resources` is handed in than should exist.
+    pub fn check_victory<V: VictoryCondition>(
+        &self,
+        victory_resources: &V,
+    ) -> Result<(), ConservationError> {
+        let mut handed_in = BTreeMap::<&str, u64>::new();
+        for (resource, amount) in victory_resources.handed_in() {
+            *handed_in.entry(resource).or_default() += u64::from(amount);
+        }
+        for (resource, held) in handed_in {
+            let expected = self.resource(resource).expected();
+            if i128::from(held) > expected {
+                return Err(ConservationError {
+                    resource: resource.to_string(),
+                    expected,
+                    held,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Tick {
+    /// Asserts that `held` is exactly how much of `resource` should exist, given everything created and destroyed so far.
+    /// See the [module documentation](crate::conservation).
+    ///
+    /// # Panics
+    /// Panics if some of the resource appeared or vanished.
+    pub fn assert_conserved(&self, resource: &str, held: u64) {
+        if let Err(error) = self.ledger().check(resource, held) {
+            panic!("Resource not conserved: {error}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game_state::GameState,
+        gamemodes::{AtLeast, GameMode, StartingResources},
+        machine::Machine,
+        recipe::{Recipe, RecipeEx},
+        resource, resource_type,
+        resources::{Bundle, bundle},
+        run_gamemode, stats,
+    };
+
+    resource_type!(Ore);
+    resource_type!(Ingot);
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((2, Ore))]
+    #[recipe_outputs((1, Ingot))]
+    #[recipe_ticks(3)]
+    struct Smelting;
+
+    #[test]
+    fn leaks_are_caught() {
+        let mut tick = Tick::start();
+        let mut machine = Machine::<Smelting>::new(&tick);
+
+        // Mined ore, as a mod would mine it.
+        stats::record_produced(&tick, "Ore", 7);
+        machine.inputs(&tick).0.add(resource::<Ore>(7));
+        tick.advance_by(100);
+        let ore = machine.inputs(&tick).0.amount();
+        let ingots = machine.outputs(&tick).0.amount();
+        assert_eq!((ore, ingots), (1, 3));
+        tick.assert_conserved("Ore", ore.into());
+        tick.assert_conserved("Ingot", ingots.into());
+        assert_eq!(
+            tick.ledger().resource("Ore"),
+            ResourceLedger {
+                created: 7,
+                destroyed: 6
+            }
+        );
+
+        // An ingot out of nowhere.
+        machine.outputs(&tick).0.add(resource::<Ingot>(1));
+        let ingots = machine.outputs(&tick).0.amount();
+        let error = tick.ledger().check("Ingot", ingots.into()).unwrap_err();
+        assert_eq!((error.expected, error.held), (3, 4));
+        assert_eq!(
+            error.to_string(),
+            "1 Ingot appeared: expected 3, but 4 exist"
+        );
+
+        // Ore thrown away.
+        let _ = machine.inputs(&tick).0.empty();
+        let error = tick.ledger().check("Ore", 0).unwrap_err();
+        assert_eq!(error.to_string(), "1 Ore vanished: expected 1, but 0 exist");
+    }
+
+    struct NothingToStart;
+
+    impl StartingResources for NothingToStart {
+        fn init(tick: &Tick) -> Self {
+            let _ = tick;
+            Self
+        }
+    }
+
+    struct Smeltery;
+
+    impl GameMode for Smeltery {
+        type StartingResources = NothingToStart;
+        type VictoryResources = (Bundle<Ingot, 3>, AtLeast<Ore, 1>);
+    }
+
+    #[test]
+    fn fabricated_victory_resources_lose() {
+        let produced = run_gamemode::<Smeltery>(|mut tick, NothingToStart| {
+            let mut machine = Machine::<Smelting>::new(&tick);
+            stats::record_produced(&tick, "Ore", 7);
+            machine.inputs(&tick).0.add(resource::<Ore>(7));
+            tick.advance_by(100);
+            let ingots = machine.outputs(&tick).0.bundle().unwrap();
+            let ore = machine.inputs(&tick).0.empty();
+            (tick, (ingots, ore.into()))
+        });
+        assert_eq!(produced.outcome, Ok(()));
+
+        let fabricated = run_gamemode::<Smeltery>(|tick, NothingToStart| {
+            (tick, (bundle(), resource::<Ore>(1).into()))
+        });
+        assert_eq!(
+            fabricated.outcome,
+            Err("The victory resources weren't all produced: 3 Ingot appeared: expected 0, but 3 exist".to_string())
+        );
+    }
+
+    #[test]
+    fn restored_resources_count_as_created() {
+        let mut tick = Tick::start();
+        let mut machine = Machine::<Smelting>::new(&tick);
+        stats::record_produced(&tick, "Ore", 4);
+        machine.inputs(&tick).0.add(resource::<Ore>(4));
+        tick.advance_by(3);
+        assert_eq!(machine.inputs(&tick).0.amount(), 2);
+        let mut state = GameState::new();
+        state.store("machine", machine);
+
+        // A fresh game at the same tick, as when loading the state in a later run.
+        let mut tick = Tick::start();
+        tick.advance_by(3);
+        let mut machine: Machine<Smelting> = state.take(&tick, "machine").unwrap();
+        tick.assert_conserved("Ore", machine.inputs(&tick).0.amount().into());
+        tick.assert_conserved("Ingot", machine.outputs(&tick).0.amount().into());
+        assert_eq!(tick.ledger().resource("Ore").created, 2);
+        assert_eq!(tick.ledger().resource("Ingot").created, 1);
+    }
+
+    #[test]
+    #[should_panic = "Resource not conserved: 5 Ore appeared"]
+    fn assert_conserved_panics_on_leak() {
+        let tick = Tick::start();
+        let _ore = resource::<Ore>(5);
+        tick.assert_conserved("Ore", 5);
+    }
+}
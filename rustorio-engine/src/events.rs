@@ -0,0 +1,92 @@
+//! A log of why time passed, for finding out where a slow or stalled run spends its ticks.
+//!
+//! Call [`Tick::start_event_log`](crate::mod_reexports::Tick::start_event_log) and every advancement of the tick is logged with its [cause](AdvanceCause) and how many ticks it took.
+//! Read it back with [`Tick::event_log`](crate::mod_reexports::Tick::event_log), e.g. at the end of `user_main`:
+//! ```
+//! # use rustorio_engine::{events::AdvanceCause, mod_reexports::Tick};
+//! # fn report(tick: &Tick) {
+//! let log = tick.event_log().unwrap();
+//! let waited: u64 = log
+//!     .events()
+//!     .filter(|event| event.cause == AdvanceCause::AdvanceUntil)
+//!     .map(|event| event.ticks)
+//!     .sum();
+//! println!("Spent {waited} ticks waiting on conditions");
+//! # }
+//! ```
+//!
+//! The log only keeps the most recent events, so long games don't use ever more memory.
+//! A call to [`advance_until`](crate::mod_reexports::Tick::advance_until) is logged once, however many ticks it steps through.
+
+use alloc::collections::VecDeque;
+
+/// Why the tick advanced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AdvanceCause {
+    /// [`advance`](crate::mod_reexports::Tick::advance), [`advance_by`](crate::mod_reexports::Tick::advance_by) or [`advance_to_tick`](crate::mod_reexports::Tick::advance_to_tick) was called.
+    Advance,
+    /// Waiting for a condition, with [`advance_until`](crate::mod_reexports::Tick::advance_until) or something like it.
+    AdvanceUntil,
+    /// Mining ore by hand.
+    HandMine,
+    /// Crafting a [`HandRecipe`](crate::recipe::HandRecipe).
+    HandCraft,
+}
+
+/// A single advancement of the tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdvanceEvent {
+    /// The tick time started passing at.
+    pub tick: u64,
+    /// How many ticks passed.
+    pub ticks: u64,
+    /// Why they passed.
+    pub cause: AdvanceCause,
+}
+
+/// The most recent [`AdvanceEvent`]s, oldest first. See the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventLog {
+    capacity: usize,
+    events: VecDeque<AdvanceEvent>,
+    dropped: u64,
+}
+
+impl EventLog {
+    /// Creates an empty log that keeps the last `capacity` events.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "An event log must keep at least one event");
+        Self {
+            capacity,
+            events: VecDeque::with_capacity(capacity),
+            dropped: 0,
+        }
+    }
+
+    /// Logs an event, forgetting the oldest one if the log is full.
+    pub(crate) fn push(&mut self, event: AdvanceEvent) {
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+            self.dropped += 1;
+        }
+        self.events.push_back(event);
+    }
+
+    /// The logged events, oldest first.
+    pub fn events(&self) -> impl Iterator<Item = &AdvanceEvent> + '_ {
+        self.events.iter()
+    }
+
+    /// The causes of the logged events, oldest first.
+    pub fn causes(&self) -> impl Iterator<Item = AdvanceCause> + '_ {
+        self.events.iter().map(|event| event.cause)
+    }
+
+    /// How many events were forgotten to make room for newer ones.
+    pub const fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
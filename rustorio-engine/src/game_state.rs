@@ -0,0 +1,274 @@
+//! Saving a game to a file and picking it up again later.
+//!
+//! A game's state lives in the locals of the player's `user_main`, so the engine can't save it on its own.
+//! Instead the player moves everything they want to keep into a [`GameState`], [saves](GameState::save) it,
+//! and in a later run [loads](GameState::load) it and [takes](GameState::take) everything back out:
+//! ```rust
+//! use rustorio_engine::{game_state::GameState, mod_reexports::{Resource, Tick}};
+//! # rustorio_engine::resource_type!(Iron);
+//!
+//! fn pause(tick: &Tick, iron: Resource<Iron>, path: &std::path::Path) {
+//!     let mut state = GameState::new();
+//!     state.store("iron", iron);
+//!     state.save(tick, path).unwrap();
+//! }
+//!
+//! fn resume(tick: &mut Tick, path: &std::path::Path) -> Resource<Iron> {
+//!     let mut state = GameState::load(tick, path).unwrap();
+//!     state.take(tick, "iron").unwrap()
+//! }
+//! ```
+//!
+//! Storing moves things into the state and saving consumes it, so nothing can be both kept and saved.
+//! Loading, on the other hand, trusts the save file: whatever amounts it holds, edited or not, come back as is.
+//! So [`GameState`] isn't part of [`mod_reexports`](crate::mod_reexports) and should not be reexported in mods.
+//! It is for hosts and tools that run games on a player's behalf, which decide themselves which save files to trust.
+//!
+//! With the `debug_conservation` feature, everything [taken](GameState::take) out of a state
+//! counts as created in the `conservation` ledger, like starting resources do.
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Display};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+/// Reexported so mods can derive their [snapshots](Persist::Snapshot) with `#[serde(crate = "rustorio_engine::game_state::serde")]`
+/// instead of depending on `serde` themselves.
+pub use serde;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    machine::Machine,
+    recipe::RecipeEx,
+    resources::{Resource, ResourceType},
+    tick::Tick,
+};
+
+/// Something that can be stored in a [`GameState`].
+///
+/// Implemented for [`Resource`]s, [`Machine`]s and tuples of them. Mods implement it for their buildings.
+/// Should not be reexported in mods, as [`restore`](Persist::restore) creates things out of thin air.
+pub trait Persist: Sized {
+    /// The plain data written to the save file.
+    type Snapshot: Serialize + DeserializeOwned;
+
+    /// Turns this into plain data for the save file.
+    fn snapshot(self) -> Self::Snapshot;
+
+    /// Rebuilds this from the plain data in a save file.
+    /// Every [`Resource`] in it should be rebuilt with [`Persist::restore`] as well,
+    /// which reports it to the `tick`'s `conservation` ledger.
+    fn restore(snapshot: Self::Snapshot, tick: &Tick) -> Self;
+}
+
+impl<Content: ResourceType> Persist for Resource<Content> {
+    type Snapshot = u32;
+
+    fn snapshot(self) -> u32 {
+        self.amount
+    }
+
+    fn restore(snapshot: u32, tick: &Tick) -> Self {
+        #[cfg(feature = "debug_conservation")]
+        tick.with_ledger(|ledger| ledger.created(Content::NAME, snapshot));
+        #[cfg(not(feature = "debug_conservation"))]
+        let _ = tick;
+        Self::new(snapshot)
+    }
+}
+
+macro_rules! tuple_persist {
+    ($($name:ident),+) => {
+        impl<$($name: Persist),+> Persist for ($($name,)+) {
+            type Snapshot = ($($name::Snapshot,)+);
+
+            #[allow(non_snake_case)]
+            fn snapshot(self) -> Self::Snapshot {
+                let ($($name,)+) = self;
+                ($($name.snapshot(),)+)
+            }
+
+            #[allow(non_snake_case)]
+            fn restore(snapshot: Self::Snapshot, tick: &Tick) -> Self {
+                let ($($name,)+) = snapshot;
+                ($($name::restore($name, tick),)+)
+            }
+        }
+    };
+}
+
+tuple_persist!(A);
+tuple_persist!(A, B);
+tuple_persist!(A, B, C);
+tuple_persist!(A, B, C, D);
+
+/// The save file data of a [`Machine`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MachineSnapshot<Inputs, Outputs> {
+    pub(crate) inputs: Inputs,
+    pub(crate) outputs: Outputs,
+    pub(crate) tick: u64,
+    pub(crate) crafting_time: u64,
+    pub(crate) speed: u64,
+    pub(crate) starvation_limit: Option<u64>,
+    pub(crate) starved_since: Option<u64>,
+    pub(crate) broken: bool,
+    #[serde(default)]
+    pub(crate) output_remainders: Vec<u32>,
+    #[serde(default)]
+    pub(crate) busy_ticks: u64,
+    #[serde(default)]
+    pub(crate) observed_ticks: u64,
+}
+
+impl<Inputs, Outputs> MachineSnapshot<Inputs, Outputs> {
+    fn map<Inputs2, Outputs2>(
+        self,
+        inputs: impl FnOnce(Inputs) -> Inputs2,
+        outputs: impl FnOnce(Outputs) -> Outputs2,
+    ) -> MachineSnapshot<Inputs2, Outputs2> {
+        MachineSnapshot {
+            inputs: inputs(self.inputs),
+            outputs: outputs(self.outputs),
+            tick: self.tick,
+            crafting_time: self.crafting_time,
+            speed: self.speed,
+            starvation_limit: self.starvation_limit,
+            starved_since: self.starved_since,
+            broken: self.broken,
+            output_remainders: self.output_remainders,
+            busy_ticks: self.busy_ticks,
+            observed_ticks: self.observed_ticks,
+        }
+    }
+}
+
+impl<R: RecipeEx> Persist for Machine<R>
+where
+    R::Inputs: Persist,
+    R::Outputs: Persist,
+{
+    type Snapshot =
+        MachineSnapshot<<R::Inputs as Persist>::Snapshot, <R::Outputs as Persist>::Snapshot>;
+
+    fn snapshot(self) -> Self::Snapshot {
+        self.into_snapshot()
+            .map(Persist::snapshot, Persist::snapshot)
+    }
+
+    fn restore(snapshot: Self::Snapshot, tick: &Tick) -> Self {
+        Self::from_snapshot(snapshot.map(
+            |inputs| Persist::restore(inputs, tick),
+            |outputs| Persist::restore(outputs, tick),
+        ))
+    }
+}
+
+/// Error returned when saving, loading or taking from a [`GameState`] fails.
+#[derive(Debug)]
+pub enum GameStateError {
+    /// The save file couldn't be read or written.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// The save file isn't a valid save.
+    Format(serde_json::Error),
+    /// Nothing was stored under the name.
+    Missing(String),
+    /// What was stored under the name isn't what was asked for.
+    WrongType(String),
+}
+
+impl Display for GameStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(err) => write!(f, "Could not access save file: {err}"),
+            Self::Format(err) => write!(f, "Invalid save file: {err}"),
+            Self::Missing(name) => write!(f, "Nothing was saved as {name:?}"),
+            Self::WrongType(name) => write!(f, "What was saved as {name:?} is something else"),
+        }
+    }
+}
+
+impl core::error::Error for GameStateError {}
+
+/// A snapshot of everything the player chose to keep, along with the tick it was taken at.
+/// See the [module documentation](self) for how to use it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GameState {
+    tick: u64,
+    entries: BTreeMap<String, serde_json::Value>,
+}
+
+impl GameState {
+    /// Creates an empty game state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves `value` into the state under `name`.
+    /// Anything already stored under `name` is replaced and lost.
+    pub fn store<T: Persist>(&mut self, name: impl Into<String>, value: T) {
+        let value = serde_json::to_value(value.snapshot())
+            .expect("Snapshots of game objects are plain data");
+        self.entries.insert(name.into(), value);
+    }
+
+    /// Takes what was stored under `name` back out of the state.
+    /// If it is missing or of the wrong type, the state is left unchanged.
+    pub fn take<T: Persist>(&mut self, tick: &Tick, name: &str) -> Result<T, GameStateError> {
+        let value = self
+            .entries
+            .get(name)
+            .ok_or_else(|| GameStateError::Missing(name.to_string()))?;
+        let snapshot = T::Snapshot::deserialize(value)
+            .map_err(|_| GameStateError::WrongType(name.to_string()))?;
+        self.entries.remove(name);
+        Ok(T::restore(snapshot, tick))
+    }
+
+    /// The names of everything still stored in the state.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Whether everything has been taken out of the state.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Writes the state to `path`, recording the current tick.
+    #[cfg(feature = "std")]
+    pub fn save(mut self, tick: &Tick, path: impl AsRef<Path>) -> Result<(), GameStateError> {
+        self.tick = tick.cur();
+        let contents = serde_json::to_string_pretty(&self).map_err(GameStateError::Format)?;
+        std::fs::write(path, contents).map_err(GameStateError::Io)
+    }
+
+    /// Reads a state from `path` and moves `tick` on to the tick it was saved at.
+    /// Time never goes backwards, so loading a save from an earlier tick leaves `tick` as is.
+    ///
+    /// Loaded before any time has passed, the game resumes at the save's tick without it being recorded or logged as played,
+    /// as if it had been played from the game mode's [`START_TICK`](crate::gamemodes::GameMode::START_TICK) up to there.
+    /// Loaded later, the time in between is advanced like any other.
+    #[cfg(feature = "std")]
+    pub fn load(tick: &mut Tick, path: impl AsRef<Path>) -> Result<Self, GameStateError> {
+        let contents = std::fs::read_to_string(path).map_err(GameStateError::Io)?;
+        let state: Self = serde_json::from_str(&contents).map_err(GameStateError::Format)?;
+        if tick.has_advanced() {
+            tick.advance_to_tick(state.tick);
+        } else {
+            tick.resume_at(state.tick);
+        }
+        Ok(state)
+    }
+
+    /// The tick the state was saved at, or 0 if it hasn't been saved.
+    pub const fn tick(&self) -> u64 {
+        self.tick
+    }
+}
@@ -1,21 +1,505 @@
 //! A game mode defines the starting resources and victory conditions for a game.
 
-use crate::tick::Tick;
+use alloc::{collections::BTreeSet, format, string::String, vec, vec::Vec};
+use core::{
+    any::Any,
+    fmt::{self, Display},
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    recipe::RecipeFlow,
+    resources::{Bundle, Resource, ResourceType, Solid, bundle, resource},
+    tick::Tick,
+};
 
 /// The starting resources of a game mode. These are provided to the player at the beginning of the game.
 pub trait StartingResources {
     /// Called once at the start of the game before control is handed to the player to create the starting resources.
     ///
     /// # Parameters
-    /// - `tick`: The current game tick. Since this method is called at the start of the game, this will always be tick 0.
+    /// - `tick`: The current game tick. Since this method is called at the start of the game, this will always be [`GameMode::START_TICK`].
     fn init(tick: &Tick) -> Self;
 }
 
+/// Creates `amount` of a resource to hand out in the starting resources.
+/// With the `debug_conservation` feature, the resource is reported to the [`Tick`]'s ledger,
+/// so it can be handed in for victory like anything produced during the game.
+/// Should not be reexported in mods.
+///
+/// # Panics
+/// Panics unless the game [is starting](Tick::is_starting).
+pub fn starting_resource<Content: ResourceType>(tick: &Tick, amount: u32) -> Resource<Content> {
+    assert!(
+        tick.is_starting(),
+        "Starting resources can only be created while the game starts"
+    );
+    #[cfg(feature = "debug_conservation")]
+    tick.with_ledger(|ledger| ledger.created(Content::NAME, amount));
+    resource(amount)
+}
+
+/// Creates a [`Bundle`] to hand out in the starting resources, like [`starting_resource`].
+/// Should not be reexported in mods.
+///
+/// # Panics
+/// Panics unless the game [is starting](Tick::is_starting).
+pub fn starting_bundle<Content: Solid, const AMOUNT: u32>(tick: &Tick) -> Bundle<Content, AMOUNT> {
+    let _ = starting_resource::<Content>(tick, AMOUNT);
+    bundle()
+}
+
+/// Resources the player hands in to win a game.
+///
+/// Any [`Bundle`] is a victory condition that holds by construction, so single-bundle game modes need nothing else.
+/// Tuples of victory conditions require all of them, e.g. `(Bundle<Point, 200>, Bundle<Steel, 50>)`.
+/// Other types can implement [`check`](VictoryCondition::check) as a predicate over the final resources.
+pub trait VictoryCondition {
+    /// How much has to be handed in, summed over all resources, e.g. 200 for a `Bundle<Point, 200>`.
+    /// For showing progress towards victory. 0 for conditions that aren't about resources.
+    const REQUIRED_AMOUNT: u32 = 0;
+
+    /// Checks whether these resources, handed in at `tick`, win the game.
+    /// Returns the reason they don't otherwise.
+    fn check(&self, tick: &Tick) -> Result<(), String> {
+        let _ = tick;
+        Ok(())
+    }
+
+    /// The [names](ResourceType::NAME) of the resources that have to be handed in, used by [`validate_victory`].
+    /// Empty by default, for conditions that aren't about resources.
+    fn required_resources() -> Vec<&'static str>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
+
+    /// The [names](ResourceType::NAME) and amounts of the resources actually handed in.
+    /// With the `debug_conservation` feature, [`run_gamemode`](crate::run_gamemode) checks these were all produced during the game.
+    /// Empty by default, for conditions that aren't about resources.
+    fn handed_in(&self) -> Vec<(&'static str, u32)> {
+        Vec::new()
+    }
+}
+
+impl VictoryCondition for () {}
+
+impl<Content: ResourceType, const AMOUNT: u32> VictoryCondition for Bundle<Content, AMOUNT> {
+    const REQUIRED_AMOUNT: u32 = AMOUNT;
+
+    fn required_resources() -> Vec<&'static str> {
+        vec![Content::NAME]
+    }
+
+    fn handed_in(&self) -> Vec<(&'static str, u32)> {
+        vec![(Content::NAME, AMOUNT)]
+    }
+}
+
+/// Victory by handing in at least `AMOUNT` of a resource, rather than a [`Bundle`] of exactly that amount.
+///
+/// Create it from a [`Resource`] or a [`Bundle`] using [`From`], e.g. `furnace.outputs(&tick).0.empty().into()`.
+#[derive(Debug)]
+pub struct AtLeast<Content: ResourceType, const AMOUNT: u32>(pub Resource<Content>);
+
+impl<Content: ResourceType, const AMOUNT: u32> AtLeast<Content, AMOUNT> {
+    /// The amount of the resource handed in.
+    pub const fn amount(&self) -> u32 {
+        self.0.amount()
+    }
+}
+
+impl<Content: ResourceType, const AMOUNT: u32> From<Resource<Content>>
+    for AtLeast<Content, AMOUNT>
+{
+    fn from(resource: Resource<Content>) -> Self {
+        Self(resource)
+    }
+}
+
+impl<Content: ResourceType, const AMOUNT: u32, const BUNDLE_AMOUNT: u32>
+    From<Bundle<Content, BUNDLE_AMOUNT>> for AtLeast<Content, AMOUNT>
+{
+    fn from(bundle: Bundle<Content, BUNDLE_AMOUNT>) -> Self {
+        Self(bundle.to_resource())
+    }
+}
+
+impl<Content: ResourceType, const AMOUNT: u32> VictoryCondition for AtLeast<Content, AMOUNT> {
+    const REQUIRED_AMOUNT: u32 = AMOUNT;
+
+    fn check(&self, tick: &Tick) -> Result<(), String> {
+        let _ = tick;
+        if self.amount() >= AMOUNT {
+            Ok(())
+        } else {
+            Err(format!(
+                "Handed in {} {}, but at least {AMOUNT} are needed.",
+                self.amount(),
+                Content::NAME
+            ))
+        }
+    }
+
+    fn required_resources() -> Vec<&'static str> {
+        vec![Content::NAME]
+    }
+
+    fn handed_in(&self) -> Vec<(&'static str, u32)> {
+        vec![(Content::NAME, self.amount())]
+    }
+}
+
+macro_rules! tuple_victory_condition {
+    ($($name:ident),+) => {
+        impl<$($name: VictoryCondition),+> VictoryCondition for ($($name,)+) {
+            const REQUIRED_AMOUNT: u32 = 0 $(+ $name::REQUIRED_AMOUNT)+;
+
+            fn check(&self, tick: &Tick) -> Result<(), String> {
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $($name.check(tick)?;)+
+                Ok(())
+            }
+
+            fn required_resources() -> Vec<&'static str> {
+                let mut resources = Vec::new();
+                $(resources.extend($name::required_resources());)+
+                resources
+            }
+
+            fn handed_in(&self) -> Vec<(&'static str, u32)> {
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                let mut resources = Vec::new();
+                $(resources.extend($name.handed_in());)+
+                resources
+            }
+        }
+    };
+}
+
+tuple_victory_condition!(A);
+tuple_victory_condition!(A, B);
+tuple_victory_condition!(A, B, C);
+tuple_victory_condition!(A, B, C, D);
+tuple_victory_condition!(A, B, C, D, E);
+tuple_victory_condition!(A, B, C, D, E, F);
+
+/// How hard a game is. What each level changes is up to the mod; the engine only passes it on through [`Tick::difficulty`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    /// Easier than intended.
+    Easy,
+    /// The intended balance.
+    #[default]
+    Normal,
+    /// Harder than intended.
+    Hard,
+}
+
 /// A game mode defines the starting resources and victory conditions for a game.
 pub trait GameMode {
     /// Starting resources provided to the player at the beginning of the game.
     #[allow(private_bounds)]
     type StartingResources: StartingResources;
     /// Resources required to achieve victory.
-    type VictoryResources;
+    type VictoryResources: VictoryCondition;
+
+    /// If set, machines that are starved of inputs for more than this many ticks break and stop crafting for good.
+    /// See [`Machine::is_broken`](crate::machine::Machine::is_broken).
+    const STARVATION_LIMIT: Option<u64> = None;
+
+    /// How hard the game is. Available to everything built during the game through [`Tick::difficulty`].
+    const DIFFICULTY: Difficulty = Difficulty::Normal;
+
+    /// If set, at most this many miners work at once across the whole game, and any more slow all of them down.
+    /// See [`logistics`](crate::logistics).
+    const MINING_BUDGET: Option<u32> = None;
+
+    /// If set, the world is generated from this seed, e.g. how rich territories are.
+    /// The same seed always gives the same world. Available to the starting resources through [`Tick::seed`].
+    /// See [`rng`](crate::rng).
+    const SEED: Option<u64> = None;
+
+    /// How much has to be handed in to win, e.g. to report progress like `150/200 points`.
+    /// See [`VictoryCondition::REQUIRED_AMOUNT`].
+    const VICTORY_AMOUNT: u32 = Self::VictoryResources::REQUIRED_AMOUNT;
+
+    /// Parameters the game mode reads when the game starts, e.g. from a file.
+    /// The starting resources and [`check_victory`](GameMode::check_victory) get them through [`Tick::game_config`].
+    /// Since they can change the game, each result is tagged with a [hash](config_hash) of them.
+    type Config: Any + Hash + Default = ();
+
+    /// Reads the [`Config`](GameMode::Config) when the game starts. Defaults to [`Default::default`].
+    fn load_config() -> Self::Config {
+        Self::Config::default()
+    }
+
+    /// The tick the game starts at, e.g. to continue a scenario or a loaded save where it left off.
+    /// Everything, including the starting resources, sees time begin here rather than at 0,
+    /// and the [result](crate::GameResult::ticks) counts the ticks played from here.
+    const START_TICK: u64 = 0;
+
+    /// Called when the player hands in the victory resources at `tick` to decide whether they actually won.
+    /// Returns the reason the player lost otherwise.
+    ///
+    /// By default, this only checks the [`VictoryCondition`] of the victory resources.
+    fn check_victory(
+        tick: &Tick,
+        victory_resources: &Self::VictoryResources,
+    ) -> Result<(), String> {
+        victory_resources.check(tick)
+    }
+}
+
+/// A hash of a game mode's [config](GameMode::Config), to tell apart results of games with different configs.
+/// Uses FNV-1a, so it doesn't change between runs or Rust versions like [`core::hash::BuildHasher`]s may.
+pub fn config_hash<C: Hash>(config: &C) -> u64 {
+    struct Fnv1a(u64);
+    impl Hasher for Fnv1a {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        }
+    }
+    let mut hasher = Fnv1a(0xcbf2_9ce4_8422_2325);
+    config.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Macro to define a custom game mode that starts the player with a fixed set of bundles.
+///
+/// Defines the game mode type along with its starting resources type, whose fields are the given bundles.
+/// Any [`VictoryCondition`] can be used as the victory resources; [`GameMode::check_victory`] keeps its default.
+///
+/// Since the starting bundles are created from nothing, save files should not be able to invoke this macro,
+/// which is why it is not reexported in mods.
+///
+/// # Example
+/// ```rust
+/// use rustorio_engine::{game_mode, resource_type, resources::Bundle};
+///
+/// resource_type!(Gold);
+/// resource_type!(Crown);
+///
+/// game_mode! {
+///     /// Turn a pile of gold into crowns.
+///     Coronation {
+///         starting: CoronationStartingResources {
+///             /// Gold to forge crowns from.
+///             gold: Bundle<Gold, 100>,
+///         },
+///         victory: Bundle<Crown, 5>,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! game_mode {
+    (
+        $(#[$outer:meta])*
+        $name:ident {
+            starting: $starting:ident {
+                $(
+                    $(#[$field_meta:meta])*
+                    $field:ident: Bundle<$resource:ty, $amount:tt>
+                ),* $(,)?
+            },
+            victory: $victory:ty $(,)?
+        }
+    ) => {
+        #[doc = concat!("Starting resources for the [`", stringify!($name), "`] game mode.")]
+        pub struct $starting {
+            $(
+                $(#[$field_meta])*
+                pub $field: $crate::resources::Bundle<$resource, $amount>,
+            )*
+        }
+
+        impl $crate::gamemodes::StartingResources for $starting {
+            fn init(tick: &$crate::mod_reexports::Tick) -> Self {
+                let _ = tick;
+                Self {
+                    $($field: $crate::gamemodes::starting_bundle(tick),)*
+                }
+            }
+        }
+
+        $(#[$outer])*
+        pub struct $name;
+
+        impl $crate::gamemodes::GameMode for $name {
+            type StartingResources = $starting;
+            type VictoryResources = $victory;
+        }
+    };
+}
+
+/// Returned by [`validate_victory`] when a game mode's victory resources can't be produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnproducibleVictoryError {
+    /// The victory resources no recipe can reach.
+    pub missing: Vec<&'static str>,
+}
+
+impl Display for UnproducibleVictoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Victory requires resources that can't be produced: {}",
+            self.missing.join(", ")
+        )
+    }
+}
+
+impl core::error::Error for UnproducibleVictoryError {}
+
+/// Checks that every resource in `G`'s [victory resources](GameMode::VictoryResources) can be produced
+/// by some chain of `recipes` starting from the `available` resources, e.g. the starting resources and mineable ores.
+/// Meant to be called from tests when adding new game modes, so an impossible goal is caught before anyone plays it.
+///
+/// Technologies aren't modelled, so recipes are assumed to be unlocked whenever their inputs are available.
+pub fn validate_victory<G: GameMode>(
+    available: &[&'static str],
+    recipes: &[RecipeFlow],
+) -> Result<(), UnproducibleVictoryError> {
+    let mut reachable: BTreeSet<&'static str> = available.iter().copied().collect();
+    loop {
+        let before = reachable.len();
+        for recipe in recipes {
+            if recipe.inputs.iter().all(|input| reachable.contains(input)) {
+                reachable.extend(recipe.outputs.iter().copied());
+            }
+        }
+        if reachable.len() == before {
+            break;
+        }
+    }
+    let missing: Vec<_> = G::VictoryResources::required_resources()
+        .into_iter()
+        .filter(|resource| !reachable.contains(resource))
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(UnproducibleVictoryError { missing })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bundle,
+        recipe::{Recipe, RecipeEx},
+        resource, resource_type,
+    };
+
+    resource_type!(Gold);
+    resource_type!(GoldOre);
+    resource_type!(Unobtainium);
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((2, GoldOre))]
+    #[recipe_outputs((1, Gold))]
+    #[recipe_ticks(5)]
+    struct GoldSmelting;
+
+    struct StartsWithOre;
+
+    impl StartingResources for StartsWithOre {
+        fn init(tick: &Tick) -> Self {
+            let _ = tick;
+            Self
+        }
+    }
+
+    struct GoldRush;
+
+    impl GameMode for GoldRush {
+        type StartingResources = StartsWithOre;
+        type VictoryResources = (Bundle<Gold, 10>, AtLeast<GoldOre, 5>);
+    }
+
+    struct Impossible;
+
+    impl GameMode for Impossible {
+        type StartingResources = StartsWithOre;
+        type VictoryResources = (Bundle<Gold, 10>, Bundle<Unobtainium, 1>);
+    }
+
+    #[test]
+    fn victory_must_be_producible() {
+        let recipes = [RecipeFlow::of::<GoldSmelting>()];
+        assert_eq!(validate_victory::<GoldRush>(&["GoldOre"], &recipes), Ok(()));
+        assert_eq!(
+            validate_victory::<GoldRush>(&[], &recipes),
+            Err(UnproducibleVictoryError {
+                missing: vec!["Gold", "GoldOre"]
+            })
+        );
+        assert_eq!(
+            validate_victory::<Impossible>(&["GoldOre"], &recipes),
+            Err(UnproducibleVictoryError {
+                missing: vec!["Unobtainium"]
+            })
+        );
+    }
+
+    #[test]
+    fn victory_amounts_add_up() {
+        assert_eq!(GoldRush::VICTORY_AMOUNT, 15);
+        assert_eq!(Impossible::VICTORY_AMOUNT, 11);
+        assert_eq!(<(NotBefore,)>::REQUIRED_AMOUNT, 0);
+    }
+
+    /// Wins only after a given tick.
+    struct NotBefore(u64);
+
+    impl VictoryCondition for NotBefore {
+        fn check(&self, tick: &Tick) -> Result<(), String> {
+            if tick.cur() < self.0 {
+                Err(format!("Too early by {} ticks", self.0 - tick.cur()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn tuples_require_every_condition() {
+        let tick = Tick::start();
+        assert!(bundle::<Gold, 5>().check(&tick).is_ok());
+        assert!((bundle::<Gold, 5>(), NotBefore(0)).check(&tick).is_ok());
+        assert_eq!(
+            (bundle::<Gold, 5>(), NotBefore(3)).check(&tick),
+            Err("Too early by 3 ticks".to_string())
+        );
+    }
+
+    #[test]
+    fn at_least_accepts_more_but_not_less() {
+        let tick = Tick::start();
+        assert!(
+            AtLeast::<Gold, 5>::from(bundle::<Gold, 5>())
+                .check(&tick)
+                .is_ok()
+        );
+        assert!(
+            AtLeast::<Gold, 5>::from(resource::<Gold>(7))
+                .check(&tick)
+                .is_ok()
+        );
+        assert!(
+            AtLeast::<Gold, 5>::from(resource::<Gold>(4))
+                .check(&tick)
+                .is_err()
+        );
+    }
 }
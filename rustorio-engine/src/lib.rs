@@ -1,44 +1,220 @@
 #![feature(generic_const_exprs)]
+#![feature(associated_type_defaults)]
 #![allow(incomplete_features)] // silence the “still incomplete” lint
 #![warn(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
 //! The core engine for Rustorio.
 //! Only relevant if you are writing a mod for Rustorio.
 //! A save file depending on this crate has access to some APIs that make it trivial to cheat, which can be great for testing and debugging, but removes the challenge.
 //! To play the game, depend on the [`rustorio`](https://crates.io/crates/rustorio) crate instead.
 //!
 //! For more information, see the [repo](https://github.com/albertsgarde/rustorio).
+//!
+//! The simulation itself only needs `alloc`. Everything that does I/O, like [`play`], logging and saving to files, is behind the default `std` feature.
+//! Disable it to run the engine in `no_std` environments such as WASM.
+
+extern crate alloc;
+// Lets the derive macros refer to the engine as `::rustorio_engine` from inside it too.
+extern crate self as rustorio_engine;
 
+#[cfg(feature = "debug_conservation")]
+pub mod conservation;
+pub mod events;
+pub mod game_state;
 pub mod gamemodes;
+pub mod logistics;
 pub mod machine;
+pub mod plan;
 pub mod recipe;
+pub mod registry;
+pub mod replay;
 pub mod research;
 pub mod resources;
+pub mod rng;
+pub mod stats;
 mod tick;
 
-use std::sync::Once;
+use alloc::{boxed::Box, string::String};
+use core::{
+    fmt::{self, Display},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 pub use crate::resources::{ResourceType, bundle, resource};
 use crate::{
     gamemodes::{GameMode, StartingResources},
+    replay::{Replay, ReplayMismatch},
+    stats::Stats,
     tick::Tick,
 };
 
-static ONCE: Once = Once::new();
+/// Set by the first call to [`play_result`].
+/// Process-global on purpose and never reset, since a second game running alongside the first could be used to cheat.
+static PLAYED: AtomicBool = AtomicBool::new(false);
+
+/// If this environment variable is set when [`play`] is called, the game never logs tick advancement.
+/// Used by tools that run a save and only care about the result.
+pub const QUIET_ENV_VAR: &str = "RUSTORIO_QUIET";
+
+/// If this environment variable is set to `json` when [`play`] is called,
+/// the victory message is printed as `{"won": true, "ticks": N, "config": "..."}` instead of prose, for tools to parse.
+/// A loss is printed as `{"won": false, "ticks": N, "config": "...", "reason": "..."}`.
+/// `config` is the [config hash](GameResult::config_hash) in hexadecimal.
+pub const OUTPUT_ENV_VAR: &str = "RUSTORIO_OUTPUT";
+
+/// Error returned by [`play_result`] when a game has already been played in this process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlreadyPlayedError;
+
+impl Display for AlreadyPlayedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "play() can only be called once per program execution to prevent cheating via multithreading."
+        )
+    }
+}
+
+impl core::error::Error for AlreadyPlayedError {}
+
+/// Runs your play and returns the result instead of exiting the process.
+/// Meant for hosts that can't exit, like a browser playground.
+///
+/// Like [`play`], only one game can be played per process, shared between both functions.
+/// Every call after the first returns [`AlreadyPlayedError`] without running `main`.
+pub fn play_result<G: GameMode>(
+    main: fn(Tick, G::StartingResources) -> (Tick, G::VictoryResources),
+) -> Result<GameResult<G>, AlreadyPlayedError> {
+    if PLAYED.swap(true, Ordering::SeqCst) {
+        return Err(AlreadyPlayedError);
+    }
+    Ok(run_gamemode::<G>(main))
+}
 
 /// Runs your play. If it is run multiple times, it will panic. This is to prevent using multiple threads to cheat.
+#[cfg(feature = "std")]
 pub fn play<G: GameMode>(main: fn(Tick, G::StartingResources) -> (Tick, G::VictoryResources)) -> ! {
-    let mut call_once_ran = false;
-    ONCE.call_once(|| call_once_ran = true);
-    if !call_once_ran {
-        panic!(
-            "play() can only be called once per program execution to prevent cheating via multithreading."
-        );
+    let GameResult {
+        ticks,
+        outcome,
+        replay,
+        stats,
+        config_hash,
+        ..
+    } = play_result::<G>(main).unwrap_or_else(|err| panic!("{err}"));
+    if let Some(replay) = replay {
+        let path = std::env::var_os(replay::REPLAY_ENV_VAR)
+            .unwrap_or_else(|| replay::DEFAULT_REPLAY_FILE.into());
+        if let Err(err) = replay.save(&path) {
+            eprintln!("Could not save replay to {}: {err}", path.display());
+        }
     }
-    let tick = Tick::start();
+    if let Some(stats) = stats {
+        let path = std::env::var_os(stats::STATS_ENV_VAR)
+            .unwrap_or_else(|| stats::DEFAULT_STATS_FILE.into());
+        if let Err(err) = stats.save(&path) {
+            eprintln!("Could not save statistics to {}: {err}", path.display());
+        }
+    }
+    let json = std::env::var_os(OUTPUT_ENV_VAR).is_some_and(|output| output == "json");
+    match outcome {
+        Ok(()) => {
+            if json {
+                println!(r#"{{"won": true, "ticks": {ticks}, "config": "{config_hash:016x}"}}"#);
+            } else {
+                println!("You won in {ticks} ticks!");
+            }
+            std::process::exit(0);
+        }
+        Err(reason) => {
+            if json {
+                println!(
+                    r#"{{"won": false, "ticks": {ticks}, "config": "{config_hash:016x}", "reason": {reason:?}}}"#
+                );
+            } else {
+                println!("You lost after {ticks} ticks: {reason}");
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The outcome of a game run with [`run_gamemode`] or [`play_result`].
+#[derive(Debug)]
+pub struct GameResult<G: GameMode> {
+    /// How many ticks passed from the game mode's [`START_TICK`](GameMode::START_TICK) until the player handed in the victory resources.
+    pub ticks: u64,
+    /// The resources the player handed in.
+    pub victory_resources: G::VictoryResources,
+    /// Whether the player won according to [`GameMode::check_victory`], and why not if they lost.
+    pub outcome: Result<(), String>,
+    /// The [replay] of the game, if the player [recorded](Tick::start_recording) it.
+    pub replay: Option<Replay>,
+    /// The [production statistics](stats) of the game, if the player [collected](Tick::collect_stats) them.
+    pub stats: Option<Stats>,
+    /// A [hash](gamemodes::config_hash) of the game mode's [config](GameMode::Config).
+    /// Results are only comparable between games whose configs hash the same.
+    pub config_hash: u64,
+}
+
+/// Runs a game like [`play`], but returns the result instead of exiting the process.
+/// Useful for running a game in tests or other tools.
+///
+/// Unlike [`play`], this can be called any number of times, even concurrently.
+/// Should not be reexported in mods.
+pub fn run_gamemode<G: GameMode>(
+    main: fn(Tick, G::StartingResources) -> (Tick, G::VictoryResources),
+) -> GameResult<G> {
+    let mut tick = Tick::start_at(G::START_TICK);
+    #[cfg(feature = "std")]
+    if std::env::var_os(QUIET_ENV_VAR).is_some() {
+        tick.silence();
+    }
+    tick.set_starvation_limit(G::STARVATION_LIMIT);
+    tick.set_difficulty(G::DIFFICULTY);
+    tick.set_mining_budget(G::MINING_BUDGET);
+    tick.set_seed(G::SEED);
+    let config = G::load_config();
+    let config_hash = gamemodes::config_hash(&config);
+    tick.set_game_config(Box::new(config));
+    tick.set_starting(true);
     let start_resources = G::StartingResources::init(&tick);
-    let (tick, _points) = main(tick, start_resources);
-    println!("You won in {} ticks!", tick.cur());
-    std::process::exit(0);
+    tick.set_starting(false);
+    let (mut tick, victory_resources) = main(tick, start_resources);
+    let outcome = G::check_victory(&tick, &victory_resources);
+    #[cfg(feature = "debug_conservation")]
+    let outcome = outcome.and_then(|()| {
+        tick.ledger()
+            .check_victory(&victory_resources)
+            .map_err(|error| alloc::format!("The victory resources weren't all produced: {error}"))
+    });
+    GameResult {
+        ticks: tick.cur() - G::START_TICK,
+        outcome,
+        victory_resources,
+        replay: tick.stop_recording(),
+        stats: tick.stop_collecting_stats(),
+        config_hash,
+    }
+}
+
+/// Runs a game like [`run_gamemode`] and checks that it takes exactly the actions recorded in `replay`.
+/// The game must [record](Tick::start_recording) itself, just as it did when the replay was made.
+///
+/// The replay's actions are not executed themselves, as they don't say which machine or resource they were applied to.
+/// Instead, `main` is run again from the start and the actions it takes are compared with the recorded ones,
+/// so this verifies that `main` is the code that made the replay.
+/// Should not be reexported in mods.
+pub fn verify_replay<G: GameMode>(
+    main: fn(Tick, G::StartingResources) -> (Tick, G::VictoryResources),
+    replay: &Replay,
+) -> Result<GameResult<G>, ReplayMismatch> {
+    let result = run_gamemode::<G>(main);
+    let actual = result.replay.clone().unwrap_or_default();
+    match replay.first_difference(&actual) {
+        Some(mismatch) => Err(mismatch),
+        None => Ok(result),
+    }
 }
 
 /// A trait to prevent players from implementing certain traits.
@@ -52,12 +228,21 @@ pub trait Sealed {}
 /// pub use rustorio_engine::mod_reexports::*;
 /// ```
 pub mod mod_reexports {
+    #[cfg(feature = "std")]
+    pub use crate::play;
     pub use crate::{
-        gamemodes::GameMode,
-        play,
-        recipe::{HandRecipe, Recipe},
-        research::{ResearchPoint, Technology},
-        resources::{Bundle, InsufficientResourceError, Resource, ResourceType},
+        AlreadyPlayedError, GameResult,
+        events::{AdvanceCause, AdvanceEvent, EventLog},
+        gamemodes::{AtLeast, Difficulty, GameMode, VictoryCondition},
+        play_result,
+        recipe::{Buffer, HandRecipe, Recipe, assert_input_type, assert_output_type},
+        research::{
+            GENERIC_RESEARCH_POINT_REFUND_DIVISOR, GenericResearchPoint, GenericResearchPointOf,
+            ResearchPoint, ResearchQueue, Researched, Technology,
+        },
+        resources::{
+            AmountStyle, Blueprint, Bundle, InsufficientResourceError, Resource, ResourceType,
+        },
         tick::Tick,
     };
 }
@@ -0,0 +1,96 @@
+//! A cap on how many miners can work at once across the whole game, for game modes that limit logistics.
+//!
+//! When a game mode sets a [`MINING_BUDGET`](crate::gamemodes::GameMode::MINING_BUDGET),
+//! mods register every miner they place with [`miners_changed`].
+//! While more miners are registered than the budget allows, each of them works at `budget / miners` of its normal speed,
+//! so every territory gets its fair share and spamming miners stops paying off.
+//!
+//! Mods only update their miners lazily, so the budget keeps the history of how many miners there were when.
+//! That way it doesn't matter in which order territories catch up.
+
+use alloc::{vec, vec::Vec};
+
+use crate::tick::Tick;
+
+/// How many miners can work at once, and how many there were over time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MiningBudget {
+    limit: u32,
+    /// The tick from which each total number of miners applied, in order.
+    history: Vec<(u64, u32)>,
+}
+
+impl MiningBudget {
+    /// Creates a budget that lets `limit` miners work at once, with no miners registered yet.
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            history: vec![(0, 0)],
+        }
+    }
+
+    /// How many miners can work at once.
+    pub const fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// How many miners were registered at tick `at`.
+    pub fn miners_at(&self, at: u64) -> u32 {
+        let index = self.history.partition_point(|&(from, _)| from <= at);
+        self.history[index.saturating_sub(1)].1
+    }
+
+    /// The first tick after `at` at which the number of miners changed, if any.
+    pub fn next_change_after(&self, at: u64) -> Option<u64> {
+        let index = self.history.partition_point(|&(from, _)| from <= at);
+        self.history.get(index).map(|&(from, _)| from)
+    }
+
+    /// Replaces `removed` registered miners with `added` ones from tick `at` on.
+    ///
+    /// # Panics
+    /// Panics if `at` is before the last change, or more miners are removed than are registered.
+    pub fn miners_changed(&mut self, at: u64, removed: u32, added: u32) {
+        let &(last_change, miners) = self.history.last().expect("History starts at tick 0");
+        assert!(at >= last_change, "Miners changed in the past");
+        let miners = miners
+            .checked_sub(removed)
+            .expect("Removed more miners than were registered")
+            + added;
+        if at == last_change {
+            self.history.pop();
+        }
+        self.history.push((at, miners));
+    }
+}
+
+/// Replaces `removed` of the game's registered miners with `added` ones at the current tick,
+/// if its game mode has a [`MINING_BUDGET`](crate::gamemodes::GameMode::MINING_BUDGET).
+/// Should not be reexported in mods.
+pub fn miners_changed(tick: &Tick, removed: u32, added: u32) {
+    if removed != added {
+        tick.with_mining_budget_mut(|budget| budget.miners_changed(tick.cur(), removed, added));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_tracks_miners_over_time() {
+        let mut budget = MiningBudget::new(10);
+        budget.miners_changed(0, 0, 4);
+        budget.miners_changed(50, 0, 8);
+        budget.miners_changed(50, 2, 0);
+        budget.miners_changed(80, 10, 1);
+        assert_eq!(budget.limit(), 10);
+        assert_eq!(
+            [0, 49, 50, 79, 80, 1000].map(|at| budget.miners_at(at)),
+            [4, 4, 10, 10, 1, 1]
+        );
+        assert_eq!(budget.next_change_after(0), Some(50));
+        assert_eq!(budget.next_change_after(50), Some(80));
+        assert_eq!(budget.next_change_after(80), None);
+    }
+}
@@ -8,8 +8,25 @@
 //! pub struct Assembler<R: AssemblerRecipe>(Machine<R>);
 //! ```
 
+use alloc::{
+    boxed::Box,
+    rc::Rc,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    cell::RefCell,
+    fmt::Write,
+    ops::{Deref, DerefMut},
+};
+
 use crate::{
-    recipe::{Recipe, RecipeEx},
+    game_state::MachineSnapshot,
+    recipe::{Buffer, Recipe, RecipeEx},
+    replay::{ActionKind, Replay},
+    resources::{Resource, ResourceType},
+    stats::short_type_name,
     tick::Tick,
 };
 
@@ -22,6 +39,17 @@ pub enum BufferLocation {
     Output,
 }
 
+/// Finds the first non-empty buffer among `buffers`, as yielded by [`RecipeEx::iter_inputs`] or [`RecipeEx::iter_outputs`].
+/// Returns its resource name and amount, tagged with `location`.
+pub fn find_nonempty<'a>(
+    mut buffers: impl Iterator<Item = (&'static str, u32, &'a mut u32)>,
+    location: BufferLocation,
+) -> Option<(&'static str, u32, BufferLocation)> {
+    buffers.find_map(|(resource_type, _, &mut amount)| {
+        (amount > 0).then_some((resource_type, amount, location))
+    })
+}
+
 /// Error returned when trying to change a machine's recipe while it has non-empty input or output buffers.
 #[derive(Debug)]
 pub struct MachineNotEmptyError<M> {
@@ -50,8 +78,8 @@ impl<M> MachineNotEmptyError<M> {
     }
 }
 
-impl<R: Recipe> std::fmt::Display for MachineNotEmptyError<R> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<R: Recipe> core::fmt::Display for MachineNotEmptyError<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "Machine is not empty: machine has {} of resource {} in its {:?} buffer",
@@ -60,6 +88,115 @@ impl<R: Recipe> std::fmt::Display for MachineNotEmptyError<R> {
     }
 }
 
+/// Error returned by [`Machine::try_feed`] when feeding would put more than the allowed amount into an input buffer.
+#[derive(Debug)]
+pub struct TooMuchInputError<Content: ResourceType> {
+    /// The rejected resource, handed back untouched.
+    pub resource: Resource<Content>,
+    /// How much the input buffer already held.
+    pub current: u32,
+    /// How much the input buffer was allowed to hold.
+    pub capacity: u32,
+}
+
+impl<Content: ResourceType> core::fmt::Display for TooMuchInputError<Content> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Too much input: adding {} to a buffer holding {} would exceed its capacity of {}",
+            self.resource, self.current, self.capacity
+        )
+    }
+}
+
+impl<Content: ResourceType> core::error::Error for TooMuchInputError<Content> {}
+
+/// Input buffers of a [`Machine`], as handed out by [`Machine::inputs`] and [`Machine::input`].
+/// Derefs to the buffers, so e.g. `machine.inputs(&tick).0 += ore` works as if they were borrowed directly.
+///
+/// While the game is [recorded](Tick::start_recording), whatever was added to the buffers
+/// is recorded as fed into the machine when this is dropped, at the tick they were handed out.
+#[derive(Debug)]
+pub struct Feeding<'a, T> {
+    buffers: &'a mut T,
+    recording: Option<FeedRecording<T>>,
+}
+
+/// What [`Feeding`] needs to record feeds.
+#[derive(Debug)]
+struct FeedRecording<T> {
+    replay: Rc<RefCell<Replay>>,
+    at: u64,
+    /// Reads the name and amount of each buffer.
+    amounts: fn(&mut T) -> Vec<(&'static str, u32)>,
+    /// The amounts when the buffers were handed out.
+    observed: Vec<(&'static str, u32)>,
+}
+
+impl<'a, T> Feeding<'a, T> {
+    fn new(
+        tick: &Tick,
+        buffers: &'a mut T,
+        amounts: fn(&mut T) -> Vec<(&'static str, u32)>,
+    ) -> Self {
+        let recording = tick.replay().map(|replay| FeedRecording {
+            replay,
+            at: tick.cur(),
+            amounts,
+            observed: amounts(buffers),
+        });
+        Self { buffers, recording }
+    }
+}
+
+impl<T> Deref for Feeding<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.buffers
+    }
+}
+
+impl<T> DerefMut for Feeding<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.buffers
+    }
+}
+
+impl<T> Drop for Feeding<'_, T> {
+    fn drop(&mut self) {
+        let Some(recording) = &self.recording else {
+            return;
+        };
+        let mut replay = recording.replay.borrow_mut();
+        let amounts = (recording.amounts)(self.buffers);
+        for ((resource, current), (_, observed)) in amounts.into_iter().zip(&recording.observed) {
+            if current > *observed {
+                replay.push(
+                    recording.at,
+                    ActionKind::Feed {
+                        resource: resource.to_string(),
+                        amount: current - observed,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// A function called with the number of cycles a machine completed, set with [`Machine::on_cycle`].
+struct CycleCallback(Box<dyn FnMut(u32)>);
+
+impl core::fmt::Debug for CycleCallback {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("CycleCallback")
+    }
+}
+
+/// Roughly how many recent ticks [`Machine::utilization`] averages over.
+/// Whenever more ticks than this have been observed, the older half of the history is forgotten.
+pub const UTILIZATION_WINDOW: u64 = 1000;
+
 /// Basic machine that can process recipes.
 #[derive(Debug)]
 pub struct Machine<R: Recipe> {
@@ -67,27 +204,141 @@ pub struct Machine<R: Recipe> {
     outputs: R::Outputs,
     tick: u64,
     crafting_time: u64,
+    speed: u64,
+    starvation_limit: Option<u64>,
+    starved_since: Option<u64>,
+    broken: bool,
+    /// Whether the machine has been counted in the game's statistics since it got its recipe.
+    counted: bool,
+    /// Fractions of an item, in units of [`OUTPUT_SCALE`](Recipe::OUTPUT_SCALE), carried over for each output.
+    /// Empty for recipes without an output scale.
+    output_remainders: Vec<u32>,
+    on_cycle: Option<CycleCallback>,
+    /// Ticks spent crafting out of the `observed_ticks` [`utilization`](Machine::utilization) averages over.
+    busy_ticks: u64,
+    observed_ticks: u64,
 }
 
 impl<R: RecipeEx> Machine<R> {
-    fn new_inner(tick: u64) -> Self {
+    fn new_inner(tick: u64, speed: u64, starvation_limit: Option<u64>) -> Self {
+        assert!(speed > 0, "Machine speed must be positive");
         Self {
             inputs: R::new_inputs(),
             outputs: R::new_outputs(),
             tick,
             crafting_time: 0,
+            speed,
+            starvation_limit,
+            starved_since: None,
+            broken: false,
+            counted: false,
+            output_remainders: Vec::new(),
+            on_cycle: None,
+            busy_ticks: 0,
+            observed_ticks: 0,
         }
     }
 
     /// Build a new machine.
     pub fn new(tick: &Tick) -> Self {
-        Self::new_inner(tick.cur())
+        Self::new_inner(tick.cur(), 1, tick.starvation_limit())
+    }
+
+    /// Build a new machine that crafts `speed` times as fast as a normal one.
+    pub fn with_speed(tick: &Tick, speed: u64) -> Self {
+        Self::new_inner(tick.cur(), speed, tick.starvation_limit())
+    }
+
+    /// Whether the machine has broken from being starved of inputs for too long.
+    /// Broken machines no longer craft, but their buffers can still be emptied.
+    ///
+    /// Machines only break in game modes with a [`STARVATION_LIMIT`](crate::gamemodes::GameMode::STARVATION_LIMIT).
+    /// A machine counts as starved from the moment it lacks the inputs for its next cycle, including right after it is built.
+    pub fn is_broken(&mut self, tick: &Tick) -> bool {
+        self.tick(tick);
+        self.broken
+    }
+
+    /// Update internal state and return whether all input and output buffers are empty,
+    /// which is exactly when [`change_recipe`](Machine::change_recipe) succeeds.
+    pub fn is_empty(&mut self, tick: &Tick) -> bool {
+        self.tick(tick);
+        self.iter_inputs().all(|(_, _, &mut current)| current == 0)
+            && self.iter_outputs().all(|(_, _, &mut current)| current == 0)
+    }
+
+    /// How many ticks until the machine next finishes a cycle, or `None` if it won't without more inputs.
+    /// Used to [skip ahead](Tick::advance_until_scheduled) to the next tick where something happens.
+    pub fn ticks_until_next_output(&mut self, tick: &Tick) -> Option<u64> {
+        self.tick(tick);
+        if self.broken || !self.has_enough_inputs() {
+            return None;
+        }
+        Some(
+            R::TIME
+                .saturating_sub(self.crafting_time)
+                .div_ceil(self.speed.saturating_mul(tick.machine_speed_multiplier()))
+                .max(1),
+        )
+    }
+
+    /// How many times as fast as a normal machine this machine crafts.
+    pub const fn speed(&self) -> u64 {
+        self.speed
+    }
+
+    /// How many ticks of crafting time the machine has put towards its next cycle, as of its last update.
+    /// Crafting time accumulates [`speed`](Machine::speed) times as fast as game time.
+    ///
+    /// Doesn't update the machine. Use [`progress`](Machine::progress) for the up to date fraction of the cycle.
+    pub const fn crafting_progress_ticks(&self) -> u64 {
+        self.crafting_time
+    }
+
+    /// How much of each input the machine needs to produce `outputs_wanted` of the recipe's first output,
+    /// e.g. `[("Iron", 5), ("ElectronicCircuit", 5)]` for 5 red science, in the order the inputs are declared.
+    ///
+    /// Only whole cycles are crafted, so this is rounded up to the inputs of the cycles needed.
+    /// Works from the recipe alone, ignoring time and what is already in the machine's buffers.
+    /// Recipes that never produce their first output need [`u32::MAX`] of every input.
+    pub fn plan_inputs_for(&self, outputs_wanted: u32) -> Vec<(&'static str, u32)> {
+        let per_cycle = R::iter_outputs(&mut R::new_outputs())
+            .next()
+            .map_or(0, |(_, needed, _)| u64::from(needed));
+        let cycles = if outputs_wanted == 0 {
+            0
+        } else if per_cycle == 0 {
+            u64::MAX
+        } else {
+            (u64::from(outputs_wanted) * u64::from(R::OUTPUT_SCALE)).div_ceil(per_cycle)
+        };
+        R::iter_inputs(&mut R::new_inputs())
+            .map(|(resource, needed, _)| {
+                let amount = cycles.saturating_mul(u64::from(needed));
+                (resource, u32::try_from(amount).unwrap_or(u32::MAX))
+            })
+            .collect()
+    }
+
+    /// Update internal state and return how much of the current cycle is done, from 0 to 1.
+    /// Starved machines make no progress, so they report 0.
+    pub fn progress(&mut self, tick: &Tick) -> f64 {
+        self.tick(tick);
+        if R::TIME == 0 {
+            0.0
+        } else {
+            self.crafting_time.min(R::TIME) as f64 / R::TIME as f64
+        }
     }
 
     /// Update internal state and access input buffers.
-    pub fn inputs(&mut self, tick: &Tick) -> &mut R::Inputs {
+    pub fn inputs(&mut self, tick: &Tick) -> Feeding<'_, R::Inputs> {
         self.tick(tick);
-        &mut self.inputs
+        Feeding::new(tick, &mut self.inputs, |inputs| {
+            R::iter_inputs(inputs)
+                .map(|(resource, _, current)| (resource, *current))
+                .collect()
+        })
     }
 
     /// Update internal state and access output buffers.
@@ -96,30 +347,212 @@ impl<R: RecipeEx> Machine<R> {
         &mut self.outputs
     }
 
+    pub(crate) fn into_snapshot(self) -> MachineSnapshot<R::Inputs, R::Outputs> {
+        MachineSnapshot {
+            inputs: self.inputs,
+            outputs: self.outputs,
+            tick: self.tick,
+            crafting_time: self.crafting_time,
+            speed: self.speed,
+            starvation_limit: self.starvation_limit,
+            starved_since: self.starved_since,
+            broken: self.broken,
+            output_remainders: self.output_remainders,
+            busy_ticks: self.busy_ticks,
+            observed_ticks: self.observed_ticks,
+        }
+    }
+
+    pub(crate) fn from_snapshot(snapshot: MachineSnapshot<R::Inputs, R::Outputs>) -> Self {
+        Self {
+            inputs: snapshot.inputs,
+            outputs: snapshot.outputs,
+            tick: snapshot.tick,
+            crafting_time: snapshot.crafting_time,
+            speed: snapshot.speed,
+            starvation_limit: snapshot.starvation_limit,
+            starved_since: snapshot.starved_since,
+            broken: snapshot.broken,
+            counted: false,
+            output_remainders: snapshot.output_remainders,
+            on_cycle: None,
+            busy_ticks: snapshot.busy_ticks,
+            observed_ticks: snapshot.observed_ticks,
+        }
+    }
+
+    /// Update internal state and access input buffer `I`, without counting tuple fields by hand.
+    /// Indices past the recipe's inputs don't compile:
+    /// ```compile_fail,E0277
+    /// # use rustorio_engine::{machine::Machine, mod_reexports::Tick, recipe::{Recipe, RecipeEx}, resource_type};
+    /// # resource_type!(IronOre);
+    /// # resource_type!(Iron);
+    /// # #[derive(Debug, Recipe, RecipeEx)]
+    /// # #[recipe_inputs((1, IronOre))]
+    /// # #[recipe_outputs((1, Iron))]
+    /// # #[recipe_ticks(2)]
+    /// # struct IronSmelting;
+    /// fn second_input(furnace: &mut Machine<IronSmelting>, tick: &Tick) {
+    ///     furnace.input::<1>(tick);
+    /// }
+    /// ```
+    pub fn input<const I: usize>(
+        &mut self,
+        tick: &Tick,
+    ) -> Feeding<'_, Resource<<R::Inputs as Buffer<I>>::Content>>
+    where
+        R::Inputs: Buffer<I>,
+    {
+        self.tick(tick);
+        Feeding::new(tick, self.inputs.buffer(), |input| {
+            vec![(<R::Inputs as Buffer<I>>::Content::NAME, input.amount())]
+        })
+    }
+
+    /// Adds all of `resource` to input buffer `I` if the buffer then holds at most `capacity`.
+    /// Otherwise nothing is added and `resource` is handed back in the error.
+    ///
+    /// Unlike [`Resource::add`], which takes anything, this makes overfilling a building something the caller has to handle.
+    pub fn try_feed<const I: usize>(
+        &mut self,
+        tick: &Tick,
+        resource: Resource<<R::Inputs as Buffer<I>>::Content>,
+        capacity: u32,
+    ) -> Result<(), TooMuchInputError<<R::Inputs as Buffer<I>>::Content>>
+    where
+        R::Inputs: Buffer<I>,
+    {
+        let mut input = self.input::<I>(tick);
+        if input.amount().saturating_add(resource.amount()) > capacity {
+            return Err(TooMuchInputError {
+                resource,
+                current: input.amount(),
+                capacity,
+            });
+        }
+        input.add(resource);
+        Ok(())
+    }
+
+    /// Update internal state and return how much more input buffer `I` can take before it holds `capacity`.
+    /// The read side of [`try_feed`](Machine::try_feed): feeding exactly this much never overfills the buffer.
+    pub fn input_headroom<const I: usize>(&mut self, tick: &Tick, capacity: u32) -> u32
+    where
+        R::Inputs: Buffer<I>,
+    {
+        capacity.saturating_sub(self.input::<I>(tick).amount())
+    }
+
+    /// Update internal state and access output buffer `I`, without counting tuple fields by hand.
+    /// Like [`input`](Machine::input), indices past the recipe's outputs don't compile.
+    pub fn output<const I: usize>(
+        &mut self,
+        tick: &Tick,
+    ) -> &mut Resource<<R::Outputs as Buffer<I>>::Content>
+    where
+        R::Outputs: Buffer<I>,
+    {
+        self.outputs(tick).buffer()
+    }
+
     fn iter_inputs(&mut self) -> impl Iterator<Item = (&'static str, u32, &mut u32)> {
+        debug_assert_eq!(
+            R::iter_inputs(&mut self.inputs).count(),
+            R::INPUT_COUNT,
+            "iter_inputs must yield every input buffer"
+        );
         R::iter_inputs(&mut self.inputs)
     }
 
     fn iter_outputs(&mut self) -> impl Iterator<Item = (&'static str, u32, &mut u32)> {
+        debug_assert_eq!(
+            R::iter_outputs(&mut self.outputs).count(),
+            R::OUTPUT_COUNT,
+            "iter_outputs must yield every output buffer"
+        );
         R::iter_outputs(&mut self.outputs)
     }
 
+    /// Update internal state and describe the machine for logging, e.g.
+    /// `Alloying { inputs: [Ore: 4/3, Ingot: 2/2], outputs: [Ingot: 2/2], progress: 3/7 }`.
+    ///
+    /// Each buffer is shown as `Name: current/needed`, where `needed` is the amount per cycle,
+    /// followed by how many ticks of the current cycle are done. Broken machines end with `, broken`.
+    pub fn describe(&mut self, tick: &Tick) -> String {
+        fn buffers<'a>(
+            description: &mut String,
+            iter: impl Iterator<Item = (&'static str, u32, &'a mut u32)>,
+        ) {
+            for (i, (resource, needed, current)) in iter.enumerate() {
+                let separator = if i == 0 { "" } else { ", " };
+                write!(description, "{separator}{resource}: {current}/{needed}")
+                    .expect("Writing to a string can't fail");
+            }
+        }
+
+        self.tick(tick);
+        let mut description = short_type_name::<R>();
+        description += " { inputs: [";
+        buffers(&mut description, self.iter_inputs());
+        description += "], outputs: [";
+        buffers(&mut description, self.iter_outputs());
+        write!(
+            description,
+            "], progress: {}/{}",
+            self.crafting_time.min(R::TIME),
+            R::TIME
+        )
+        .expect("Writing to a string can't fail");
+        if self.broken {
+            description += ", broken";
+        }
+        description + " }"
+    }
+
+    /// Calls `f` whenever the machine completes cycles, so code can react to production instead of polling the outputs.
+    ///
+    /// The machine only updates when it is accessed, so `f` is called once per update with the number of cycles completed since the last one.
+    /// After a long [`advance_by`](Tick::advance_by) that is the total for the whole jump, not one call per cycle.
+    /// Updates that complete no cycles don't call `f`.
+    ///
+    /// Replaces any earlier callback. The callback is dropped when the recipe is [changed](Machine::change_recipe) or the machine is saved.
+    pub fn on_cycle(&mut self, f: impl FnMut(u32) + 'static) {
+        self.on_cycle = Some(CycleCallback(Box::new(f)));
+    }
+
+    /// Update internal state and return the fraction of recent ticks the machine spent crafting rather than starved, from 0 to 1.
+    /// Recent means roughly the last [`UTILIZATION_WINDOW`] ticks. A machine that hasn't seen a tick pass yet reports 0.
+    ///
+    /// Machines with the lowest utilization are waiting on their inputs, so the bottleneck is whatever feeds them.
+    /// Changing the recipe starts the average over.
+    pub fn utilization(&mut self, tick: &Tick) -> f64 {
+        self.tick(tick);
+        if self.observed_ticks == 0 {
+            0.0
+        } else {
+            self.busy_ticks as f64 / self.observed_ticks as f64
+        }
+    }
+
+    /// Adds `busy` of the `elapsed` ticks since the last update to the [utilization](Machine::utilization) average.
+    const fn record_utilization(&mut self, busy: u64, elapsed: u64) {
+        self.busy_ticks = self.busy_ticks.saturating_add(busy);
+        self.observed_ticks = self.observed_ticks.saturating_add(elapsed);
+        while self.observed_ticks > UTILIZATION_WINDOW {
+            self.busy_ticks /= 2;
+            self.observed_ticks /= 2;
+        }
+    }
+
     /// Changes the [`Recipe`](crate::recipe) of the machine.
     /// Returns the original machine if the machine has any inputs or outputs.
+    // The error hands the machine back, so it is as large as the machine.
+    #[allow(clippy::result_large_err)]
     pub fn change_recipe<R2: RecipeEx>(
         mut self,
         recipe: R2,
     ) -> Result<Machine<R2>, MachineNotEmptyError<Self>> {
         let _ = recipe;
-        fn find_nonempty<'a>(
-            mut iter: impl Iterator<Item = (&'static str, u32, &'a mut u32)>,
-            location: BufferLocation,
-        ) -> Option<(&'static str, u32, BufferLocation)> {
-            iter.find_map(|(resource_name, _needed, &mut current)| {
-                (current > 0).then_some((resource_name, current, location))
-            })
-        }
-
         if let Some((resource_type, amount, location)) =
             find_nonempty(self.iter_inputs(), BufferLocation::Input)
                 .or_else(|| find_nonempty(self.iter_outputs(), BufferLocation::Output))
@@ -131,37 +564,553 @@ impl<R: RecipeEx> Machine<R> {
                 location,
             })
         } else {
-            Ok(Machine::new_inner(self.tick))
+            let mut machine = Machine::new_inner(self.tick, self.speed, self.starvation_limit);
+            // An empty machine is starved, so changing its recipe neither resets nor repairs it.
+            machine.starved_since = self.starved_since;
+            machine.broken = self.broken;
+            Ok(machine)
+        }
+    }
+
+    fn has_enough_inputs(&mut self) -> bool {
+        self.iter_inputs()
+            .all(|(_, needed, current)| *current >= needed)
+    }
+
+    /// Breaks the machine if it has been starved from `since` until `until` for longer than it can handle.
+    fn check_starvation(&mut self, since: u64, until: u64) {
+        if self
+            .starvation_limit
+            .is_some_and(|limit| until - since > limit)
+        {
+            self.broken = true;
         }
     }
 
     fn tick(&mut self, tick: &Tick) {
         assert!(tick.cur() >= self.tick, "Tick must be non-decreasing");
 
-        self.crafting_time += tick.cur() - self.tick;
+        if let Some(since) = self.starved_since
+            && !self.broken
+            && self.has_enough_inputs()
+        {
+            // The inputs were refilled at the last update, which ended the starvation.
+            self.check_starvation(since, self.tick);
+            self.starved_since = None;
+        }
+        if self.broken {
+            self.record_stats(tick, 0);
+            self.record_utilization(0, tick.cur() - self.tick);
+            self.tick = tick.cur();
+            return;
+        }
+
+        let previous_crafting_time = self.crafting_time;
+        // Saturating, as a huge jump only matters up to what the inputs allow anyway.
+        self.crafting_time = self.crafting_time.saturating_add(
+            tick.boosted_ticks(self.tick, tick.cur())
+                .saturating_mul(self.speed),
+        );
         let crafting_time = self.crafting_time;
-        let count = self
+        let input_cycles = self
             .iter_inputs()
             .map(|(_, needed, current)| *current / needed)
-            .chain((R::TIME > 0).then(|| (crafting_time / R::TIME).try_into().unwrap()))
             .min()
-            .unwrap();
+            .unwrap_or(u32::MAX);
+        if R::OUTPUT_SCALE != 1 {
+            self.output_remainders.resize(R::OUTPUT_COUNT, 0);
+        }
+        let scale = u64::from(R::OUTPUT_SCALE);
+        // A full output buffer holds the machine up instead of overflowing.
+        let output_cycles = R::iter_outputs(&mut self.outputs)
+            .enumerate()
+            .filter(|(_, (_, needed, _))| *needed > 0)
+            .map(|(i, (_, needed, current))| {
+                let remainder = self.output_remainders.get(i).map_or(0, |&r| u64::from(r));
+                let room = (u64::from(u32::MAX - *current) + 1) * scale - 1 - remainder;
+                room / u64::from(needed)
+            })
+            .min()
+            .unwrap_or(u64::MAX);
+        // At most `input_cycles`, so it fits in a `u32`.
+        let count = crafting_time
+            .checked_div(R::TIME)
+            .unwrap_or(u64::MAX)
+            .min(u64::from(input_cycles))
+            .min(output_cycles) as u32;
 
-        for (_, needed, current) in self.iter_inputs() {
+        for (_resource, needed, current) in self.iter_inputs() {
             *current -= count * needed;
+            #[cfg(feature = "debug_conservation")]
+            tick.with_ledger(|ledger| ledger.destroyed(_resource, count * needed));
         }
-        for (_, needed, current) in self.iter_outputs() {
-            *current += count * needed;
+        let collecting_stats = tick.is_collecting_stats();
+        for (i, (resource, needed, current)) in R::iter_outputs(&mut self.outputs).enumerate() {
+            let mut total = u64::from(count) * u64::from(needed);
+            if let Some(remainder) = self.output_remainders.get_mut(i) {
+                total += u64::from(*remainder);
+                // Less than the scale, so it fits.
+                *remainder = (total % scale) as u32;
+            }
+            // At most the room left in the buffer, by `output_cycles`.
+            let produced = (total / scale) as u32;
+            *current += produced;
+            #[cfg(feature = "debug_conservation")]
+            tick.with_ledger(|ledger| ledger.created(resource, produced));
+            if collecting_stats {
+                tick.with_stats(|stats| stats.produced(resource, produced));
+            }
         }
         self.crafting_time -= u64::from(count) * R::TIME;
-
-        if self
-            .iter_inputs()
-            .any(|(_, needed, current)| *current < needed)
+        if collecting_stats {
+            self.record_consumption(tick, count);
+        }
+        if count > 0
+            && let Some(CycleCallback(on_cycle)) = &mut self.on_cycle
         {
+            on_cycle(count);
+        }
+
+        if !self.has_enough_inputs() {
             self.crafting_time = 0;
+            let start = self.tick;
+            let speed = self.speed.saturating_mul(tick.machine_speed_multiplier());
+            // Starved since the last cycle finished, or since the last update if none did.
+            let since = *self.starved_since.get_or_insert_with(|| {
+                start
+                    + (u64::from(count) * R::TIME)
+                        .saturating_sub(previous_crafting_time)
+                        .div_ceil(speed)
+            });
+            self.check_starvation(since, tick.cur());
         }
 
+        let busy_until = self
+            .starved_since
+            .map_or(tick.cur(), |since| since.clamp(self.tick, tick.cur()));
+        self.record_stats(tick, busy_until - self.tick);
+        self.record_utilization(busy_until - self.tick, tick.cur() - self.tick);
         self.tick = tick.cur();
     }
+
+    /// Records the resources consumed by `count` cycles, if the game is collecting statistics.
+    fn record_consumption(&mut self, tick: &Tick, count: u32) {
+        tick.with_stats(|stats| {
+            for (resource, needed, _) in R::iter_inputs(&mut self.inputs) {
+                stats.consumed(resource, count * needed);
+            }
+        });
+    }
+
+    /// Records how much of the time since the last update the machine spent crafting, if the game is collecting statistics.
+    fn record_stats(&mut self, tick: &Tick, busy_ticks: u64) {
+        tick.with_stats(|stats| {
+            let recipe = short_type_name::<R>();
+            if !self.counted {
+                stats.machine_built(&recipe);
+                self.counted = true;
+            }
+            stats.machine_ran(&recipe, busy_ticks, tick.cur() - self.tick);
+        });
+    }
+}
+
+/// Makes every machine in the game craft `multiplier` times as fast as its own [speed](Machine::speed) from the current tick on,
+/// e.g. as the payoff of a technology. Crafting done before now isn't affected, however lazily machines are updated.
+/// Should not be reexported in mods.
+///
+/// # Panics
+/// Panics if `multiplier` is 0.
+pub fn set_speed_multiplier(tick: &mut Tick, multiplier: u64) {
+    assert!(multiplier > 0, "Machine speed multiplier must be positive");
+    tick.set_machine_speed_multiplier(multiplier);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{resource, resource_type};
+
+    resource_type!(Ore);
+    resource_type!(Ingot);
+    resource_type!(Slag);
+
+    #[derive(Debug, crate::recipe::Recipe, crate::recipe::RecipeEx)]
+    #[recipe_inputs((1, Ore))]
+    #[recipe_outputs((1, Ingot))]
+    #[recipe_ticks(3)]
+    struct Smelting;
+
+    #[derive(Debug, crate::recipe::Recipe, crate::recipe::RecipeEx)]
+    #[recipe_inputs((3, Ore), (2, Ingot))]
+    #[recipe_outputs((2, Ingot))]
+    #[recipe_ticks(7)]
+    struct Alloying;
+
+    #[derive(Debug, crate::recipe::Recipe, crate::recipe::RecipeEx)]
+    #[recipe_inputs((2, Ore))]
+    #[recipe_outputs((2, Ingot), (3, Slag))]
+    #[recipe_ticks(1)]
+    struct Splitting;
+
+    #[derive(Debug, crate::recipe::Recipe, crate::recipe::RecipeEx)]
+    #[recipe_inputs((1, Ore))]
+    #[recipe_outputs((1, Ingot))]
+    #[recipe_output_scale(2)]
+    #[recipe_ticks(1)]
+    struct Sifting;
+
+    /// Runs a machine for `total` ticks, filling it at the start and again at `refill_at`,
+    /// and updating it after every step of a size picked by `step`.
+    fn run_in_steps<R: RecipeEx>(
+        speed: u64,
+        starvation_limit: Option<u64>,
+        fill: impl Fn(&mut R::Inputs),
+        refill_at: u64,
+        total: u64,
+        mut step: impl FnMut() -> u64,
+    ) -> Machine<R> {
+        let mut tick = Tick::start();
+        tick.set_starvation_limit(starvation_limit);
+        let mut machine = Machine::<R>::with_speed(&tick, speed);
+        fill(&mut machine.inputs(&tick));
+        while tick.cur() < total {
+            let until = if tick.cur() < refill_at {
+                refill_at
+            } else {
+                total
+            };
+            tick.advance_by(step().min(until - tick.cur()));
+            machine.tick(&tick);
+            if tick.cur() == refill_at {
+                fill(&mut machine.inputs(&tick));
+            }
+        }
+        machine
+    }
+
+    #[test]
+    fn describe_shows_buffers_and_progress() {
+        let mut tick = Tick::start();
+        let mut alloying = Machine::<Alloying>::new(&tick);
+        alloying.inputs(&tick).0.amount = 7;
+        alloying.inputs(&tick).1.amount = 4;
+        tick.advance_by(10);
+        assert_eq!(
+            alloying.describe(&tick),
+            "Alloying { inputs: [Ore: 4/3, Ingot: 2/2], outputs: [Ingot: 2/2], progress: 3/7 }"
+        );
+
+        tick.set_starvation_limit(Some(5));
+        let mut smelting = Machine::<Smelting>::new(&tick);
+        tick.advance_by(10);
+        assert_eq!(
+            smelting.describe(&tick),
+            "Smelting { inputs: [Ore: 0/1], outputs: [Ingot: 0/1], progress: 0/3, broken }"
+        );
+    }
+
+    #[test]
+    fn output_scale_carries_fractions() {
+        let mut tick = Tick::start();
+        let mut machine = Machine::<Sifting>::new(&tick);
+        machine.inputs(&tick).0.amount = 1001;
+        // Odd steps, so fractions are carried across updates.
+        let mut outputs = Vec::new();
+        for _ in 0..3 {
+            tick.advance_by(3);
+            outputs.push(machine.outputs(&tick).0.amount());
+        }
+        assert_eq!(outputs, [1, 3, 4]);
+
+        tick.advance_by(2000);
+        assert_eq!(machine.outputs(&tick).0.amount(), 500);
+        assert_eq!(machine.inputs(&tick).0.amount(), 0);
+    }
+
+    #[test]
+    fn half_fed_machine_is_half_utilized() {
+        let mut tick = Tick::start();
+        let mut machine = Machine::<Smelting>::new(&tick);
+        assert_eq!(machine.utilization(&tick), 0.0);
+
+        // Enough ore for half of every 60 ticks, fed in small steps and checked in between.
+        for _ in 0..20 {
+            machine.inputs(&tick).0.amount += 10;
+            for _ in 0..6 {
+                tick.advance_by(10);
+                machine.tick(&tick);
+            }
+        }
+        let utilization = machine.utilization(&tick);
+        assert!((utilization - 0.5).abs() < 0.01, "{utilization}");
+
+        // Long idle stretches are forgotten no matter how they are stepped through.
+        tick.advance_by(100 * UTILIZATION_WINDOW);
+        assert!(machine.utilization(&tick) < 0.01);
+        machine.inputs(&tick).0.amount += 100 * UTILIZATION_WINDOW as u32;
+        tick.advance_by(100 * UTILIZATION_WINDOW);
+        assert!(machine.utilization(&tick) > 0.99);
+    }
+
+    #[test]
+    fn partial_progress_carries_across_steps() {
+        let mut tick = Tick::start();
+        let mut stepped = Machine::<Alloying>::with_speed(&tick, 2);
+        stepped.inputs(&tick).0.amount = 6;
+        stepped.inputs(&tick).1.amount = 4;
+
+        let mut progress = Vec::new();
+        for _ in 0..8 {
+            tick.advance_by(1);
+            stepped.tick(&tick);
+            progress.push(stepped.crafting_progress_ticks());
+        }
+        // A cycle takes 7 ticks of crafting time, and the leftover tick goes towards the next one.
+        // Once the inputs run out, progress resets.
+        assert_eq!(progress, [2, 4, 6, 1, 3, 5, 0, 0]);
+        assert_eq!(stepped.outputs(&tick).0.amount(), 4);
+
+        let mut tick = Tick::start();
+        let mut jumped = Machine::<Alloying>::with_speed(&tick, 2);
+        jumped.inputs(&tick).0.amount = 6;
+        jumped.inputs(&tick).1.amount = 4;
+        tick.advance_by(5);
+        assert_eq!(jumped.progress(&tick), 3.0 / 7.0);
+        assert_eq!(jumped.crafting_progress_ticks(), 3);
+    }
+
+    #[test]
+    fn plan_inputs_follows_recipe_ratios() {
+        resource_type!(Circuit);
+        resource_type!(Science);
+
+        #[derive(Debug, crate::recipe::Recipe, crate::recipe::RecipeEx)]
+        #[recipe_inputs((1, Ingot), (1, Circuit))]
+        #[recipe_outputs((1, Science))]
+        #[recipe_ticks(10)]
+        struct ScienceRecipe;
+
+        let tick = Tick::start();
+        let science = Machine::<ScienceRecipe>::new(&tick);
+        assert_eq!(science.plan_inputs_for(5), [("Ingot", 5), ("Circuit", 5)]);
+        assert_eq!(science.plan_inputs_for(0), [("Ingot", 0), ("Circuit", 0)]);
+
+        // 2 ingots per cycle, so 5 take 3 cycles.
+        let alloying = Machine::<Alloying>::new(&tick);
+        assert_eq!(alloying.plan_inputs_for(5), [("Ore", 9), ("Ingot", 6)]);
+        assert_eq!(alloying.plan_inputs_for(6), [("Ore", 9), ("Ingot", 6)]);
+
+        // Half an ingot per cycle.
+        let sifting = Machine::<Sifting>::new(&tick);
+        assert_eq!(sifting.plan_inputs_for(3), [("Ore", 6)]);
+    }
+
+    #[test]
+    fn try_feed_accepts_up_to_capacity() {
+        let tick = Tick::start();
+        let mut machine = Machine::<Alloying>::new(&tick);
+
+        machine.try_feed::<0>(&tick, resource(4), 6).unwrap();
+        machine.try_feed::<0>(&tick, resource(2), 6).unwrap();
+        assert_eq!(machine.input::<0>(&tick).amount(), 6);
+
+        let err = machine.try_feed::<0>(&tick, resource(1), 6).unwrap_err();
+        assert_eq!(
+            (err.resource.amount(), err.current, err.capacity),
+            (1, 6, 6)
+        );
+        assert_eq!(machine.input::<0>(&tick).amount(), 6);
+
+        // Each buffer is checked on its own.
+        machine.try_feed::<1>(&tick, resource(6), 6).unwrap();
+        assert!(machine.try_feed::<1>(&tick, resource(u32::MAX), 6).is_err());
+    }
+
+    #[test]
+    fn speed_multiplier_applies_from_when_it_is_set() {
+        let mut tick = Tick::start();
+        let mut boosted = Machine::<Smelting>::new(&tick);
+        let mut lazy = Machine::<Smelting>::new(&tick);
+        for machine in [&mut boosted, &mut lazy] {
+            machine.inputs(&tick).0.add(resource(100));
+        }
+
+        tick.advance_by(12);
+        assert_eq!(boosted.outputs(&tick).0.amount(), 4);
+        set_speed_multiplier(&mut tick, 3);
+        assert_eq!(tick.machine_speed_multiplier(), 3);
+        assert_eq!(boosted.ticks_until_next_output(&tick), Some(1));
+        tick.advance_by(12);
+        // 12 ticks at normal speed and 12 at triple speed, however often the machine was looked at.
+        assert_eq!(boosted.outputs(&tick).0.amount(), 16);
+        assert_eq!(lazy.outputs(&tick).0.amount(), 16);
+    }
+
+    #[test]
+    fn input_headroom_shrinks_to_zero_at_capacity() {
+        let mut tick = Tick::start();
+        let mut machine = Machine::<Alloying>::new(&tick);
+        assert_eq!(machine.input_headroom::<0>(&tick, 6), 6);
+
+        machine.input::<0>(&tick).add(resource(4));
+        assert_eq!(machine.input_headroom::<0>(&tick, 6), 2);
+        let headroom = machine.input_headroom::<0>(&tick, 6);
+        machine.try_feed::<0>(&tick, resource(headroom), 6).unwrap();
+        assert_eq!(machine.input_headroom::<0>(&tick, 6), 0);
+        // Overfilled buffers have no headroom rather than negative headroom.
+        machine.input::<0>(&tick).add(resource(3));
+        assert_eq!(machine.input_headroom::<0>(&tick, 6), 0);
+        assert_eq!(machine.input_headroom::<1>(&tick, 6), 6);
+
+        // Consumed inputs free up room again.
+        machine.input::<1>(&tick).add(resource(4));
+        tick.advance_by(100);
+        assert_eq!(machine.input_headroom::<0>(&tick, 6), 3);
+    }
+
+    #[test]
+    fn is_empty_matches_change_recipe() {
+        let mut tick = Tick::start();
+        let mut machine = Machine::<Alloying>::new(&tick);
+        assert!(machine.is_empty(&tick));
+
+        machine.input::<1>(&tick).add(resource(1));
+        assert!(!machine.is_empty(&tick));
+        let mut machine = machine.change_recipe(Alloying).unwrap_err().machine;
+
+        // Crafting moves the inputs to the output, which keeps the machine non-empty.
+        machine.input::<0>(&tick).add(resource(3));
+        machine.input::<1>(&tick).add(resource(1));
+        tick.advance_by(100);
+        assert!(!machine.is_empty(&tick));
+        let _ = machine.output::<0>(&tick).empty();
+        assert!(machine.is_empty(&tick));
+        assert!(machine.change_recipe(Alloying).is_ok());
+    }
+
+    #[test]
+    fn on_cycle_counts_every_cycle() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        let mut tick = Tick::start();
+        let mut machine = Machine::<Smelting>::new(&tick);
+        let cycles = Rc::new(Cell::new(0));
+        let calls = Rc::new(Cell::new(0));
+        machine.on_cycle({
+            let (cycles, calls) = (cycles.clone(), calls.clone());
+            move |count| {
+                cycles.set(cycles.get() + count);
+                calls.set(calls.get() + 1);
+            }
+        });
+        machine.inputs(&tick).0.amount = 10;
+
+        // One cycle at a time.
+        for _ in 0..3 {
+            tick.advance_by(3);
+            machine.tick(&tick);
+        }
+        assert_eq!((cycles.get(), calls.get()), (3, 3));
+
+        // A partial cycle completes nothing and doesn't call back.
+        tick.advance_by(1);
+        machine.tick(&tick);
+        assert_eq!(calls.get(), 3);
+
+        // A long jump is reported once, limited by the inputs.
+        tick.advance_by(1000);
+        machine.tick(&tick);
+        assert_eq!((cycles.get(), calls.get()), (10, 4));
+        assert_eq!(machine.outputs(&tick).0.amount(), 10);
+    }
+
+    #[test]
+    fn step_size_does_not_matter() {
+        // A small deterministic generator, so failures are reproducible.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut random = move |max: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % max
+        };
+        for _ in 0..200 {
+            let speed = 1 + random(3);
+            let starvation_limit = random(2).eq(&1).then(|| random(20));
+            let ore = random(40) as u32;
+            let ingots = random(30) as u32;
+            let total = random(200);
+            let refill_at = random(total + 1);
+            let fill = |inputs: &mut <Alloying as Recipe>::Inputs| {
+                inputs.0.amount += ore;
+                inputs.1.amount += ingots;
+            };
+            let jumped =
+                run_in_steps::<Alloying>(speed, starvation_limit, fill, refill_at, total, || total);
+            let stepped =
+                run_in_steps::<Alloying>(speed, starvation_limit, fill, refill_at, total, || 1);
+            let max_step = 1 + random(10);
+            let mixed =
+                run_in_steps::<Alloying>(speed, starvation_limit, fill, refill_at, total, || {
+                    1 + random(max_step)
+                });
+            for machine in [stepped, mixed] {
+                assert_eq!(
+                    (
+                        machine.inputs.0.amount,
+                        machine.inputs.1.amount,
+                        machine.outputs.0.amount,
+                        machine.crafting_time,
+                        machine.starved_since,
+                        machine.broken,
+                    ),
+                    (
+                        jumped.inputs.0.amount,
+                        jumped.inputs.1.amount,
+                        jumped.outputs.0.amount,
+                        jumped.crafting_time,
+                        jumped.starved_since,
+                        jumped.broken,
+                    ),
+                    "speed {speed}, starvation limit {starvation_limit:?}, {ore} ore, {ingots} ingots, refill at {refill_at}, {total} ticks"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn huge_jump_is_limited_by_inputs() {
+        let mut tick = Tick::start();
+        let mut machine = Machine::<Smelting>::with_speed(&tick, 4);
+        machine.inputs(&tick).0.amount = 10;
+        tick.advance_by(u64::MAX / 2);
+        assert_eq!(machine.outputs(&tick).0.amount(), 10);
+        assert_eq!(machine.inputs(&tick).0.amount(), 0);
+
+        let _ = machine.outputs(&tick).0.empty();
+        machine.inputs(&tick).0.amount = u32::MAX;
+        tick.advance_by(u64::MAX / 2);
+        assert_eq!(machine.outputs(&tick).0.amount(), u32::MAX);
+    }
+
+    #[test]
+    fn full_output_buffer_holds_the_machine_up() {
+        let mut tick = Tick::start();
+        let mut machine = Machine::<Splitting>::new(&tick);
+        machine.outputs(&tick).1.amount = u32::MAX - 5;
+        machine.inputs(&tick).0.amount = 10;
+        tick.advance_by(1000);
+        // Each cycle makes 3 slag, so only one more cycle fits.
+        assert_eq!(machine.outputs(&tick).0.amount(), 2);
+        assert_eq!(machine.outputs(&tick).1.amount(), u32::MAX - 2);
+        assert_eq!(machine.inputs(&tick).0.amount(), 8);
+
+        // Emptying the outputs lets it carry on.
+        let _ = machine.outputs(&tick).1.empty();
+        tick.advance_by(1000);
+        assert_eq!(machine.outputs(&tick).0.amount(), 10);
+        assert_eq!(machine.outputs(&tick).1.amount(), 12);
+    }
 }
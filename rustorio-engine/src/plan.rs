@@ -0,0 +1,426 @@
+//! Working out how many buildings a factory needs, the classic ratio calculation.
+//!
+//! Describe every recipe the factory may use with [`PlanRecipe::of`], along with how fast the building running it crafts,
+//! then ask [`plan`] how many buildings of each recipe it takes to produce a resource at a given rate.
+//! ```
+//! # #![feature(generic_const_exprs)]
+//! # use rustorio_engine::{plan::{PlanRecipe, plan}, recipe::{Recipe, RecipeEx}, resource_type};
+//! # resource_type!(Ore);
+//! # resource_type!(Ingot);
+//! # resource_type!(Gear);
+//! #[derive(Debug, Recipe, RecipeEx)]
+//! #[recipe_inputs((1, Ore))]
+//! #[recipe_outputs((1, Ingot))]
+//! #[recipe_ticks(4)]
+//! struct Smelting;
+//!
+//! #[derive(Debug, Recipe, RecipeEx)]
+//! #[recipe_inputs((2, Ingot))]
+//! #[recipe_outputs((1, Gear))]
+//! #[recipe_ticks(2)]
+//! struct GearRecipe;
+//!
+//! let recipes = [PlanRecipe::of::<Smelting>(1), PlanRecipe::of::<GearRecipe>(1)];
+//! // One gear every 2 ticks needs one gear building and 4 furnaces, fed one ore per tick.
+//! let plan = plan(&recipes, "Gear", 0.5).unwrap();
+//! assert_eq!(plan.buildings["GearRecipe"], 1.0);
+//! assert_eq!(plan.buildings["Smelting"], 4.0);
+//! assert_eq!(plan.raw["Ore"], 1.0);
+//! ```
+//!
+//! Building counts are fractional. Round them up to get a factory that keeps up.
+//!
+//! To compare strategies without playing them out, [`simulate`] projects how long a given set of buildings takes to produce an amount of a resource.
+//!
+//! To compare stockpiles of raw and processed resources, [`score_equivalent`] values them all in processed resources.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::fmt::{self, Display};
+
+use crate::{
+    recipe::{RecipeEx, Rounding, convert_estimate_wide},
+    stats::short_type_name,
+};
+
+/// What [`plan`] needs to know about a recipe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanRecipe {
+    name: String,
+    /// Ticks per cycle, divided by the speed of the building running the recipe.
+    ticks_per_cycle: f64,
+    output_scale: u32,
+    inputs: Vec<(&'static str, u32)>,
+    outputs: Vec<(&'static str, u32)>,
+}
+
+impl PlanRecipe {
+    /// Describes recipe `R`, run in buildings that craft `speed` times as fast as a normal machine.
+    pub fn of<R: RecipeEx>(speed: u64) -> Self {
+        Self {
+            name: short_type_name::<R>(),
+            ticks_per_cycle: R::TIME as f64 / speed as f64,
+            output_scale: R::OUTPUT_SCALE,
+            inputs: R::iter_inputs(&mut R::new_inputs())
+                .map(|(name, amount, _)| (name, amount))
+                .collect(),
+            outputs: R::iter_outputs(&mut R::new_outputs())
+                .map(|(name, amount, _)| (name, amount))
+                .collect(),
+        }
+    }
+
+    /// The name of the recipe, without module paths, e.g. `IronSmelting`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// How much of `resource` one cycle produces, if any.
+    fn output_per_cycle(&self, resource: &str) -> Option<f64> {
+        self.outputs
+            .iter()
+            .find(|&&(name, _)| name == resource)
+            .map(|&(_, amount)| f64::from(amount) / f64::from(self.output_scale))
+    }
+}
+
+/// The buildings and raw resources it takes to sustain a production rate, as worked out by [`plan`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Plan {
+    /// How many buildings each recipe needs, by recipe [name](PlanRecipe::name).
+    pub buildings: BTreeMap<String, f64>,
+    /// How much of each resource no recipe produces, like ore, is needed per tick.
+    pub raw: BTreeMap<&'static str, f64>,
+}
+
+/// Error returned by [`plan`] when a resource is needed to produce itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanCycleError {
+    /// The resources in the cycle, starting and ending with the same one.
+    pub cycle: Vec<&'static str>,
+}
+
+impl Display for PlanCycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Resources needed to produce themselves: ")?;
+        for (i, resource) in self.cycle.iter().enumerate() {
+            let separator = if i == 0 { "" } else { " -> " };
+            write!(f, "{separator}{resource}")?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for PlanCycleError {}
+
+/// Works out how many buildings of each of `recipes` it takes to produce `resource` at `per_tick` per tick,
+/// following the inputs of each recipe down to the resources no recipe produces.
+///
+/// Each resource is produced by the first recipe in `recipes` that outputs it.
+/// Other outputs of that recipe are treated as surplus, and don't reduce what other recipes have to make.
+pub fn plan(
+    recipes: &[PlanRecipe],
+    resource: &'static str,
+    per_tick: f64,
+) -> Result<Plan, PlanCycleError> {
+    let mut plan = Plan::default();
+    let mut path = Vec::new();
+    demand(recipes, resource, per_tick, &mut path, &mut plan)?;
+    Ok(plan)
+}
+
+/// Adds what it takes to produce `resource` at `per_tick` to `plan`.
+/// `path` holds the resources currently being produced, to catch cycles.
+fn demand(
+    recipes: &[PlanRecipe],
+    resource: &'static str,
+    per_tick: f64,
+    path: &mut Vec<&'static str>,
+    plan: &mut Plan,
+) -> Result<(), PlanCycleError> {
+    if let Some(start) = path.iter().position(|&needed| needed == resource) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(resource);
+        return Err(PlanCycleError { cycle });
+    }
+    let Some((recipe, per_cycle)) = recipes.iter().find_map(|recipe| {
+        recipe
+            .output_per_cycle(resource)
+            .map(|per_cycle| (recipe, per_cycle))
+    }) else {
+        *plan.raw.entry(resource).or_default() += per_tick;
+        return Ok(());
+    };
+
+    let cycles_per_tick = per_tick / per_cycle;
+    *plan.buildings.entry(recipe.name.clone()).or_default() +=
+        cycles_per_tick * recipe.ticks_per_cycle;
+    path.push(resource);
+    for &(input, amount) in &recipe.inputs {
+        demand(
+            recipes,
+            input,
+            cycles_per_tick * f64::from(amount),
+            path,
+            plan,
+        )?;
+    }
+    path.pop();
+    Ok(())
+}
+
+/// A factory for [`simulate`] to project: how many buildings run each recipe, and how much of each raw resource comes in.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Strategy {
+    /// How many buildings run each recipe, by recipe [name](PlanRecipe::name).
+    pub buildings: BTreeMap<String, u32>,
+    /// How much of each resource no recipe produces, like ore, comes in per tick.
+    pub raw: BTreeMap<&'static str, f64>,
+}
+
+/// How a [`Strategy`] is projected to do, as worked out by [`simulate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection {
+    /// How much of the resource the factory makes per tick once every building is busy.
+    pub per_tick: f64,
+    /// The recipe or raw resource that limits `per_tick`, by name.
+    pub bottleneck: String,
+    /// How many ticks it takes to make the amount asked for, or `None` if the factory never makes any.
+    pub ticks: Option<u64>,
+}
+
+/// Projects how many ticks the factory described by `strategy` takes to produce `amount` of `resource`,
+/// without running it or touching any real resources.
+///
+/// The factory is assumed to run at the rate of its [bottleneck](Projection::bottleneck) from the start,
+/// after waiting one cycle of each recipe along the longest chain for the first output to come through.
+/// This is an estimate, not a bound: for a simple chain whose inputs are moved along as soon as they are made,
+/// the tests check that a real run lands within 10% of it.
+/// A real run can take longer when buildings are fed less often, or less time when they start with inputs already buffered.
+///
+/// Recipes are picked as in [`plan`], whose errors are passed on.
+pub fn simulate(
+    recipes: &[PlanRecipe],
+    strategy: &Strategy,
+    resource: &'static str,
+    amount: u32,
+) -> Result<Projection, PlanCycleError> {
+    let needed = plan(recipes, resource, 1.0)?;
+    let limits = needed
+        .buildings
+        .iter()
+        .map(|(name, &per_unit)| {
+            let count = strategy.buildings.get(name).copied().unwrap_or(0);
+            (name.clone(), f64::from(count) / per_unit)
+        })
+        .chain(needed.raw.iter().map(|(&name, &per_unit)| {
+            let supply = strategy.raw.get(name).copied().unwrap_or(0.0);
+            (String::from(name), supply / per_unit)
+        }));
+    let (bottleneck, per_tick) = limits
+        .reduce(|lowest, limit| if limit.1 < lowest.1 { limit } else { lowest })
+        .unwrap_or_else(|| (String::from(resource), 0.0));
+    let ticks = (per_tick > 0.0).then(|| {
+        let ticks = latency(recipes, resource) + f64::from(amount) / per_tick;
+        let whole = ticks as u64;
+        if (whole as f64) < ticks {
+            whole + 1
+        } else {
+            whole
+        }
+    });
+    Ok(Projection {
+        per_tick,
+        bottleneck,
+        ticks,
+    })
+}
+
+/// How many ticks the first `resource` takes to come out of a factory that starts empty,
+/// one cycle of each recipe along the longest chain of inputs.
+/// `recipes` must not have cycles, which [`plan`] checks first.
+fn latency(recipes: &[PlanRecipe], resource: &str) -> f64 {
+    let Some(recipe) = recipes
+        .iter()
+        .find(|recipe| recipe.output_per_cycle(resource).is_some())
+    else {
+        return 0.0;
+    };
+    let slowest_input = recipe
+        .inputs
+        .iter()
+        .map(|&(input, _)| latency(recipes, input))
+        .fold(0.0, f64::max);
+    recipe.ticks_per_cycle + slowest_input
+}
+
+/// How many of the first output of recipe `R` a stockpile of `ore`, its first input, and `ingots`, its first output, is worth.
+///
+/// The ore is converted by the recipe's ratio, rounded as `rounding` says.
+/// Unlike `ore * OUT / IN + ingots` in `u32`, this can't overflow,
+/// and [`Rounding::Nearest`] keeps strategies that differ by a fraction of a cycle from ranking the same.
+/// ```
+/// # #![feature(generic_const_exprs)]
+/// # use rustorio_engine::{plan::score_equivalent, recipe::{Recipe, RecipeEx, Rounding}, resource_type};
+/// # resource_type!(Ore);
+/// # resource_type!(Ingot);
+/// #[derive(Debug, Recipe, RecipeEx)]
+/// #[recipe_inputs((3, Ore))]
+/// #[recipe_outputs((2, Ingot))]
+/// #[recipe_ticks(5)]
+/// struct Smelting;
+///
+/// // 4 ore is 2⅔ ingots.
+/// assert_eq!(score_equivalent::<Smelting>(4, 10, Rounding::Floor), 12);
+/// assert_eq!(score_equivalent::<Smelting>(4, 10, Rounding::Nearest), 13);
+/// ```
+pub fn score_equivalent<R: RecipeEx>(ore: u32, ingots: u32, rounding: Rounding) -> u64 {
+    convert_estimate_wide::<R>(ore, rounding) + u64::from(ingots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{machine::Machine, recipe::Recipe, resource_type, resources::resource, tick::Tick};
+
+    resource_type!(Ore);
+    resource_type!(Ingot);
+    resource_type!(Scrap);
+    resource_type!(Gear);
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((1, Ore))]
+    #[recipe_outputs((1, Ingot))]
+    #[recipe_ticks(4)]
+    struct Smelting;
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((3, Ore))]
+    #[recipe_outputs((5, Ingot))]
+    #[recipe_ticks(1)]
+    struct Enriching;
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((2, Ingot))]
+    #[recipe_outputs((1, Gear))]
+    #[recipe_ticks(2)]
+    struct GearRecipe;
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((1, Ingot))]
+    #[recipe_outputs((1, Scrap))]
+    #[recipe_ticks(1)]
+    struct Shredding;
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((1, Scrap))]
+    #[recipe_outputs((1, Ingot))]
+    #[recipe_output_scale(2)]
+    #[recipe_ticks(1)]
+    struct Remelting;
+
+    #[test]
+    fn speed_and_output_scale_change_building_counts() {
+        let fast = plan(&[PlanRecipe::of::<Smelting>(2)], "Ingot", 1.0).unwrap();
+        assert_eq!(fast.buildings["Smelting"], 2.0);
+
+        let scaled = plan(
+            &[
+                PlanRecipe::of::<Remelting>(1),
+                PlanRecipe::of::<Smelting>(1),
+            ],
+            "Ingot",
+            1.0,
+        )
+        .unwrap();
+        // Remelting is listed first, so it is used, making half an ingot per scrap.
+        assert_eq!(scaled.buildings["Remelting"], 2.0);
+        assert!(!scaled.buildings.contains_key("Smelting"));
+        assert_eq!(scaled.raw["Scrap"], 2.0);
+    }
+
+    #[test]
+    fn score_equivalent_is_exact_where_truncation_is_not() {
+        // 3 ore make 5 ingots, so 4 ore are worth 6⅔ ingots.
+        let truncated = |ore: u32, ingots: u32| ore * 5 / 3 + ingots;
+        assert_eq!(truncated(4, 1), 7);
+        assert_eq!(score_equivalent::<Enriching>(4, 1, Rounding::Floor), 7);
+        assert_eq!(score_equivalent::<Enriching>(4, 1, Rounding::Nearest), 8);
+        assert_eq!(score_equivalent::<Enriching>(4, 1, Rounding::Cycles), 6);
+        // Truncation ranks 4 ore and 1 ingot the same as 2 ore and 4 ingots, which is worth 7⅓.
+        assert_eq!(truncated(2, 4), truncated(4, 1));
+        assert!(
+            score_equivalent::<Enriching>(4, 1, Rounding::Nearest)
+                > score_equivalent::<Enriching>(2, 4, Rounding::Nearest)
+        );
+
+        assert_eq!(
+            score_equivalent::<Enriching>(u32::MAX, u32::MAX, Rounding::Floor),
+            u64::from(u32::MAX) * 5 / 3 + u64::from(u32::MAX)
+        );
+        assert!(u32::MAX.checked_mul(5).is_none());
+    }
+
+    #[test]
+    fn projection_is_close_to_a_real_run() {
+        let recipes = [
+            PlanRecipe::of::<Smelting>(1),
+            PlanRecipe::of::<GearRecipe>(1),
+        ];
+        let strategy = Strategy {
+            buildings: [
+                (String::from("Smelting"), 4),
+                (String::from("GearRecipe"), 1),
+            ]
+            .into(),
+            raw: [("Ore", 1.0)].into(),
+        };
+        let projection = simulate(&recipes, &strategy, "Gear", 50).unwrap();
+        // The furnaces, the gear machine and the ore all allow exactly half a gear per tick.
+        assert_eq!(projection.per_tick, 0.5);
+        assert_eq!(projection.bottleneck, "GearRecipe");
+        let projected = projection.ticks.unwrap();
+        assert_eq!(projected, 6 + 100);
+
+        // The same factory for real, fed an ore every tick, with ingots moved along as soon as they are made.
+        let mut tick = Tick::start();
+        let mut furnaces: Vec<Machine<Smelting>> = (0..4).map(|_| Machine::new(&tick)).collect();
+        let mut gears = Machine::<GearRecipe>::new(&tick);
+        let mut next_furnace = 0;
+        while gears.output::<0>(&tick).amount() < 50 {
+            furnaces[next_furnace].input::<0>(&tick).add(resource(1));
+            next_furnace = (next_furnace + 1) % furnaces.len();
+            for furnace in &mut furnaces {
+                let ingots = furnace.output::<0>(&tick).empty();
+                gears.input::<0>(&tick).add(ingots);
+            }
+            tick.advance();
+        }
+        let actual = tick.cur();
+        assert!(
+            actual.abs_diff(projected) <= projected / 10,
+            "Projected {projected} ticks, took {actual}"
+        );
+
+        // Without a gear machine, no gears are ever made.
+        let mut no_gears = strategy.clone();
+        no_gears.buildings.remove("GearRecipe");
+        let projection = simulate(&recipes, &no_gears, "Gear", 50).unwrap();
+        assert_eq!(projection.per_tick, 0.0);
+        assert_eq!(projection.bottleneck, "GearRecipe");
+        assert_eq!(projection.ticks, None);
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let recipes = [
+            PlanRecipe::of::<Remelting>(1),
+            PlanRecipe::of::<Shredding>(1),
+        ];
+        let err = plan(&recipes, "Ingot", 1.0).unwrap_err();
+        assert_eq!(err.cycle, ["Ingot", "Scrap", "Ingot"]);
+        assert_eq!(
+            err.to_string(),
+            "Resources needed to produce themselves: Ingot -> Scrap -> Ingot"
+        );
+    }
+}
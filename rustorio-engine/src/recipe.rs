@@ -1,8 +1,20 @@
 //! Recipes define all item transformations in the game via input items, output items, and time.
 
+use alloc::{string::ToString, vec::Vec};
+use core::{
+    fmt::{self, Display},
+    marker::PhantomData,
+};
+
 pub use rustorio_derive::{Recipe, RecipeEx, recipe_doc};
 
-use crate::{Sealed, tick::Tick};
+use crate::{
+    Sealed,
+    events::AdvanceCause,
+    replay::ActionKind,
+    resources::{Resource, ResourceType},
+    tick::Tick,
+};
 
 /// Basic recipe trait. A building's specific recipe trait can then be defined like
 /// ```rust
@@ -39,11 +51,11 @@ pub trait Recipe {
 
     /// Typically a tuple of multiple `RecipeTypes`, to define the inputs
     /// for one cycle of the recipe.
-    type Inputs: std::fmt::Debug;
+    type Inputs: core::fmt::Debug;
 
     /// Typically a tuple of multiple `RecipeTypes`, to define the outputs
     /// for one cycle of the recipe.
-    type Outputs: std::fmt::Debug;
+    type Outputs: core::fmt::Debug;
 
     /// Factory function to create a new `Self::Inputs` with zero resources.
     fn new_inputs() -> Self::Inputs;
@@ -53,48 +65,590 @@ pub trait Recipe {
 
     /// The type for `Self::InputAmountsType`, which is used to allow users to
     /// access the input amount for each of the input resource types, per recipe cycle.
-    type InputAmountsType: std::fmt::Debug;
+    type InputAmountsType: core::fmt::Debug;
 
     /// Amount for each of the input resource types, per recipe cycle.
     const INPUT_AMOUNTS: Self::InputAmountsType;
 
+    /// Number of input resource types, which is the length of the `Self::Inputs` tuple.
+    const INPUT_COUNT: usize;
+
     /// The type for `Self::OuptutAmountsType`, which is used to allow users to
     /// access the output amount for each of the output resource types, per recipe cycle.
-    type OutputAmountsType: std::fmt::Debug;
+    type OutputAmountsType: core::fmt::Debug;
 
     /// Amount for each of the output resource types, per recipe cycle.
     const OUTPUT_AMOUNTS: Self::OutputAmountsType;
+
+    /// Number of output resource types, which is the length of the `Self::Outputs` tuple.
+    const OUTPUT_COUNT: usize;
+
+    /// What [`OUTPUT_AMOUNTS`](Recipe::OUTPUT_AMOUNTS) are divided by, for recipes that produce less than one item per cycle.
+    /// Machines carry the fractions over to later cycles, so a recipe with an output of 1 and a scale of 2 produces one item every two cycles.
+    /// Set it with `#[recipe_output_scale(2)]` when deriving [`Recipe`]. Must be positive.
+    const OUTPUT_SCALE: u32 = 1;
+
+    /// Amount of the `i`th input consumed per recipe cycle, counting inputs in the order they are declared,
+    /// or `None` if the recipe has no `i`th input.
+    ///
+    /// Prefer this over [`INPUT_AMOUNTS`](Recipe::INPUT_AMOUNTS), whose representation may change.
+    fn input_amount(i: usize) -> Option<u32>;
+
+    /// Amount of the `i`th output produced per recipe cycle, counting outputs in the order they are declared,
+    /// or `None` if the recipe has no `i`th output.
+    /// For recipes with an [`OUTPUT_SCALE`](Recipe::OUTPUT_SCALE), this is before dividing by the scale.
+    ///
+    /// Prefer this over [`OUTPUT_AMOUNTS`](Recipe::OUTPUT_AMOUNTS), whose representation may change.
+    fn output_amount(i: usize) -> Option<u32>;
+
+    /// Amount of ticks one cycle of the recipe takes to complete, the same as [`TIME`](Recipe::TIME).
+    fn time() -> u64 {
+        Self::TIME
+    }
 }
 
 #[doc(hidden)]
 pub trait RecipeEx: Recipe {
+    /// The name of the recipe, e.g. `IronSmelting`.
+    /// Derived recipes are named after their type, so tools and replays don't depend on module paths.
+    const NAME: &'static str;
+
     /// A type guaranteed to contain exactly the input resources for one recipe cycle.
     /// Used in handcrafting.
-    type InputBundle: std::fmt::Debug;
+    type InputBundle: core::fmt::Debug;
     /// A type guaranteed to contain exactly the output resources for one recipe cycle.
     /// Used in handcrafting.
-    type OutputBundle: std::fmt::Debug;
+    type OutputBundle: core::fmt::Debug;
 
     /// Factory function to create a new `Self::InputBundle`.
     fn new_output_bundle() -> Self::OutputBundle;
 
     /// Iterator helper over `Self::Inputs`.
+    ///
+    /// Yields one `(name, amount per cycle, current amount)` entry per buffer, in the order the inputs are declared,
+    /// which is also the order of the `Self::Inputs` tuple and of [`INPUT_AMOUNTS`](Recipe::INPUT_AMOUNTS).
+    /// [`Machine`](crate::machine::Machine) relies on this to take the right amount out of each buffer.
     fn iter_inputs(items: &mut Self::Inputs)
     -> impl Iterator<Item = (&'static str, u32, &mut u32)>;
 
     /// Iterator helper over `Self::Outputs`.
+    ///
+    /// Ordered like [`iter_inputs`](RecipeEx::iter_inputs), following the declaration of the outputs.
     fn iter_outputs(
         items: &mut Self::Outputs,
     ) -> impl Iterator<Item = (&'static str, u32, &mut u32)>;
+
+    /// Estimates how much of the first output `input_amount` of the first input is worth, assuming every other input is plentiful.
+    /// Recipes without inputs or outputs are estimated at 0.
+    ///
+    /// ```
+    /// # #![feature(generic_const_exprs)]
+    /// # use rustorio_engine::{recipe::{Recipe, RecipeEx, Rounding}, resource_type};
+    /// # resource_type!(Ore);
+    /// # resource_type!(Ingot);
+    /// #[derive(Debug, Recipe, RecipeEx)]
+    /// #[recipe_inputs((3, Ore))]
+    /// #[recipe_outputs((4, Ingot))]
+    /// #[recipe_ticks(5)]
+    /// struct Smelting;
+    ///
+    /// // 5 ore is one full cycle with 2 ore left over, or 6⅔ ingots by ratio.
+    /// assert_eq!(Smelting::convert_estimate(5, Rounding::Cycles), 4);
+    /// assert_eq!(Smelting::convert_estimate(5, Rounding::Floor), 6);
+    /// assert_eq!(Smelting::convert_estimate(5, Rounding::Nearest), 7);
+    /// ```
+    fn convert_estimate(input_amount: u32, rounding: Rounding) -> u32 {
+        u32::try_from(convert_estimate_wide::<Self>(input_amount, rounding)).unwrap_or(u32::MAX)
+    }
+}
+
+/// Bundles holding the inputs and outputs of `N` recipe cycles at once, used by [`HandRecipe::craft_n`].
+/// Should only be implemented via the `#[derive(RecipeEx)]` macro,
+/// whose implementation computes the amounts with `generic_const_exprs`, so crates deriving `RecipeEx` need that feature.
+#[doc(hidden)]
+pub trait RecipeBundles<const N: u32> {
+    /// Like [`RecipeEx::InputBundle`], with `N` times the amount of each input.
+    type InputBundles: core::fmt::Debug;
+    /// Like [`RecipeEx::OutputBundle`], with `N` times the amount of each output.
+    type OutputBundles: core::fmt::Debug;
+
+    /// Factory function to create a new `Self::OutputBundles`.
+    fn new_output_bundles() -> Self::OutputBundles;
+}
+
+/// [`RecipeEx::convert_estimate`] without saturating to `u32`.
+/// Computed in `u128`, so it can't overflow.
+pub(crate) fn convert_estimate_wide<R: RecipeEx + ?Sized>(
+    input_amount: u32,
+    rounding: Rounding,
+) -> u64 {
+    let Some((_, per_input, _)) = R::iter_inputs(&mut R::new_inputs()).next() else {
+        return 0;
+    };
+    let Some((_, per_output, _)) = R::iter_outputs(&mut R::new_outputs()).next() else {
+        return 0;
+    };
+    if R::OUTPUT_SCALE == 0 {
+        return 0;
+    }
+    let (input_amount, per_input, per_output, scale) = (
+        u128::from(input_amount),
+        u128::from(per_input),
+        u128::from(per_output),
+        u128::from(R::OUTPUT_SCALE),
+    );
+    let Some(cycles) = input_amount.checked_div(per_input) else {
+        return 0;
+    };
+    let per_input = per_input * scale;
+    let estimate = match rounding {
+        Rounding::Cycles => cycles * per_output / scale,
+        Rounding::Floor => input_amount * per_output / per_input,
+        Rounding::Nearest => (2 * input_amount * per_output + per_input) / (2 * per_input),
+    };
+    // At most `u32::MAX * u32::MAX`, which fits.
+    estimate as u64
+}
+
+/// How [`RecipeEx::convert_estimate`] rounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Only count whole recipe cycles, which is what a machine actually produces.
+    Cycles,
+    /// Scale by the recipe's ratio and round down.
+    Floor,
+    /// Scale by the recipe's ratio and round to the nearest amount, with halves rounded up.
+    Nearest,
+}
+
+/// Fails to compile when `R` has an [`OUTPUT_SCALE`](Recipe::OUTPUT_SCALE), as soon as `CHECK` is used.
+struct Unscaled<R: ?Sized>(PhantomData<R>);
+
+impl<R: Recipe + ?Sized> Unscaled<R> {
+    const CHECK: () = assert!(
+        R::OUTPUT_SCALE == 1,
+        "Recipes with an output scale can't be hand-crafted"
+    );
 }
 
 /// A recipe that can be hand-crafted by the player.
-pub trait HandRecipe: std::fmt::Debug + Sealed + RecipeEx {
+pub trait HandRecipe: core::fmt::Debug + Sealed + RecipeEx {
     /// Crafts the recipe by consuming the input bundle and producing the output bundle.
     /// Advances the provided `Tick` by the recipe's time.
+    ///
+    /// A single craft can't produce a fraction of an item, so recipes with an [`OUTPUT_SCALE`](Recipe::OUTPUT_SCALE) can't be hand-crafted:
+    /// ```compile_fail,E0080
+    /// # #![feature(generic_const_exprs)]
+    /// # use rustorio_engine::{Sealed, mod_reexports::{Bundle, Tick}, recipe::{HandRecipe, Recipe, RecipeEx}, resource_type};
+    /// # resource_type!(Copper);
+    /// # resource_type!(CopperWire);
+    /// #[derive(Debug, Recipe, RecipeEx)]
+    /// #[recipe_inputs((1, Copper))]
+    /// #[recipe_outputs((1, CopperWire))]
+    /// #[recipe_output_scale(2)]
+    /// #[recipe_ticks(1)]
+    /// struct HalfWireRecipe;
+    /// # impl Sealed for HalfWireRecipe {}
+    /// impl HandRecipe for HalfWireRecipe {}
+    ///
+    /// fn craft_wire(tick: &mut Tick, copper: Bundle<Copper, 1>) -> Bundle<CopperWire, 1> {
+    ///     HalfWireRecipe::craft(tick, (copper,)).0
+    /// }
+    /// # let _: fn(&mut Tick, Bundle<Copper, 1>) -> Bundle<CopperWire, 1> = craft_wire;
+    /// ```
     fn craft(tick: &mut Tick, inputs: Self::InputBundle) -> Self::OutputBundle {
+        let () = Unscaled::<Self>::CHECK;
         let _ = inputs;
-        tick.advance_by(Self::TIME);
+        hand_craft::<Self>(tick, 1);
         Self::new_output_bundle()
     }
+
+    /// [Crafts](HandRecipe::craft) the recipe `N` times in a row, taking bundles with `N` times the inputs of one cycle
+    /// and returning bundles with `N` times its outputs.
+    /// Advances the provided `Tick` by `N` times the recipe's time.
+    ///
+    /// ```
+    /// # #![feature(generic_const_exprs)]
+    /// # use rustorio_engine::{Sealed, mod_reexports::{Bundle, Tick}, recipe::{HandRecipe, Recipe, RecipeEx}, resource_type};
+    /// # resource_type!(Copper);
+    /// # resource_type!(CopperWire);
+    /// #[derive(Debug, Recipe, RecipeEx)]
+    /// #[recipe_inputs((1, Copper))]
+    /// #[recipe_outputs((2, CopperWire))]
+    /// #[recipe_ticks(1)]
+    /// struct CopperWireRecipe;
+    /// # impl Sealed for CopperWireRecipe {}
+    /// impl HandRecipe for CopperWireRecipe {}
+    ///
+    /// fn craft_wire(tick: &mut Tick, copper: Bundle<Copper, 5>) -> Bundle<CopperWire, 10> {
+    ///     CopperWireRecipe::craft_n::<5>(tick, (copper,)).0
+    /// }
+    /// # let _: fn(&mut Tick, Bundle<Copper, 5>) -> Bundle<CopperWire, 10> = craft_wire;
+    /// ```
+    ///
+    /// Bundles that aren't exactly `N` cycles' worth don't compile:
+    /// ```compile_fail,E0308
+    /// # #![feature(generic_const_exprs)]
+    /// # use rustorio_engine::{Sealed, mod_reexports::{Bundle, Tick}, recipe::{HandRecipe, Recipe, RecipeEx}, resource_type};
+    /// # resource_type!(Copper);
+    /// # resource_type!(CopperWire);
+    /// # #[derive(Debug, Recipe, RecipeEx)]
+    /// # #[recipe_inputs((1, Copper))]
+    /// # #[recipe_outputs((2, CopperWire))]
+    /// # #[recipe_ticks(1)]
+    /// # struct CopperWireRecipe;
+    /// # impl Sealed for CopperWireRecipe {}
+    /// # impl HandRecipe for CopperWireRecipe {}
+    /// fn craft_wire(tick: &mut Tick, copper: Bundle<Copper, 4>) -> Bundle<CopperWire, 10> {
+    ///     CopperWireRecipe::craft_n::<5>(tick, (copper,)).0
+    /// }
+    /// # let _: fn(&mut Tick, Bundle<Copper, 4>) -> Bundle<CopperWire, 10> = craft_wire;
+    /// ```
+    fn craft_n<const N: u32>(
+        tick: &mut Tick,
+        inputs: <Self as RecipeBundles<N>>::InputBundles,
+    ) -> <Self as RecipeBundles<N>>::OutputBundles
+    where
+        Self: RecipeBundles<N>,
+    {
+        let () = Unscaled::<Self>::CHECK;
+        let _ = inputs;
+        hand_craft::<Self>(tick, N);
+        Self::new_output_bundles()
+    }
+}
+
+/// Records and accounts for `cycles` hand-crafted cycles of `R`, one after the other, advancing `tick` past them.
+fn hand_craft<R: HandRecipe + ?Sized>(tick: &mut Tick, cycles: u32) {
+    for _ in 0..cycles {
+        tick.record(
+            tick.cur(),
+            ActionKind::Craft {
+                recipe: R::NAME.to_string(),
+            },
+        );
+        tick.with_stats(|stats| {
+            for (resource, needed, _) in R::iter_inputs(&mut R::new_inputs()) {
+                stats.consumed(resource, needed);
+            }
+            for (resource, needed, _) in R::iter_outputs(&mut R::new_outputs()) {
+                stats.produced(resource, needed);
+            }
+        });
+        #[cfg(feature = "debug_conservation")]
+        tick.with_ledger(|ledger| {
+            for (resource, needed, _) in R::iter_inputs(&mut R::new_inputs()) {
+                ledger.destroyed(resource, needed);
+            }
+            for (resource, needed, _) in R::iter_outputs(&mut R::new_outputs()) {
+                ledger.created(resource, needed);
+            }
+        });
+        tick.advance_because(R::TIME, AdvanceCause::HandCraft);
+    }
+}
+
+/// The `I`th resource buffer of a recipe's [`Inputs`](Recipe::Inputs) or [`Outputs`](Recipe::Outputs).
+///
+/// Tuple fields can't be picked by a generic parameter, so generic code uses this to pick a buffer by index instead,
+/// e.g. `<R::Inputs as Buffer<0>>::buffer(inputs)` for the first input of any recipe `R`.
+pub trait Buffer<const I: usize> {
+    /// The resource held in the buffer.
+    type Content: ResourceType;
+
+    /// Access the buffer.
+    fn buffer(&mut self) -> &mut Resource<Self::Content>;
+}
+
+macro_rules! tuple_buffer {
+    ($index:tt => $content:ident in ($($name:ident),+)) => {
+        impl<$($name: ResourceType),+> Buffer<$index> for ($(Resource<$name>,)+) {
+            type Content = $content;
+
+            fn buffer(&mut self) -> &mut Resource<$content> {
+                &mut self.$index
+            }
+        }
+    };
+}
+
+tuple_buffer!(0 => A in (A));
+tuple_buffer!(0 => A in (A, B));
+tuple_buffer!(1 => B in (A, B));
+tuple_buffer!(0 => A in (A, B, C));
+tuple_buffer!(1 => B in (A, B, C));
+tuple_buffer!(2 => C in (A, B, C));
+tuple_buffer!(0 => A in (A, B, C, D));
+tuple_buffer!(1 => B in (A, B, C, D));
+tuple_buffer!(2 => C in (A, B, C, D));
+tuple_buffer!(3 => D in (A, B, C, D));
+
+/// Fails to compile unless input `I` of recipe `R` is the resource `T`, e.g. to make sure ratio math
+/// written for one recipe still refers to the right inputs if the recipe changes.
+/// Does nothing at runtime.
+///
+/// ```
+/// # use rustorio_engine::{recipe::{Recipe, assert_input_type, assert_output_type}, resource_type};
+/// # resource_type!(Iron);
+/// # resource_type!(Circuit);
+/// # resource_type!(RedScience);
+/// #[derive(Recipe)]
+/// #[recipe_inputs((1, Iron), (1, Circuit))]
+/// #[recipe_outputs((1, RedScience))]
+/// #[recipe_ticks(10)]
+/// struct RedScienceRecipe;
+///
+/// const _: () = assert_input_type::<RedScienceRecipe, 1, Circuit>();
+/// const _: () = assert_output_type::<RedScienceRecipe, 0, RedScience>();
+/// ```
+/// ```compile_fail,E0271
+/// # use rustorio_engine::{recipe::{Recipe, assert_input_type}, resource_type};
+/// # resource_type!(Iron);
+/// # resource_type!(Circuit);
+/// # resource_type!(RedScience);
+/// # #[derive(Recipe)]
+/// # #[recipe_inputs((1, Iron), (1, Circuit))]
+/// # #[recipe_outputs((1, RedScience))]
+/// # #[recipe_ticks(10)]
+/// # struct RedScienceRecipe;
+/// const _: () = assert_input_type::<RedScienceRecipe, 0, Circuit>();
+/// ```
+pub const fn assert_input_type<R: Recipe, const I: usize, T: ResourceType>()
+where
+    R::Inputs: Buffer<I, Content = T>,
+{
+}
+
+/// Fails to compile unless output `I` of recipe `R` is the resource `T`.
+/// See [`assert_input_type`].
+pub const fn assert_output_type<R: Recipe, const I: usize, T: ResourceType>()
+where
+    R::Outputs: Buffer<I, Content = T>,
+{
+}
+
+/// The resources a recipe turns into which, by [name](ResourceType::NAME), for reasoning about a set of recipes as a whole.
+/// Used by [`validate_victory`](crate::gamemodes::validate_victory).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipeFlow {
+    /// The [name](RecipeEx::NAME) of the recipe.
+    pub recipe: &'static str,
+    /// The resources the recipe consumes.
+    pub inputs: Vec<&'static str>,
+    /// The resources the recipe produces.
+    pub outputs: Vec<&'static str>,
+}
+
+impl RecipeFlow {
+    /// The flow of recipe `R`.
+    pub fn of<R: RecipeEx>() -> Self {
+        Self {
+            recipe: R::NAME,
+            inputs: R::iter_inputs(&mut R::new_inputs())
+                .map(|(name, _, _)| name)
+                .collect(),
+            outputs: R::iter_outputs(&mut R::new_outputs())
+                .map(|(name, _, _)| name)
+                .collect(),
+        }
+    }
+}
+
+/// A balance invariant broken by a recipe, as reported by [`validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecipeInvariant {
+    /// The recipe takes no time, so a machine could run it infinitely often per tick.
+    NonZeroTime,
+    /// The recipe's [`OUTPUT_SCALE`](Recipe::OUTPUT_SCALE) is zero, so its outputs can't be divided by it.
+    NonZeroOutputScale,
+    /// The recipe lists a resource with an amount of zero.
+    PositiveAmount {
+        /// The name of the offending resource.
+        resource: &'static str,
+    },
+    /// The recipe consumes nothing, so it generates resources for free.
+    /// Expected for deliberate generators, which can ignore this violation.
+    HasInputs,
+    /// The recipe produces nothing.
+    HasOutputs,
+}
+
+impl Display for RecipeInvariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonZeroTime => write!(f, "recipe takes zero ticks"),
+            Self::NonZeroOutputScale => write!(f, "output scale is zero"),
+            Self::PositiveAmount { resource } => write!(f, "amount of {resource} is zero"),
+            Self::HasInputs => write!(f, "recipe has no inputs"),
+            Self::HasOutputs => write!(f, "recipe has no outputs"),
+        }
+    }
+}
+
+/// Returned by [`validate`] when a recipe breaks one or more balance invariants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipeValidationError {
+    /// The [name](RecipeEx::NAME) of the recipe.
+    pub recipe: &'static str,
+    /// Every invariant the recipe broke.
+    pub violations: Vec<RecipeInvariant>,
+}
+
+impl Display for RecipeValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Recipe {} is unbalanced:", self.recipe)?;
+        for violation in &self.violations {
+            write!(f, " {violation};")?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks a recipe's [`TIME`](Recipe::TIME), [`INPUT_AMOUNTS`](Recipe::INPUT_AMOUNTS), [`OUTPUT_AMOUNTS`](Recipe::OUTPUT_AMOUNTS) and [`OUTPUT_SCALE`](Recipe::OUTPUT_SCALE) for balance mistakes.
+/// Meant to be called from tests when adding new recipes.
+pub fn validate<R: RecipeEx>() -> Result<(), RecipeValidationError> {
+    let mut violations = Vec::new();
+    if R::TIME == 0 {
+        violations.push(RecipeInvariant::NonZeroTime);
+    }
+    if R::OUTPUT_SCALE == 0 {
+        violations.push(RecipeInvariant::NonZeroOutputScale);
+    }
+    let mut inputs = R::new_inputs();
+    let mut outputs = R::new_outputs();
+    let mut has_inputs = false;
+    let mut has_outputs = false;
+    for (resource, amount, _) in R::iter_inputs(&mut inputs) {
+        has_inputs = true;
+        if amount == 0 {
+            violations.push(RecipeInvariant::PositiveAmount { resource });
+        }
+    }
+    for (resource, amount, _) in R::iter_outputs(&mut outputs) {
+        has_outputs = true;
+        if amount == 0 {
+            violations.push(RecipeInvariant::PositiveAmount { resource });
+        }
+    }
+    if !has_inputs {
+        violations.push(RecipeInvariant::HasInputs);
+    }
+    if !has_outputs {
+        violations.push(RecipeInvariant::HasOutputs);
+    }
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(RecipeValidationError {
+            recipe: R::NAME,
+            violations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource_type;
+
+    resource_type!(Ore);
+    resource_type!(Ingot);
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((1, Ore))]
+    #[recipe_outputs((1, Ingot))]
+    #[recipe_ticks(5)]
+    struct Smelting;
+
+    resource_type!(Flux);
+    resource_type!(Slag);
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((3, Ore), (1, Flux), (2, Ingot))]
+    #[recipe_outputs((4, Ingot), (1, Slag))]
+    #[recipe_ticks(5)]
+    struct Alloying;
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((1, Ore))]
+    #[recipe_outputs((1, Ingot))]
+    #[recipe_output_scale(2)]
+    #[recipe_ticks(1)]
+    struct Sifting;
+
+    #[derive(Debug, Recipe, RecipeEx)]
+    #[recipe_inputs((0, Ore))]
+    #[recipe_outputs((0, Ingot))]
+    #[recipe_ticks(0)]
+    struct Broken;
+
+    #[test]
+    fn iterators_follow_declaration_order() {
+        let mut inputs = Alloying::new_inputs();
+        inputs.0.amount = 10;
+        inputs.1.amount = 20;
+        inputs.2.amount = 30;
+        let inputs: Vec<_> = Alloying::iter_inputs(&mut inputs)
+            .map(|(name, needed, current)| (name, needed, *current))
+            .collect();
+        assert_eq!(inputs, [("Ore", 3, 10), ("Flux", 1, 20), ("Ingot", 2, 30)]);
+
+        let mut outputs = Alloying::new_outputs();
+        outputs.1.amount = 7;
+        let outputs: Vec<_> = Alloying::iter_outputs(&mut outputs)
+            .map(|(name, needed, current)| (name, needed, *current))
+            .collect();
+        assert_eq!(outputs, [("Ingot", 4, 0), ("Slag", 1, 7)]);
+    }
+
+    #[test]
+    fn convert_estimate_matches_hand_computed_values() {
+        // 3 ore and 1 flux make 4 ingots.
+        for (ore, cycles, floor, nearest) in [
+            (0, 0, 0, 0),
+            (2, 0, 2, 3),
+            (3, 4, 4, 4),
+            (5, 4, 6, 7),
+            (7, 8, 9, 9),
+            (100, 132, 133, 133),
+        ] {
+            assert_eq!(
+                Alloying::convert_estimate(ore, Rounding::Cycles),
+                cycles,
+                "{ore} ore"
+            );
+            assert_eq!(
+                Alloying::convert_estimate(ore, Rounding::Floor),
+                floor,
+                "{ore} ore"
+            );
+            assert_eq!(
+                Alloying::convert_estimate(ore, Rounding::Nearest),
+                nearest,
+                "{ore} ore"
+            );
+        }
+        assert_eq!(
+            Smelting::convert_estimate(u32::MAX, Rounding::Nearest),
+            u32::MAX
+        );
+        assert_eq!(Sifting::convert_estimate(5, Rounding::Cycles), 2);
+        assert_eq!(Sifting::convert_estimate(5, Rounding::Nearest), 3);
+        assert_eq!(Broken::convert_estimate(10, Rounding::Floor), 0);
+    }
+
+    #[test]
+    fn validate_reports_every_broken_invariant() {
+        assert_eq!(validate::<Smelting>(), Ok(()));
+        let err = validate::<Broken>().unwrap_err();
+        assert!(err.recipe.ends_with("Broken"));
+        assert_eq!(
+            err.violations,
+            [
+                RecipeInvariant::NonZeroTime,
+                RecipeInvariant::PositiveAmount { resource: "Ore" },
+                RecipeInvariant::PositiveAmount { resource: "Ingot" },
+            ]
+        );
+    }
 }
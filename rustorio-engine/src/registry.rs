@@ -0,0 +1,167 @@
+//! Looking up resource types by [name](ResourceType::NAME).
+//!
+//! Saves and tools only see resource names, like `"Copper"`, so they need a way back to `Resource<Copper>`.
+//! Rust can't list every type implementing [`ResourceType`], so a mod registers its resource types once at startup:
+//! ```rust
+//! use rustorio_engine::{mod_reexports::Resource, registry::ResourceRegistry, resource_type};
+//! resource_type!(Iron);
+//! resource_type!(Copper);
+//!
+//! let mut registry = ResourceRegistry::new();
+//! registry.register::<Iron>().unwrap();
+//! registry.register::<Copper>().unwrap();
+//!
+//! let copper: Resource<Copper> = registry.restore("Copper", 10).unwrap();
+//! assert_eq!(copper.amount(), 10);
+//! ```
+//!
+//! Restoring creates resources out of nothing, so none of this should be reexported in mods.
+
+use alloc::{boxed::Box, collections::BTreeMap};
+use core::{
+    any::{Any, TypeId},
+    fmt::{self, Display},
+};
+
+use crate::resources::{Resource, ResourceType, resource};
+
+/// Error returned by [`ResourceRegistry::register`] when another resource type already uses the name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DuplicateResourceError {
+    /// The name both resource types share.
+    pub name: &'static str,
+}
+
+impl Display for DuplicateResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Another resource type is already named {:?}", self.name)
+    }
+}
+
+impl core::error::Error for DuplicateResourceError {}
+
+/// A registered resource type, with its type erased.
+#[derive(Debug, Clone, Copy)]
+pub struct RegisteredResource {
+    name: &'static str,
+    type_id: TypeId,
+    restore: fn(u32) -> Box<dyn Any>,
+}
+
+impl RegisteredResource {
+    fn of<T: ResourceType + 'static>() -> Self {
+        Self {
+            name: T::NAME,
+            type_id: TypeId::of::<T>(),
+            restore: |amount| Box::new(resource::<T>(amount)),
+        }
+    }
+
+    /// The [name](ResourceType::NAME) of the resource type.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether this is the resource type `T`.
+    pub fn is<T: ResourceType + 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    /// Creates `amount` of the resource as a boxed `Resource<T>`, for callers that don't know `T`.
+    pub fn restore_any(&self, amount: u32) -> Box<dyn Any> {
+        (self.restore)(amount)
+    }
+}
+
+/// Resource types by [name](ResourceType::NAME).
+/// See the [module documentation](self) for how to use it.
+#[derive(Debug, Default)]
+pub struct ResourceRegistry {
+    resources: BTreeMap<&'static str, RegisteredResource>,
+}
+
+impl ResourceRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `T` under its [name](ResourceType::NAME).
+    /// Registering the same type again does nothing, but a different type with the same name is an error.
+    pub fn register<T: ResourceType + 'static>(&mut self) -> Result<(), DuplicateResourceError> {
+        match self.resources.get(T::NAME) {
+            Some(existing) if existing.is::<T>() => Ok(()),
+            Some(_) => Err(DuplicateResourceError { name: T::NAME }),
+            None => {
+                self.resources
+                    .insert(T::NAME, RegisteredResource::of::<T>());
+                Ok(())
+            }
+        }
+    }
+
+    /// The resource type registered under `name`.
+    pub fn get(&self, name: &str) -> Option<&RegisteredResource> {
+        self.resources.get(name)
+    }
+
+    /// The names of all registered resource types, in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.resources.keys().copied()
+    }
+
+    /// Creates `amount` of the resource registered under `name`,
+    /// or `None` if nothing is registered under `name` or it isn't `T`.
+    pub fn restore<T: ResourceType + 'static>(
+        &self,
+        name: &str,
+        amount: u32,
+    ) -> Option<Resource<T>> {
+        let registered = self.get(name)?;
+        if !registered.is::<T>() {
+            return None;
+        }
+        Some(resource(amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource_type;
+
+    resource_type!(Iron);
+    resource_type!(Copper);
+
+    mod other {
+        crate::resource_type!(Iron);
+    }
+
+    #[test]
+    fn lookup_by_name() {
+        let mut registry = ResourceRegistry::new();
+        registry.register::<Iron>().unwrap();
+        registry.register::<Copper>().unwrap();
+        registry.register::<Iron>().unwrap();
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["Copper", "Iron"]);
+
+        let copper = registry.get("Copper").unwrap();
+        assert_eq!(copper.name(), "Copper");
+        assert!(copper.is::<Copper>());
+        assert!(!copper.is::<Iron>());
+        let copper = copper
+            .restore_any(3)
+            .downcast::<Resource<Copper>>()
+            .unwrap();
+        assert_eq!(copper.amount(), 3);
+
+        assert_eq!(registry.restore::<Iron>("Iron", 5).unwrap().amount(), 5);
+        assert!(registry.restore::<Iron>("Copper", 5).is_none());
+        assert!(registry.get("Steel").is_none());
+
+        assert_eq!(
+            registry.register::<other::Iron>(),
+            Err(DuplicateResourceError { name: "Iron" })
+        );
+    }
+}
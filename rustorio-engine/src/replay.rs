@@ -0,0 +1,167 @@
+//! Recording a run so it can be reproduced and checked later.
+//!
+//! Call [`Tick::start_recording`] at the start of `user_main` and every action that changes the game is logged with the tick it happened at:
+//! time passing, mining and crafting by hand, resources being fed into machines, and technologies being researched.
+//! When [`play`](crate::play) finishes, the replay is written to the file named by [`REPLAY_ENV_VAR`], or [`DEFAULT_REPLAY_FILE`].
+//!
+//! The game has no randomness, and worlds generated from a [seed](crate::gamemodes::GameMode::SEED) are the same every time, so running the same save again produces the same replay.
+//! [`verify_replay`](crate::verify_replay) does exactly that and reports the first action that differs, which makes replays useful for bug reports and for verifying leaderboard entries.
+//! A replay can't be played back without the code that made it, as its actions don't say which machine or resource they were applied to.
+
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::tick::Tick;
+
+/// If this environment variable is set when [`play`](crate::play) finishes a recorded game, the replay is written to the file it names.
+pub const REPLAY_ENV_VAR: &str = "RUSTORIO_REPLAY";
+
+/// The file [`play`](crate::play) writes a recorded game's replay to if [`REPLAY_ENV_VAR`] isn't set.
+pub const DEFAULT_REPLAY_FILE: &str = "replay.json";
+
+/// Something the player did that changed the game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ActionKind {
+    /// Time passed.
+    Advance {
+        /// How many ticks passed.
+        ticks: u64,
+    },
+    /// Ore was mined by hand.
+    Mine {
+        /// The name of the ore.
+        ore: String,
+        /// How much was mined.
+        amount: u32,
+    },
+    /// A recipe was crafted by hand.
+    Craft {
+        /// The name of the recipe.
+        recipe: String,
+    },
+    /// Resources were put into a machine's input buffer.
+    Feed {
+        /// The name of the resource.
+        resource: String,
+        /// How much was added.
+        amount: u32,
+    },
+    /// A technology was researched.
+    Research {
+        /// The name of the technology.
+        technology: String,
+    },
+}
+
+/// An [`ActionKind`] along with the tick it happened at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Action {
+    /// The tick the action happened at. For [`ActionKind::Advance`], the tick time started passing at.
+    pub tick: u64,
+    /// What happened.
+    #[serde(flatten)]
+    pub kind: ActionKind,
+}
+
+/// Every action taken during a recorded run, in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replay {
+    actions: Vec<Action>,
+}
+
+impl Replay {
+    pub(crate) fn push(&mut self, tick: u64, kind: ActionKind) {
+        if let ActionKind::Advance { ticks } = kind
+            && let Some(Action {
+                tick: start,
+                kind: ActionKind::Advance { ticks: previous },
+            }) = self.actions.last_mut()
+            && *start + *previous == tick
+        {
+            // Advancing one tick at a time is recorded as a single action.
+            *previous += ticks;
+            return;
+        }
+        self.actions.push(Action { tick, kind });
+    }
+
+    /// The recorded actions, in the order they were taken.
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    /// The tick the run ended at, as far as the recorded actions show.
+    pub fn final_tick(&self) -> u64 {
+        self.actions
+            .iter()
+            .map(|action| match action.kind {
+                ActionKind::Advance { ticks } => action.tick + ticks,
+                _ => action.tick,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Writes the replay to `path`.
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents)
+    }
+
+    /// Reads a replay from `path`.
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Finds the first action where `other` differs from this replay.
+    /// Returns `None` if they are the same.
+    pub fn first_difference(&self, other: &Self) -> Option<ReplayMismatch> {
+        let len = self.actions.len().max(other.actions.len());
+        (0..len).find_map(|index| {
+            let expected = self.actions.get(index);
+            let actual = other.actions.get(index);
+            (expected != actual).then(|| ReplayMismatch {
+                index,
+                expected: expected.cloned(),
+                actual: actual.cloned(),
+            })
+        })
+    }
+}
+
+/// The first difference between a replay and a run that was supposed to reproduce it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayMismatch {
+    /// The index of the first action that differs.
+    pub index: usize,
+    /// The action in the replay, or `None` if the run took more actions.
+    pub expected: Option<Action>,
+    /// The action in the run, or `None` if the run stopped early.
+    pub actual: Option<Action>,
+}
+
+impl core::fmt::Display for ReplayMismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Replay differs at action {}: expected {:?}, got {:?}",
+            self.index, self.expected, self.actual
+        )
+    }
+}
+
+impl core::error::Error for ReplayMismatch {}
+
+/// Records an action if the game is being recorded.
+/// For mods whose actions happen outside the engine, like mining.
+/// Should not be reexported in mods.
+pub fn record(tick: &Tick, kind: ActionKind) {
+    tick.record(tick.cur(), kind);
+}
@@ -1,36 +1,138 @@
 //! Technologies can be unlocked by consuming science packs.
 //! They usually unlock new recipes or further technologies.
 //!
-//! This module defines the the science pack resources and the `Technology` trait.
+//! This module defines the `Technology` trait, the [`ResearchPoint`] resource used to research technologies,
+//! and the [`TechRecipe`] labs use to produce them, as well as the [`GenericResearchRecipe`] for points not tied to a technology.
 
-use std::{fmt::Debug, marker::PhantomData};
+use alloc::{boxed::Box, collections::VecDeque, string::ToString, vec::Vec};
+use core::{any::Any, fmt::Debug, marker::PhantomData};
 
 pub use rustorio_derive::{TechnologyEx, technology_doc};
 
 use crate::{
     ResourceType, Sealed,
     recipe::{Recipe, RecipeEx},
-    resources::{Bundle, Resource},
+    replay::ActionKind,
+    resources::{Bundle, InsufficientResourceError, Resource, Solid},
+    tick::Tick,
 };
 
-/// A technology can be unlocked out by calling the `research` method with the required science packs.
-/// This will consume the science packs and the technology itself, and return whatever the technology unlocks, mostly recipes and other technologies.
+/// A technology can be unlocked by calling the `research` method with the required research points.
+/// This will consume the research points and the technology itself, and return whatever the technology unlocks, mostly recipes and other technologies.
 pub trait Technology: Sealed + Debug + Sized + TechnologyEx {
     /// The name of the technology.
     const NAME: &'static str;
     /// How many of this technology's research points (`ResearchPoint<T>`) are needed to complete the research.
     const REQUIRED_RESEARCH_POINTS: u32 = Self::REQUIRED_RESEARCH_POINTS_EX;
+    /// How many ticks it takes a lab to produce one of this technology's research points.
+    /// A single lab researches the technology in `REQUIRED_RESEARCH_POINTS * POINT_RECIPE_TICKS` ticks if never starved.
+    const POINT_RECIPE_TICKS: u64 = Self::POINT_RECIPE_TIME;
 
     /// The reward for completing this technology.
     type Unlocks;
 
-    /// Carries out the research by consuming the required science packs and the research itself, returning whatever this research unlocks.
+    /// Proof that the technologies required before this one have been researched.
+    /// Typically a tuple of [`Researched`] values, or `()` if the technology has no prerequisites.
+    type Prerequisites: Debug + PrerequisiteNames;
+
+    /// Carries out the research by consuming the required research points and the technology itself, returning whatever this research unlocks.
+    /// The research is [recorded](crate::replay) at the current tick.
     fn research(
         self,
+        tick: &Tick,
+        research_points: Bundle<ResearchPoint<Self>, { Self::REQUIRED_RESEARCH_POINTS }>,
+    ) -> Self::Unlocks {
+        let _ = research_points;
+        tick.record(
+            tick.cur(),
+            ActionKind::Research {
+                technology: Self::NAME.to_string(),
+            },
+        );
+        self.reward(ResearchCompleted)
+    }
+
+    /// Returns whatever this technology unlocks.
+    /// Only [`research`](Technology::research) can call it, as it is the only way to get a [`ResearchCompleted`].
+    fn reward(self, completed: ResearchCompleted) -> Self::Unlocks;
+
+    /// The names and total amounts of the science packs needed to research this technology.
+    /// Does not require a lab or the technology itself, so it can be used for planning.
+    fn cost_description() -> impl Iterator<Item = (&'static str, u32)> {
+        let mut inputs = Self::new_inputs();
+        Self::iter_inputs(&mut inputs)
+            .map(|(name, amount, _)| (name, amount * Self::REQUIRED_RESEARCH_POINTS))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Like [`research`](Technology::research), but also returns proof that this technology has been researched.
+    /// The proof is needed to unlock technologies that have this one as a [prerequisite](Technology::Prerequisites).
+    fn research_with_proof(
+        self,
+        tick: &Tick,
         research_points: Bundle<ResearchPoint<Self>, { Self::REQUIRED_RESEARCH_POINTS }>,
-    ) -> Self::Unlocks;
+    ) -> (Self::Unlocks, Researched<Self>) {
+        (self.research(tick, research_points), Researched::new())
+    }
+}
+
+/// Proof that a technology's research points have been spent, handed to [`Technology::reward`] by [`Technology::research`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ResearchCompleted;
+
+/// Proof that the technology `T` has been researched.
+/// Can only be obtained by [researching](Technology::research_with_proof) `T`.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct Researched<T: Technology> {
+    _marker: PhantomData<T>,
 }
 
+impl<T: Technology> Researched<T> {
+    const fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Lists the technologies in a [prerequisite](Technology::Prerequisites) proof by [name](Technology::NAME), e.g. to draw the technology tree.
+pub trait PrerequisiteNames {
+    /// The names of the required technologies, in the order they are listed.
+    fn names() -> Vec<&'static str>;
+}
+
+impl PrerequisiteNames for () {
+    fn names() -> Vec<&'static str> {
+        Vec::new()
+    }
+}
+
+impl<T: Technology> PrerequisiteNames for Researched<T> {
+    fn names() -> Vec<&'static str> {
+        alloc::vec![T::NAME]
+    }
+}
+
+macro_rules! prerequisite_names_tuple {
+    ($($prerequisite:ident),+) => {
+        impl<$($prerequisite: PrerequisiteNames),+> PrerequisiteNames for ($($prerequisite,)+) {
+            fn names() -> Vec<&'static str> {
+                let mut names = Vec::new();
+                $(names.extend($prerequisite::names());)+
+                names
+            }
+        }
+    };
+}
+
+prerequisite_names_tuple!(A);
+prerequisite_names_tuple!(A, B);
+prerequisite_names_tuple!(A, B, C);
+prerequisite_names_tuple!(A, B, C, D);
+
 /// A trait handling the implementation details for a technology. Should only be implemented via the `#[derive(TechnologyEx)]` macro.
 #[doc(hidden)]
 pub trait TechnologyEx {
@@ -45,10 +147,17 @@ pub trait TechnologyEx {
     type InputBundle: Debug;
     /// Amount for each of the input resource types, per recipe cycle.
     const INPUT_AMOUNTS: Self::InputAmountsType;
+    /// Number of input resource types, which is the length of the `Self::Inputs` tuple.
+    const INPUT_COUNT: usize;
     /// The amount of ticks it takes to create one research point for this technology.
     const POINT_RECIPE_TIME: u64;
     /// How many of this technology's research points (`ResearchPoint<T>`) are needed to complete the research.
     const REQUIRED_RESEARCH_POINTS_EX: u32;
+    /// The total amount of science packs in `Self::Inputs`, consumed for one research point.
+    const SCIENCE_PACKS_PER_POINT: u32;
+
+    /// Amount of the `i`th input per research point, or `None` if there is no `i`th input.
+    fn input_amount(i: usize) -> Option<u32>;
 
     /// Factory function to create a new `Self::Inputs` with zero resources.
     fn new_inputs() -> Self::Inputs;
@@ -70,6 +179,71 @@ impl<T: Technology> Sealed for ResearchPoint<T> {}
 impl<T: Technology> ResourceType for ResearchPoint<T> {
     const NAME: &'static str = T::NAME;
 }
+impl<T: Technology> Solid for ResearchPoint<T> {}
+
+impl<T: Technology> ResearchPoint<T> {
+    /// The number of science packs consumed to produce one of these research points.
+    pub const fn science_packs_per_point() -> u32 {
+        T::SCIENCE_PACKS_PER_POINT
+    }
+
+    /// Converts research points of an abandoned technology into [`GenericResearchPoint`]s,
+    /// so that partial progress is not lost entirely when a lab switches to another technology.
+    ///
+    /// The points are worth half the science packs that went into them:
+    /// `points * science_packs_per_point() / GENERIC_RESEARCH_POINT_REFUND_DIVISOR`, rounded down
+    /// and capped at `u32::MAX`.
+    /// Redeeming them with [`from_generic`](ResearchPoint::from_generic) costs the full science pack value,
+    /// so converting back and forth always loses points.
+    pub fn convert_generic(points: Resource<Self>) -> Resource<GenericResearchPointOf<T>> {
+        let packs = u64::from(points.amount()) * u64::from(Self::science_packs_per_point());
+        let refund = packs / u64::from(GENERIC_RESEARCH_POINT_REFUND_DIVISOR);
+        Resource::new(u32::try_from(refund).unwrap_or(u32::MAX))
+    }
+
+    /// Redeems [`GenericResearchPoint`]s for `amount` research points of this technology.
+    /// Each research point costs [`science_packs_per_point`](ResearchPoint::science_packs_per_point) generic points,
+    /// which must come from technologies taking the same science packs as this one.
+    /// If there are not enough generic points, none are consumed.
+    pub fn from_generic(
+        generic: &mut Resource<GenericResearchPointOf<T>>,
+        amount: u32,
+    ) -> Result<Resource<Self>, InsufficientResourceError<GenericResearchPointOf<T>>> {
+        // More than `u32::MAX` generic points can never be held, so an overflowing cost is always unaffordable.
+        let cost = amount
+            .checked_mul(Self::science_packs_per_point())
+            .ok_or(InsufficientResourceError::new(u32::MAX, generic.amount()))?;
+        let spent = generic.split_off(cost)?;
+        let _ = spent;
+        Ok(Resource::new(amount))
+    }
+}
+
+/// How many science packs' worth of research points are needed for one [`GenericResearchPoint`].
+pub const GENERIC_RESEARCH_POINT_REFUND_DIVISOR: u32 = 2;
+
+/// A research point not tied to any technology, refunded from the points of an abandoned research
+/// or researched directly with the [`GenericResearchRecipe`], so science can be stockpiled before deciding what to research.
+/// Each one is worth a single science pack.
+/// Obtained with [`ResearchPoint::convert_generic`] and spent with [`ResearchPoint::from_generic`].
+///
+/// The points are tagged with the science packs `I` they were made from, the [`Inputs`](TechnologyEx::Inputs) of a technology,
+/// and can only be spent on technologies taking the same packs.
+/// Otherwise points refunded from a red science technology could pay for one that also needs green science.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct GenericResearchPoint<I> {
+    _marker: PhantomData<I>,
+}
+
+/// The [`GenericResearchPoint`]s that can be spent on the technology `T`.
+pub type GenericResearchPointOf<T> = GenericResearchPoint<<T as TechnologyEx>::Inputs>;
+
+impl<I> Sealed for GenericResearchPoint<I> {}
+impl<I: Debug> ResourceType for GenericResearchPoint<I> {
+    const NAME: &'static str = "Generic research point";
+}
+impl<I: Debug> Solid for GenericResearchPoint<I> {}
 
 /// A recipe for producing research points for specific technologies.
 #[derive(Debug)]
@@ -85,11 +259,13 @@ where
     type Inputs = T::Inputs;
     type InputAmountsType = T::InputAmountsType;
     const INPUT_AMOUNTS: Self::InputAmountsType = T::INPUT_AMOUNTS;
+    const INPUT_COUNT: usize = T::INPUT_COUNT;
     type Outputs = (Resource<ResearchPoint<T>>,);
 
     type OutputAmountsType = (u32,);
 
     const OUTPUT_AMOUNTS: (u32,) = (1,);
+    const OUTPUT_COUNT: usize = 1;
 
     fn new_inputs() -> Self::Inputs {
         T::new_inputs()
@@ -98,9 +274,18 @@ where
     fn new_outputs() -> Self::Outputs {
         (Resource::new_empty(),)
     }
+
+    fn input_amount(i: usize) -> Option<u32> {
+        T::input_amount(i)
+    }
+
+    fn output_amount(i: usize) -> Option<u32> {
+        (i == 0).then_some(1)
+    }
 }
 
 impl<T: Technology> RecipeEx for TechRecipe<T> {
+    const NAME: &'static str = T::NAME;
     type InputBundle = T::InputBundle;
     type OutputBundle = Bundle<ResearchPoint<T>, 1>;
 
@@ -128,3 +313,299 @@ pub const fn tech_recipe<T: Technology>() -> TechRecipe<T> {
         _marker: PhantomData,
     }
 }
+
+impl<T: Technology> Sealed for TechRecipe<T> {}
+
+/// A recipe for producing [`GenericResearchPoint`]s from the science packs of the technology `T`, one per science pack.
+/// The points can be spent on any technology taking the same science packs as `T`.
+#[derive(Debug)]
+pub struct GenericResearchRecipe<T: Technology> {
+    _marker: PhantomData<T>,
+}
+
+impl<T> Recipe for GenericResearchRecipe<T>
+where
+    T: Technology,
+{
+    const TIME: u64 = T::POINT_RECIPE_TIME;
+    type Inputs = T::Inputs;
+    type InputAmountsType = T::InputAmountsType;
+    const INPUT_AMOUNTS: Self::InputAmountsType = T::INPUT_AMOUNTS;
+    const INPUT_COUNT: usize = T::INPUT_COUNT;
+    type Outputs = (Resource<GenericResearchPointOf<T>>,);
+
+    type OutputAmountsType = (u32,);
+
+    const OUTPUT_AMOUNTS: (u32,) = (T::SCIENCE_PACKS_PER_POINT,);
+    const OUTPUT_COUNT: usize = 1;
+
+    fn new_inputs() -> Self::Inputs {
+        T::new_inputs()
+    }
+
+    fn new_outputs() -> Self::Outputs {
+        (Resource::new_empty(),)
+    }
+
+    fn input_amount(i: usize) -> Option<u32> {
+        T::input_amount(i)
+    }
+
+    fn output_amount(i: usize) -> Option<u32> {
+        (i == 0).then_some(T::SCIENCE_PACKS_PER_POINT)
+    }
+}
+
+impl<T: Technology> RecipeEx for GenericResearchRecipe<T>
+where
+    [(); T::SCIENCE_PACKS_PER_POINT as usize]:,
+{
+    const NAME: &'static str = "GenericResearchRecipe";
+    type InputBundle = T::InputBundle;
+    type OutputBundle = Bundle<GenericResearchPointOf<T>, { T::SCIENCE_PACKS_PER_POINT }>;
+
+    fn new_output_bundle() -> Self::OutputBundle {
+        Bundle::new()
+    }
+
+    fn iter_inputs(
+        items: &mut Self::Inputs,
+    ) -> impl Iterator<Item = (&'static str, u32, &mut u32)> {
+        T::iter_inputs(items)
+    }
+
+    fn iter_outputs(
+        items: &mut Self::Outputs,
+    ) -> impl Iterator<Item = (&'static str, u32, &mut u32)> {
+        [(
+            GenericResearchPointOf::<T>::NAME,
+            T::SCIENCE_PACKS_PER_POINT,
+            &mut items.0.amount,
+        )]
+        .into_iter()
+    }
+}
+
+impl<T: Technology> Sealed for GenericResearchRecipe<T> {}
+
+/// Creates a new `GenericResearchRecipe<T>` for use in a `Machine`.
+pub const fn generic_research_recipe<T: Technology>() -> GenericResearchRecipe<T> {
+    GenericResearchRecipe {
+        _marker: PhantomData,
+    }
+}
+
+trait QueuedResearch {
+    fn points(&mut self) -> &mut dyn Any;
+    fn is_ready(&self) -> bool;
+    fn complete(self: Box<Self>, tick: &Tick);
+}
+
+struct QueueEntry<T: Technology, F: FnOnce(T::Unlocks)> {
+    technology: T,
+    points: Resource<ResearchPoint<T>>,
+    on_complete: F,
+}
+
+impl<T, F> QueuedResearch for QueueEntry<T, F>
+where
+    T: Technology + 'static,
+    F: FnOnce(T::Unlocks),
+    [(); T::REQUIRED_RESEARCH_POINTS as usize]:,
+{
+    fn points(&mut self) -> &mut dyn Any {
+        &mut self.points
+    }
+
+    fn is_ready(&self) -> bool {
+        self.points.amount() >= T::REQUIRED_RESEARCH_POINTS
+    }
+
+    fn complete(mut self: Box<Self>, tick: &Tick) {
+        let research_points = self
+            .points
+            .bundle()
+            .expect("Research completed without enough research points");
+        let QueueEntry {
+            technology,
+            points,
+            on_complete,
+        } = *self;
+        let _ = points;
+        on_complete(technology.research(tick, research_points));
+    }
+}
+
+/// A queue of technologies that are researched in order as research points are deposited into it.
+///
+/// Each technology is given a callback when [pushed](ResearchQueue::push), which receives whatever the technology unlocks once it has been researched.
+/// Research points can be [deposited](ResearchQueue::deposit) for any queued technology, but a technology is only researched once all technologies before it have been.
+#[derive(Default)]
+pub struct ResearchQueue<'a> {
+    queue: VecDeque<Box<dyn QueuedResearch + 'a>>,
+}
+
+impl<'a> ResearchQueue<'a> {
+    /// Creates a new empty research queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a technology to the back of the queue.
+    /// `on_complete` is called with what the technology unlocks once it has been researched.
+    pub fn push<T, F>(&mut self, technology: T, on_complete: F)
+    where
+        T: Technology + 'static,
+        F: FnOnce(T::Unlocks) + 'a,
+        [(); T::REQUIRED_RESEARCH_POINTS as usize]:,
+    {
+        self.queue.push_back(Box::new(QueueEntry {
+            technology,
+            points: Resource::new_empty(),
+            on_complete,
+        }));
+    }
+
+    /// Moves research points from `points` to the first queued technology of type `T`, taking no more than it still needs.
+    /// Then researches technologies from the front of the queue for as long as they have enough research points.
+    ///
+    /// Leaves `points` untouched if no technology of type `T` is queued.
+    pub fn deposit<T: Technology + 'static>(
+        &mut self,
+        tick: &Tick,
+        points: &mut Resource<ResearchPoint<T>>,
+    ) {
+        if let Some(queued) = self
+            .queue
+            .iter_mut()
+            .find_map(|entry| entry.points().downcast_mut::<Resource<ResearchPoint<T>>>())
+        {
+            let needed = T::REQUIRED_RESEARCH_POINTS.saturating_sub(queued.amount());
+            *queued += points.split_off_max(needed);
+        }
+        while self.queue.front().is_some_and(|entry| entry.is_ready()) {
+            self.queue.pop_front().unwrap().complete(tick);
+        }
+    }
+
+    /// The number of technologies still waiting to be researched.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Whether all queued technologies have been researched.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl Debug for ResearchQueue<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ResearchQueue")
+            .field("len", &self.queue.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::{Sealed, resource, resource_type};
+
+    resource_type!(Science);
+
+    #[derive(Debug, TechnologyEx)]
+    #[research_inputs((1, Science))]
+    #[research_point_cost(3)]
+    #[research_ticks(1)]
+    struct FirstTechnology;
+    impl Sealed for FirstTechnology {}
+    impl Technology for FirstTechnology {
+        const NAME: &'static str = "First";
+        type Unlocks = SecondTechnology;
+        type Prerequisites = ();
+
+        fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+            let _ = completed;
+            SecondTechnology
+        }
+    }
+
+    #[derive(Debug, TechnologyEx)]
+    #[research_inputs((2, Science))]
+    #[research_point_cost(2)]
+    #[research_ticks(1)]
+    struct SecondTechnology;
+    impl Sealed for SecondTechnology {}
+    impl Technology for SecondTechnology {
+        const NAME: &'static str = "Second";
+        type Unlocks = u32;
+        type Prerequisites = ();
+
+        fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+            let _ = completed;
+            42
+        }
+    }
+
+    #[test]
+    fn research_queue_completes_technologies_in_order() {
+        let tick = Tick::start();
+        let completed = RefCell::new(Vec::new());
+        let mut queue = ResearchQueue::new();
+        queue.push(FirstTechnology, |_| completed.borrow_mut().push("first"));
+        queue.push(SecondTechnology, |_| completed.borrow_mut().push("second"));
+
+        // Points for the second technology arrive first, but it has to wait for the first one.
+        let mut second_points = resource::<ResearchPoint<SecondTechnology>>(5);
+        queue.deposit(&tick, &mut second_points);
+        assert_eq!(second_points.amount(), 3);
+        assert_eq!(queue.len(), 2);
+
+        let mut first_points = Resource::<ResearchPoint<FirstTechnology>>::new_empty();
+        for _ in 0..3 {
+            assert_eq!(queue.len(), 2);
+            first_points += resource(1);
+            queue.deposit(&tick, &mut first_points);
+        }
+        assert!(queue.is_empty());
+        assert_eq!(first_points.amount(), 0);
+        assert_eq!(*completed.borrow(), ["first", "second"]);
+    }
+
+    #[test]
+    fn cost_description_lists_total_science_packs() {
+        assert_eq!(
+            FirstTechnology::cost_description().collect::<Vec<_>>(),
+            [("Science", 3)]
+        );
+    }
+
+    #[test]
+    fn generic_research_points_cannot_be_duplicated() {
+        let abandoned = resource::<ResearchPoint<FirstTechnology>>(9);
+        let mut generic = ResearchPoint::convert_generic(abandoned);
+        assert_eq!(generic.amount(), 4);
+
+        let points = ResearchPoint::<SecondTechnology>::from_generic(&mut generic, 1).unwrap();
+        assert_eq!(points.amount(), 1);
+        assert_eq!(generic.amount(), 2);
+
+        assert!(ResearchPoint::<SecondTechnology>::from_generic(&mut generic, 2).is_err());
+        assert_eq!(generic.amount(), 2);
+    }
+
+    #[test]
+    fn generic_research_point_conversions_do_not_overflow() {
+        let abandoned = resource::<ResearchPoint<SecondTechnology>>(u32::MAX);
+        let mut generic = ResearchPoint::convert_generic(abandoned);
+        assert_eq!(generic.amount(), u32::MAX);
+
+        let err =
+            ResearchPoint::<SecondTechnology>::from_generic(&mut generic, u32::MAX).unwrap_err();
+        assert_eq!(err.available_amount, u32::MAX);
+        assert_eq!(generic.amount(), u32::MAX);
+    }
+}
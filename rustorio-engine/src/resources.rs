@@ -4,7 +4,8 @@
 //!
 //! This module the core definitions for resources, including the `ResourceType` trait, the `Resource` and `Bundle` structs, and the macro to define new resources.
 
-use std::{
+use alloc::{format, string::String};
+use core::{
     fmt::{Debug, Display},
     iter::Sum,
     marker::PhantomData,
@@ -19,12 +20,17 @@ use crate::Sealed;
 ///
 /// ## Modding
 ///
-/// To define a new resource type, use the `resource_type!` macro.
+/// To define a new resource type, use the `resource_type!` macro, or `fluid_type!` for a fluid.
 pub trait ResourceType: Sealed + Debug {
     /// A human readable name for this resource type.
     const NAME: &'static str;
 }
 
+/// A resource type that isn't a fluid, so it can be carried in a [`Bundle`].
+/// Every resource type defined with `resource_type!` is one, and none defined with `fluid_type!` are.
+/// Fluids only exist in [`Resource`]s, so they have to be piped between buildings.
+pub trait Solid: ResourceType {}
+
 /// Macro to define a new resource type.
 ///
 /// # Example
@@ -37,6 +43,40 @@ pub trait ResourceType: Sealed + Debug {
 #[macro_export]
 macro_rules! resource_type {
 
+    ($(#[$outer:meta])*
+    $name:ident) => {
+        $(#[$outer])*
+        #[derive(Debug)]
+        pub struct $name;
+        impl $crate::Sealed for $name {}
+        impl $crate::ResourceType for $name {
+            const NAME: &'static str = stringify!($name);
+        }
+        impl $crate::resources::Solid for $name {}
+    };
+}
+
+/// Macro to define a new fluid, a resource type that can't be carried in a [`Bundle`].
+///
+/// # Example
+/// ```rust
+/// use rustorio_engine::{fluid_type, mod_reexports::Resource};
+/// fluid_type!(
+///     /// Crude oil pumped from the ground.
+///     CrudeOil);
+///
+/// let mut oil = Resource::<CrudeOil>::new_empty();
+/// oil += Resource::new_empty();
+/// ```
+/// Taking a bundle of a fluid doesn't compile:
+/// ```compile_fail,E0277
+/// # use rustorio_engine::{fluid_type, mod_reexports::Resource};
+/// # fluid_type!(CrudeOil);
+/// let mut oil = Resource::<CrudeOil>::new_empty();
+/// let _ = oil.bundle::<5>();
+/// ```
+#[macro_export]
+macro_rules! fluid_type {
     ($(#[$outer:meta])*
     $name:ident) => {
         $(#[$outer])*
@@ -68,10 +108,31 @@ impl<Resource: ResourceType> InsufficientResourceError<Resource> {
             phantom: PhantomData,
         }
     }
+
+    /// How many more units it would take to fulfill the request.
+    pub const fn deficit(&self) -> u32 {
+        self.requested_amount.saturating_sub(self.available_amount)
+    }
+
+    /// How many ticks it takes to make up the [`deficit`](InsufficientResourceError::deficit) when `per_tick` units come in each tick,
+    /// e.g. the mining rate of a territory, so automation can decide whether to wait or mine by hand.
+    /// Returns `None` if `per_tick` isn't positive, as the deficit is then never made up.
+    pub fn ticks_to_cover(&self, per_tick: f64) -> Option<u64> {
+        if per_tick.is_nan() || per_tick <= 0.0 {
+            return None;
+        }
+        let ticks = f64::from(self.deficit()) / per_tick;
+        let whole = ticks as u64;
+        Some(if (whole as f64) < ticks {
+            whole + 1
+        } else {
+            whole
+        })
+    }
 }
 
 impl<Resource: ResourceType> Display for InsufficientResourceError<Resource> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "Insufficient {:?}: requested {}, but only {} available",
@@ -82,8 +143,19 @@ impl<Resource: ResourceType> Display for InsufficientResourceError<Resource> {
     }
 }
 
+/// Adds two resource amounts, panicking if the total does not fit in a [`Resource`].
+const fn checked_total(amount: u32, added: u32) -> u32 {
+    match amount.checked_add(added) {
+        Some(total) => total,
+        None => panic!("Resource amount overflowed"),
+    }
+}
+
 /// Holds an arbitrary amount of a resource.
 /// A [`Resource`] object can be split into smaller parts, combined or [`Bundle`]s can be extracted from them.
+///
+/// Piles are ordered by amount. Note that [`Ord::max`] and [`Ord::min`] consume both piles and drop one of them,
+/// so use [`max_amount`](Resource::max_amount) and [`min_amount`](Resource::min_amount) to compare without losing anything.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[must_use = "This resource is being dropped without being used. If this is intentional, use the `let _ = resource;` pattern to silence this warning."]
 pub struct Resource<Content: ResourceType> {
@@ -127,6 +199,24 @@ impl<Content: ResourceType> Resource<Content> {
         self.amount
     }
 
+    /// The larger of the amounts in this and `other`, leaving both piles untouched.
+    pub const fn max_amount(&self, other: &Self) -> u32 {
+        if self.amount >= other.amount {
+            self.amount
+        } else {
+            other.amount
+        }
+    }
+
+    /// The smaller of the amounts in this and `other`, leaving both piles untouched.
+    pub const fn min_amount(&self, other: &Self) -> u32 {
+        if self.amount <= other.amount {
+            self.amount
+        } else {
+            other.amount
+        }
+    }
+
     const fn amount_mut(&mut self) -> &mut u32 {
         &mut self.amount
     }
@@ -176,6 +266,8 @@ impl<Content: ResourceType> Resource<Content> {
     }
 
     /// Empties this [`Resource`] except for the specified amount, returning the emptied resources as a new [`Resource`].
+    /// Caps the pile at `amount` without losing the excess.
+    #[doc(alias = "clamp_to")]
     pub const fn empty_except(&mut self, amount: u32) -> Self {
         let to_empty = self.amount.saturating_sub(amount);
         self.amount -= to_empty;
@@ -183,25 +275,54 @@ impl<Content: ResourceType> Resource<Content> {
     }
 
     /// Empties this [`Resource`] into another [`Resource`], transferring all contained resources.
+    ///
+    /// # Panics
+    /// Panics if the total in `other` would exceed [`u32::MAX`], like [`add`](Resource::add).
     pub const fn empty_into(&mut self, other: &mut Self) {
-        other.amount += self.amount;
+        other.amount = checked_total(other.amount, self.amount);
         self.amount = 0;
     }
 
-    /// Adds the entire contents of another resource container to this one.
+    /// Adds the entire contents of another resource container, a [`Resource`] or a [`Bundle`], to this one.
+    ///
+    /// A [`Resource`] can hold any amount, so this always succeeds. In particular, adding to a building's input buffer never rejects anything,
+    /// so callers that want to limit how much a building holds should use [`Machine::try_feed`](crate::machine::Machine::try_feed) or its equivalent in the mod.
+    ///
+    /// # Panics
+    /// Panics if the total would exceed [`u32::MAX`].
     pub fn add(&mut self, other: impl Into<Self>) {
-        self.amount += other.into().amount();
+        self.amount = checked_total(self.amount, other.into().amount());
     }
 
     /// Consumes a [`Bundle`] of the same resource type and adds the contained resources to this [`Resource`].
+    ///
+    /// # Panics
+    /// Panics if the total would exceed [`u32::MAX`], like [`add`](Resource::add).
     pub const fn add_bundle<const AMOUNT: u32>(&mut self, bundle: Bundle<Content, AMOUNT>) {
-        self.amount += bundle.amount();
+        self.amount = checked_total(self.amount, bundle.amount());
+    }
+
+    /// Like [`add_bundle`](Resource::add_bundle), but returns the resource, so bundles can be added in a chain.
+    ///
+    /// ```
+    /// # use rustorio_engine::{bundle, mod_reexports::{Bundle, Resource}, resource_type};
+    /// # resource_type!(Iron);
+    /// # let (first, second): (Bundle<Iron, 3>, Bundle<Iron, 4>) = (bundle(), bundle());
+    /// let iron = Resource::new_empty().with_bundle(first).with_bundle(second);
+    /// assert_eq!(iron.amount(), 7);
+    /// ```
+    pub const fn with_bundle<const AMOUNT: u32>(mut self, bundle: Bundle<Content, AMOUNT>) -> Self {
+        self.add_bundle(bundle);
+        self
     }
 
     /// Takes a specified amount of resources from this [`Resource`] and puts it into a [`Bundle`].
     pub const fn bundle<const AMOUNT: u32>(
         &mut self,
-    ) -> Result<Bundle<Content, AMOUNT>, InsufficientResourceError<Content>> {
+    ) -> Result<Bundle<Content, AMOUNT>, InsufficientResourceError<Content>>
+    where
+        Content: Solid,
+    {
         if let Some(remaining) = self.amount.checked_sub(AMOUNT) {
             self.amount = remaining;
             Ok(Bundle::new())
@@ -209,10 +330,118 @@ impl<Content: ResourceType> Resource<Content> {
             Err(InsufficientResourceError::new(AMOUNT, self.amount))
         }
     }
+
+    /// Takes two [`Bundle`]s at once, e.g. to provision two buildings from one pile.
+    /// If there is less than `A + B`, nothing is taken.
+    /// `A + B` must fit in a `u32`, which is checked at compile time.
+    pub const fn bundle_pair<const A: u32, const B: u32>(
+        &mut self,
+    ) -> Result<(Bundle<Content, A>, Bundle<Content, B>), InsufficientResourceError<Content>>
+    where
+        Content: Solid,
+    {
+        let total = const { A.checked_add(B).expect("The bundles hold more than a u32") };
+        if let Some(remaining) = self.amount.checked_sub(total) {
+            self.amount = remaining;
+            Ok((Bundle::new(), Bundle::new()))
+        } else {
+            Err(InsufficientResourceError::new(total, self.amount))
+        }
+    }
+
+    /// Takes a [`Bundle`] of `AMOUNT` if there is enough, and otherwise everything there is.
+    /// Either way nothing is lost: whatever isn't in the returned bundle or resource stays in this [`Resource`].
+    pub const fn bundle_or_all<const AMOUNT: u32>(
+        &mut self,
+    ) -> Result<Bundle<Content, AMOUNT>, Self>
+    where
+        Content: Solid,
+    {
+        match self.bundle() {
+            Ok(bundle) => Ok(bundle),
+            Err(_) => Err(self.empty()),
+        }
+    }
+
+    /// Takes the resources required by `blueprint` from this [`Resource`].
+    /// If there are insufficient resources, nothing is taken.
+    pub const fn fulfill<const AMOUNT: u32>(
+        &mut self,
+        blueprint: Blueprint<Content, AMOUNT>,
+    ) -> Result<Bundle<Content, AMOUNT>, InsufficientResourceError<Content>>
+    where
+        Content: Solid,
+    {
+        let _ = blueprint;
+        self.bundle()
+    }
+
+    /// Formats the resource for logs with its amount shortened to [SI notation](AmountStyle::Si), e.g. `12.3k Iron`.
+    /// [`Display`] always writes the exact amount.
+    pub fn display_human(&self) -> String {
+        format!(
+            "{} {}",
+            AmountStyle::Si.format(self.amount.into()),
+            Content::NAME
+        )
+    }
+}
+
+/// How to write amounts that may get large, e.g. in logs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AmountStyle {
+    /// The exact amount, e.g. `12340`.
+    #[default]
+    Exact,
+    /// The exact amount with thousands separated by commas, e.g. `12,340`.
+    Grouped,
+    /// The amount shortened with an SI suffix and at most one decimal, rounded down, e.g. `12.3k`.
+    /// Amounts below 1000 are written exactly.
+    Si,
+}
+
+impl AmountStyle {
+    /// Formats `amount` in this style.
+    pub fn format(self, amount: u64) -> String {
+        match self {
+            Self::Exact => format!("{amount}"),
+            Self::Grouped => {
+                let digits = format!("{amount}");
+                let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+                for (i, digit) in digits.chars().enumerate() {
+                    if i > 0 && (digits.len() - i) % 3 == 0 {
+                        grouped.push(',');
+                    }
+                    grouped.push(digit);
+                }
+                grouped
+            }
+            Self::Si => {
+                let Some((unit, suffix)) = [
+                    (1_000_000_000_000_000_000, "E"),
+                    (1_000_000_000_000_000, "P"),
+                    (1_000_000_000_000, "T"),
+                    (1_000_000_000, "G"),
+                    (1_000_000, "M"),
+                    (1_000, "k"),
+                ]
+                .into_iter()
+                .find(|&(unit, _)| amount >= unit) else {
+                    return format!("{amount}");
+                };
+                let (whole, tenths) = (amount / unit, amount % unit / (unit / 10));
+                if tenths == 0 {
+                    format!("{whole}{suffix}")
+                } else {
+                    format!("{whole}.{tenths}{suffix}")
+                }
+            }
+        }
+    }
 }
 
 impl<Content: ResourceType> Display for Resource<Content> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "{amount} {content}",
@@ -223,7 +452,7 @@ impl<Content: ResourceType> Display for Resource<Content> {
 }
 
 impl<Content: ResourceType> PartialOrd<u32> for Resource<Content> {
-    fn partial_cmp(&self, other: &u32) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &u32) -> Option<core::cmp::Ordering> {
         Some(self.amount.cmp(other))
     }
 }
@@ -235,7 +464,7 @@ impl<Content: ResourceType> PartialEq<u32> for Resource<Content> {
 }
 
 impl<Content: ResourceType> PartialOrd<Resource<Content>> for u32 {
-    fn partial_cmp(&self, other: &Resource<Content>) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Resource<Content>) -> Option<core::cmp::Ordering> {
         Some(self.cmp(&other.amount))
     }
 }
@@ -248,7 +477,7 @@ impl<Content: ResourceType> PartialEq<Resource<Content>> for u32 {
 
 impl<Content: ResourceType> AddAssign for Resource<Content> {
     fn add_assign(&mut self, rhs: Self) {
-        self.amount += rhs.amount
+        self.amount = checked_total(self.amount, rhs.amount);
     }
 }
 
@@ -277,7 +506,7 @@ pub struct Bundle<Content: ResourceType, const AMOUNT: u32> {
 
 /// Creates a new [`Bundle`] with the specified resource type and amount.
 /// Should not be reexported in mods.
-pub fn bundle<Content: ResourceType, const AMOUNT: u32>() -> Bundle<Content, AMOUNT> {
+pub fn bundle<Content: Solid, const AMOUNT: u32>() -> Bundle<Content, AMOUNT> {
     Bundle::new()
 }
 
@@ -291,7 +520,10 @@ impl<Content: ResourceType, const AMOUNT: u32> Bundle<Content, AMOUNT> {
     /// The fixed amount of resource contained in this [`Bundle`].
     pub const AMOUNT: u32 = AMOUNT;
 
-    pub(crate) const fn new() -> Self {
+    pub(crate) const fn new() -> Self
+    where
+        Content: Solid,
+    {
         Self { dummy: PhantomData }
     }
 
@@ -306,11 +538,48 @@ impl<Content: ResourceType, const AMOUNT: u32> Bundle<Content, AMOUNT> {
         self,
     ) -> (Bundle<Content, AMOUNT1>, Bundle<Content, AMOUNT2>)
     where
+        Content: Solid,
         Assert<{ AMOUNT1 + AMOUNT2 == AMOUNT }>: IsTrue,
     {
         (Bundle::new(), Bundle::new())
     }
 
+    /// Combines `N` equal [`Bundle`]s into one, e.g. the ore from several hand mining runs.
+    /// `TOTAL` must equal `AMOUNT * N`, which is checked at compile time:
+    /// ```
+    /// # #![feature(generic_const_exprs)]
+    /// # use rustorio_engine::{bundle, mod_reexports::Bundle, resource_type};
+    /// # resource_type!(Iron);
+    /// let loads: [Bundle<Iron, 10>; 3] = [bundle(), bundle(), bundle()];
+    /// let total: Bundle<Iron, 30> = Bundle::combine(loads);
+    /// # let _ = total;
+    /// ```
+    /// ```compile_fail,E0308
+    /// # #![feature(generic_const_exprs)]
+    /// # use rustorio_engine::{bundle, mod_reexports::Bundle, resource_type};
+    /// # resource_type!(Iron);
+    /// let loads: [Bundle<Iron, 10>; 3] = [bundle(), bundle(), bundle()];
+    /// let total: Bundle<Iron, 40> = Bundle::combine(loads);
+    /// ```
+    /// For a number of [`Bundle`]s only known at runtime, [sum](core::iter::Sum) them into a [`Resource`] instead:
+    /// ```
+    /// # use rustorio_engine::{bundle, mod_reexports::{Bundle, Resource}, resource_type};
+    /// # resource_type!(Iron);
+    /// let loads: Vec<Bundle<Iron, 10>> = (0..4).map(|_| bundle()).collect();
+    /// let total: Resource<Iron> = loads.into_iter().sum();
+    /// assert_eq!(total.amount(), 40);
+    /// ```
+    pub const fn combine<const N: usize, const TOTAL: u32>(
+        bundles: [Self; N],
+    ) -> Bundle<Content, TOTAL>
+    where
+        Content: Solid,
+        Assert<{ AMOUNT as usize * N == TOTAL as usize }>: IsTrue,
+    {
+        let _ = bundles;
+        Bundle::new()
+    }
+
     /// Converts this [`Bundle`] into a [`Resource`] with the same resource type and amount.
     pub const fn to_resource(self) -> Resource<Content> {
         Resource::new(AMOUNT)
@@ -322,7 +591,7 @@ impl<Content: ResourceType, const AMOUNT: u32> AddAssign<Bundle<Content, AMOUNT>
 {
     fn add_assign(&mut self, bundle: Bundle<Content, AMOUNT>) {
         let _ = bundle;
-        self.amount += AMOUNT;
+        self.amount = checked_total(self.amount, AMOUNT);
     }
 }
 
@@ -344,8 +613,8 @@ impl<Content: ResourceType, const AMOUNT: u32> Add<Resource<Content>> for Bundle
     }
 }
 
-impl<Content: ResourceType, const AMOUNT_LHS: u32, const AMOUNT_RHS: u32>
-    Add<Bundle<Content, AMOUNT_RHS>> for Bundle<Content, AMOUNT_LHS>
+impl<Content: Solid, const AMOUNT_LHS: u32, const AMOUNT_RHS: u32> Add<Bundle<Content, AMOUNT_RHS>>
+    for Bundle<Content, AMOUNT_LHS>
 where
     [(); { AMOUNT_LHS + AMOUNT_RHS } as usize]:,
 {
@@ -357,9 +626,186 @@ where
     }
 }
 
+impl<Content: ResourceType, const AMOUNT: u32> Sum<Bundle<Content, AMOUNT>> for Resource<Content> {
+    fn sum<I: Iterator<Item = Bundle<Content, AMOUNT>>>(iter: I) -> Self {
+        iter.fold(Self::new_empty(), |total, bundle| total + bundle)
+    }
+}
+
 impl<Content: ResourceType, const AMOUNT: u32> From<Bundle<Content, AMOUNT>> for Resource<Content> {
     fn from(bundle: Bundle<Content, AMOUNT>) -> Self {
         let _ = bundle;
         Resource::new(AMOUNT)
     }
 }
+
+/// A planned requirement of a fixed amount of a resource, like "10 iron" in a build plan.
+/// Unlike a [`Bundle`], it contains nothing, so it can be created and copied freely.
+/// [`Resource::fulfill`] turns it into a real [`Bundle`] by taking the resources from a pile.
+pub struct Blueprint<Content: ResourceType, const AMOUNT: u32> {
+    dummy: PhantomData<Content>,
+}
+
+impl<Content: ResourceType, const AMOUNT: u32> Blueprint<Content, AMOUNT> {
+    /// Plans a requirement of `AMOUNT`.
+    pub const fn new() -> Self {
+        Self { dummy: PhantomData }
+    }
+
+    /// The amount of resource required.
+    pub const fn amount(&self) -> u32 {
+        AMOUNT
+    }
+}
+
+impl<Content: ResourceType, const AMOUNT: u32> Default for Blueprint<Content, AMOUNT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Implemented by hand, as deriving would require `Content` to be `Clone` and `Copy` too.
+impl<Content: ResourceType, const AMOUNT: u32> Clone for Blueprint<Content, AMOUNT> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Content: ResourceType, const AMOUNT: u32> Copy for Blueprint<Content, AMOUNT> {}
+
+impl<Content: ResourceType, const AMOUNT: u32> Debug for Blueprint<Content, AMOUNT> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Blueprint({AMOUNT} {})", Content::NAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    resource_type!(Iron);
+
+    #[test]
+    fn amount_styles_at_boundaries() {
+        let amounts = [0, 999, 1_000, 12_340, 999_999, 1_000_000, u64::MAX];
+        assert_eq!(
+            amounts.map(|amount| AmountStyle::Exact.format(amount)),
+            [
+                "0",
+                "999",
+                "1000",
+                "12340",
+                "999999",
+                "1000000",
+                "18446744073709551615"
+            ]
+        );
+        assert_eq!(
+            amounts.map(|amount| AmountStyle::Grouped.format(amount)),
+            [
+                "0",
+                "999",
+                "1,000",
+                "12,340",
+                "999,999",
+                "1,000,000",
+                "18,446,744,073,709,551,615"
+            ]
+        );
+        assert_eq!(
+            amounts.map(|amount| AmountStyle::Si.format(amount)),
+            ["0", "999", "1k", "12.3k", "999.9k", "1M", "18.4E"]
+        );
+        assert_eq!(resource::<Iron>(12_340).display_human(), "12.3k Iron");
+        assert_eq!(resource::<Iron>(12_340).to_string(), "12340 Iron");
+    }
+
+    #[test]
+    fn comparing_and_capping_conserve_resources() {
+        let mut big = resource::<Iron>(12);
+        let small = resource::<Iron>(5);
+        assert_eq!(big.max_amount(&small), 12);
+        assert_eq!(small.max_amount(&big), 12);
+        assert_eq!(big.min_amount(&small), 5);
+        assert_eq!(small.min_amount(&big), 5);
+        assert_eq!((big.amount(), small.amount()), (12, 5));
+
+        let excess = big.empty_except(8);
+        assert_eq!((big.amount(), excess.amount()), (8, 4));
+        let nothing = big.empty_except(20);
+        assert_eq!((big.amount(), nothing.amount()), (8, 0));
+        assert_eq!(big.amount() + excess.amount() + small.amount(), 17);
+    }
+
+    #[test]
+    fn fulfilling_blueprints() {
+        const PLAN: Blueprint<Iron, 5> = Blueprint::new();
+        let mut iron = resource::<Iron>(7);
+        let bundle = iron.fulfill(PLAN).unwrap();
+        assert_eq!((bundle.amount(), iron.amount()), (5, 2));
+
+        // The blueprint is still there to plan with, but the pile can't fulfill it again.
+        let err = iron.fulfill(PLAN).unwrap_err();
+        assert_eq!((err.requested_amount, err.available_amount), (5, 2));
+        assert_eq!(iron.amount(), 2);
+        assert_eq!(format!("{PLAN:?}"), "Blueprint(5 Iron)");
+    }
+
+    #[test]
+    fn bundle_or_all_never_loses_resources() {
+        let mut iron = resource::<Iron>(7);
+        let bundle = iron.bundle_or_all::<5>().unwrap();
+        assert_eq!((bundle.amount(), iron.amount()), (5, 2));
+
+        let Err(rest) = iron.bundle_or_all::<5>() else {
+            panic!("Only 2 iron is left");
+        };
+        assert_eq!((rest.amount(), iron.amount()), (2, 0));
+    }
+
+    #[test]
+    fn bundle_pair_takes_both_or_nothing() {
+        let mut iron = resource::<Iron>(12);
+        let (furnace, assembler) = iron.bundle_pair::<5, 6>().unwrap();
+        assert_eq!(
+            (furnace.amount(), assembler.amount(), iron.amount()),
+            (5, 6, 1)
+        );
+
+        let err = iron.bundle_pair::<1, 1>().unwrap_err();
+        assert_eq!((err.requested_amount, err.available_amount), (2, 1));
+        assert_eq!(iron.amount(), 1);
+    }
+
+    #[test]
+    fn insufficient_bundle_reports_deficit() {
+        let mut iron = resource::<Iron>(12);
+        assert_eq!(iron.bundle::<12>().unwrap().amount(), 12);
+
+        let mut iron = resource::<Iron>(3);
+        let err = iron.bundle::<10>().unwrap_err();
+        assert_eq!(iron.amount(), 3);
+        assert_eq!(err.deficit(), 7);
+        assert_eq!(err.ticks_to_cover(1.0), Some(7));
+        assert_eq!(err.ticks_to_cover(0.5), Some(14));
+        // A partial tick still has to pass in full.
+        assert_eq!(err.ticks_to_cover(2.0), Some(4));
+        assert_eq!(err.ticks_to_cover(0.0), None);
+        assert_eq!(err.ticks_to_cover(f64::NAN), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Resource amount overflowed")]
+    fn adding_a_bundle_past_the_maximum_panics() {
+        let mut full = resource::<Iron>(u32::MAX);
+        let bundle = resource::<Iron>(1).bundle::<1>().unwrap();
+        full.add_bundle(bundle);
+    }
+
+    #[test]
+    #[should_panic(expected = "Resource amount overflowed")]
+    fn emptying_into_a_full_resource_panics() {
+        let mut full = resource::<Iron>(u32::MAX);
+        resource::<Iron>(1).empty_into(&mut full);
+    }
+}
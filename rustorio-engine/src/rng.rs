@@ -0,0 +1,64 @@
+//! Reproducible randomness for generating game worlds.
+//!
+//! The game itself has no randomness. Game modes with a [`SEED`](crate::gamemodes::GameMode::SEED) can still vary their world,
+//! e.g. how rich territories are, by drawing from a [`SeededRng`] created from [`Tick::seed`](crate::mod_reexports::Tick::seed).
+//! The same seed always gives the same world, so players can share a seed to compare runs in the same world.
+
+use core::ops::RangeInclusive;
+
+/// A small deterministic random number generator (SplitMix64).
+/// The same seed always produces the same numbers, on every platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Creates a generator from `seed`.
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// The next random number.
+    pub const fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A random number in `range`.
+    ///
+    /// # Panics
+    /// Panics if `range` is empty.
+    pub fn range(&mut self, range: RangeInclusive<u32>) -> u32 {
+        let (start, end) = range.into_inner();
+        assert!(start <= end, "Empty range");
+        let len = u64::from(end - start) + 1;
+        // The remainder is less than `len`, so it fits in a `u32`.
+        start + (self.next_u64() % len) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let draw = |seed| {
+            let mut rng = SeededRng::new(seed);
+            (0..100).map(|_| rng.range(10..=20)).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(42), draw(42));
+        assert_ne!(draw(42), draw(43));
+        assert!(draw(7).iter().all(|n| (10..=20).contains(n)));
+        assert!((10..=20).all(|n| draw(7).contains(&n)));
+        assert_eq!(SeededRng::new(5).range(3..=3), 3);
+        // The full range doesn't overflow.
+        let _ = SeededRng::new(5).range(0..=u32::MAX);
+    }
+}
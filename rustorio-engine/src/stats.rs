@@ -0,0 +1,203 @@
+//! Production statistics for a whole game.
+//!
+//! Call [`Tick::collect_stats`] at the start of `user_main` to count how much of each resource is produced and consumed,
+//! and how much of the time machines spend crafting.
+//! When [`play`](crate::play) finishes, the statistics are written as JSON to the file named by [`STATS_ENV_VAR`], or [`DEFAULT_STATS_FILE`].
+//! Games that don't collect statistics don't pay for them.
+//!
+//! The JSON output looks like this, with resources and machines sorted by name.
+//! Fields may be added in later versions, but existing ones won't change meaning without bumping `version`:
+//! ```json
+//! {
+//!   "version": 1,
+//!   "ticks": 120,
+//!   "resources": {
+//!     "Iron": { "produced": 20, "consumed": 6 },
+//!     "IronOre": { "produced": 20, "consumed": 20 }
+//!   },
+//!   "machines": {
+//!     "IronSmelting": { "count": 1, "busy_ticks": 120, "total_ticks": 120, "uptime": 1.0 }
+//!   }
+//! }
+//! ```
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::tick::Tick;
+
+/// If this environment variable is set when [`play`](crate::play) finishes a game that collected statistics, they are written to the file it names.
+pub const STATS_ENV_VAR: &str = "RUSTORIO_STATS";
+
+/// The file [`play`](crate::play) writes statistics to if [`STATS_ENV_VAR`] isn't set.
+pub const DEFAULT_STATS_FILE: &str = "stats.json";
+
+/// The version of the statistics JSON schema.
+pub const STATS_VERSION: u32 = 1;
+
+/// How much of a resource was produced and consumed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceStats {
+    /// Total amount produced by machines, miners and hand crafting and mining.
+    pub produced: u64,
+    /// Total amount consumed by machines and hand crafting.
+    pub consumed: u64,
+}
+
+/// How much of the time the machines running a recipe spent crafting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MachineStats {
+    /// How many machines ran the recipe at some point.
+    pub count: u32,
+    /// Ticks spent crafting, summed over all the machines.
+    pub busy_ticks: u64,
+    /// Ticks the machines existed, summed over all the machines.
+    pub total_ticks: u64,
+    /// `busy_ticks / total_ticks`, or 0 if no time has passed.
+    pub uptime: f64,
+}
+
+/// Production statistics for a game. See the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    /// The version of the schema, [`STATS_VERSION`].
+    pub version: u32,
+    /// How many ticks the game lasted, like [`GameResult::ticks`](crate::GameResult::ticks).
+    pub ticks: u64,
+    /// Statistics for each resource, by name.
+    pub resources: BTreeMap<String, ResourceStats>,
+    /// Statistics for the machines running each recipe, by recipe name.
+    pub machines: BTreeMap<String, MachineStats>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            version: STATS_VERSION,
+            ticks: 0,
+            resources: BTreeMap::new(),
+            machines: BTreeMap::new(),
+        }
+    }
+}
+
+impl Stats {
+    pub(crate) fn produced(&mut self, resource: &str, amount: u32) {
+        if amount > 0 {
+            self.resource(resource).produced += u64::from(amount);
+        }
+    }
+
+    pub(crate) fn consumed(&mut self, resource: &str, amount: u32) {
+        if amount > 0 {
+            self.resource(resource).consumed += u64::from(amount);
+        }
+    }
+
+    fn resource(&mut self, resource: &str) -> &mut ResourceStats {
+        self.resources.entry(resource.to_string()).or_default()
+    }
+
+    /// Records a new machine running `recipe`.
+    pub(crate) fn machine_built(&mut self, recipe: &str) {
+        self.machine(recipe).count += 1;
+    }
+
+    /// Records that a machine running `recipe` spent `busy_ticks` of `total_ticks` crafting.
+    pub(crate) fn machine_ran(&mut self, recipe: &str, busy_ticks: u64, total_ticks: u64) {
+        let machine = self.machine(recipe);
+        machine.busy_ticks += busy_ticks;
+        machine.total_ticks += total_ticks;
+    }
+
+    fn machine(&mut self, recipe: &str) -> &mut MachineStats {
+        self.machines.entry(recipe.to_string()).or_default()
+    }
+
+    /// Fills in the derived fields once the game is over.
+    pub(crate) fn finish(mut self, ticks: u64) -> Self {
+        self.ticks = ticks;
+        for machine in self.machines.values_mut() {
+            machine.uptime = if machine.total_ticks == 0 {
+                0.0
+            } else {
+                machine.busy_ticks as f64 / machine.total_ticks as f64
+            };
+        }
+        self
+    }
+
+    /// Writes the statistics to `path` as JSON.
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents)
+    }
+}
+
+/// The name of a type without module paths, e.g. `TechRecipe<SteelTechnology>`.
+pub(crate) fn short_type_name<T: ?Sized>() -> String {
+    let full = core::any::type_name::<T>();
+    let short = |path: &str| path.rsplit("::").next().unwrap_or_default().to_string();
+    let mut name = String::with_capacity(full.len());
+    let mut start = 0;
+    for (i, delimiter) in full.match_indices(['<', '>', ',', ' ', '(', ')', '&']) {
+        name += &short(&full[start..i]);
+        name += delimiter;
+        start = i + delimiter.len();
+    }
+    name + &short(&full[start..])
+}
+
+/// Records `amount` of `resource` being produced, if the game is collecting statistics.
+/// For mods that produce resources outside the engine, like mining.
+/// With the `debug_conservation` feature, this is also how mods report the resources they create.
+/// Should not be reexported in mods.
+pub fn record_produced(tick: &Tick, resource: &str, amount: u32) {
+    tick.with_stats(|stats| stats.produced(resource, amount));
+    #[cfg(feature = "debug_conservation")]
+    tick.with_ledger(|ledger| ledger.created(resource, amount));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_type_name_strips_paths() {
+        assert_eq!(short_type_name::<Stats>(), "Stats");
+        assert_eq!(
+            short_type_name::<Option<(Stats, alloc::vec::Vec<u32>)>>(),
+            "Option<(Stats, Vec<u32>)>"
+        );
+    }
+
+    #[test]
+    fn json_schema_is_stable() {
+        let mut stats = Stats::default();
+        stats.produced("Iron", 20);
+        stats.consumed("Iron", 6);
+        stats.machine_built("IronSmelting");
+        stats.machine_ran("IronSmelting", 90, 120);
+        let stats = stats.finish(120);
+        assert_eq!(
+            serde_json::to_value(&stats).unwrap(),
+            serde_json::json!({
+                "version": 1,
+                "ticks": 120,
+                "resources": {
+                    "Iron": { "produced": 20, "consumed": 6 },
+                },
+                "machines": {
+                    "IronSmelting": { "count": 1, "busy_ticks": 90, "total_ticks": 120, "uptime": 0.75 },
+                },
+            })
+        );
+    }
+}
@@ -1,4 +1,25 @@
-use std::fmt::Display;
+use alloc::{boxed::Box, collections::BTreeMap, rc::Rc, vec::Vec};
+use core::{any::Any, cell::RefCell, fmt::Display};
+
+#[cfg(feature = "debug_conservation")]
+use crate::conservation::Ledger;
+use crate::{
+    events::{AdvanceCause, AdvanceEvent, EventLog},
+    gamemodes::Difficulty,
+    logistics::MiningBudget,
+    replay::{ActionKind, Replay},
+    resources::AmountStyle,
+    stats::Stats,
+};
+
+/// A function to call once a given tick is reached, set with [`Tick::schedule`].
+struct ScheduledCallback(Box<dyn FnOnce(&Tick)>);
+
+impl core::fmt::Debug for ScheduledCallback {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("ScheduledCallback")
+    }
+}
 
 /// The tick is used to keep track of time in the game.
 /// You can advance the game using the [`advance`](Tick::advance) method or similar.
@@ -10,35 +31,328 @@ use std::fmt::Display;
 ///
 /// Let's say we have two furnaces the we want to fill with `iron_ore` and `copper_ore` respectively, and then advance time so they can smelt the ore into ingots:
 /// ```
+/// # #![feature(generic_const_exprs)]
+/// # use rustorio_engine::{machine::Machine, mod_reexports::{Resource, Tick}, recipe::{Recipe, RecipeEx}, resource_type};
+/// # resource_type!(IronOre);
+/// # resource_type!(Iron);
+/// # resource_type!(CopperOre);
+/// # resource_type!(Copper);
+/// # #[derive(Debug, Recipe, RecipeEx)]
+/// # #[recipe_inputs((1, IronOre))]
+/// # #[recipe_outputs((1, Iron))]
+/// # #[recipe_ticks(2)]
+/// # struct IronSmelting;
+/// # #[derive(Debug, Recipe, RecipeEx)]
+/// # #[recipe_inputs((1, CopperOre))]
+/// # #[recipe_outputs((1, Copper))]
+/// # #[recipe_ticks(2)]
+/// # struct CopperSmelting;
+/// # fn smelt(
+/// #     tick: &mut Tick,
+/// #     furnace1: &mut Machine<IronSmelting>,
+/// #     furnace2: &mut Machine<CopperSmelting>,
+/// #     iron_ore: Resource<IronOre>,
+/// #     copper_ore: Resource<CopperOre>,
+/// # ) -> (Resource<Iron>, Resource<Copper>) {
 /// // Add ore to the furnaces at the current tick
 /// furnace1.inputs(&tick).0.add(iron_ore);
 /// furnace2.inputs(&tick).0.add(copper_ore);
 /// // Advance time by 10 ticks so the furnaces can process some of the ore.
 /// tick.advance_by(10);
 /// // Now we can extract the smelted ingots from the furnaces
-/// let iron_ingots = furnace1.outputs(&tick).0.empty().unwrap();
-/// let copper_ingots = furnace2.outputs(&tick).0.empty().unwrap();
+/// let iron_ingots = furnace1.outputs(&tick).0.empty();
+/// let copper_ingots = furnace2.outputs(&tick).0.empty();
+/// # (iron_ingots, copper_ingots)
+/// # }
 /// ```
 #[derive(Debug)]
 pub struct Tick {
     /// The current tick number.
     tick: u64,
+    /// The tick the game started at.
+    start: u64,
     log: bool,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    log_style: AmountStyle,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    quiet: bool,
+    starvation_limit: Option<u64>,
+    difficulty: Difficulty,
+    recording: Option<Rc<RefCell<Replay>>>,
+    stats: Option<RefCell<Stats>>,
+    events: Option<EventLog>,
+    mining_budget: Option<RefCell<MiningBudget>>,
+    seed: Option<u64>,
+    config: Option<Box<dyn Any>>,
+    starting: bool,
+    /// The tick from which each machine speed multiplier applied, in order. Empty until the first change.
+    machine_speed: Vec<(u64, u64)>,
+    #[cfg(feature = "debug_conservation")]
+    ledger: RefCell<Ledger>,
+    /// Callbacks by the tick they are due at, then the order they were scheduled in.
+    scheduled: BTreeMap<(u64, u64), ScheduledCallback>,
+    scheduled_count: u64,
 }
 
 impl Tick {
+    /// Starts the game at tick 0.
+    #[cfg(test)]
     pub(crate) const fn start() -> Self {
+        Self::start_at(0)
+    }
+
+    /// Starts the game at tick `tick` rather than 0, e.g. to continue a scenario where it left off.
+    pub(crate) const fn start_at(tick: u64) -> Self {
         Self {
-            tick: 0,
+            tick,
+            start: tick,
             log: false,
+            log_style: AmountStyle::Exact,
+            quiet: false,
+            starvation_limit: None,
+            difficulty: Difficulty::Normal,
+            recording: None,
+            stats: None,
+            events: None,
+            mining_budget: None,
+            seed: None,
+            config: None,
+            starting: false,
+            machine_speed: Vec::new(),
+            #[cfg(feature = "debug_conservation")]
+            ledger: RefCell::new(Ledger::new()),
+            scheduled: BTreeMap::new(),
+            scheduled_count: 0,
+        }
+    }
+
+    /// Sets how many ticks machines built during the game can be starved before they break.
+    pub(crate) const fn set_starvation_limit(&mut self, starvation_limit: Option<u64>) {
+        self.starvation_limit = starvation_limit;
+    }
+
+    /// Sets the difficulty of the game.
+    pub(crate) const fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+
+    /// Limits how many miners can work at once for the rest of the game.
+    pub(crate) fn set_mining_budget(&mut self, limit: Option<u32>) {
+        self.mining_budget = limit.map(|limit| RefCell::new(MiningBudget::new(limit)));
+    }
+
+    /// The game's [mining budget](crate::logistics), if its [game mode](crate::gamemodes::GameMode::MINING_BUDGET) has one.
+    pub fn mining_budget(&self) -> Option<core::cell::Ref<'_, MiningBudget>> {
+        self.mining_budget.as_ref().map(RefCell::borrow)
+    }
+
+    /// Updates the mining budget, if the game has one.
+    pub(crate) fn with_mining_budget_mut(&self, f: impl FnOnce(&mut MiningBudget)) {
+        if let Some(budget) = &self.mining_budget {
+            f(&mut budget.borrow_mut());
+        }
+    }
+
+    /// Sets the seed the world is generated from.
+    pub(crate) const fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    /// The seed the world is generated from, if its [game mode](crate::gamemodes::GameMode::SEED) has one.
+    /// Share it so others can play in the same world.
+    pub const fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Sets the config the game mode loaded.
+    pub(crate) fn set_game_config(&mut self, config: Box<dyn Any>) {
+        self.config = Some(config);
+    }
+
+    /// The [config](crate::gamemodes::GameMode::Config) the game mode loaded at the start of the game,
+    /// or `None` if it isn't a `C`.
+    pub fn game_config<C: Any>(&self) -> Option<&C> {
+        self.config.as_deref()?.downcast_ref()
+    }
+
+    /// How many times as fast as normal every machine crafts from now on, see [`set_speed_multiplier`](crate::machine::set_speed_multiplier).
+    pub fn machine_speed_multiplier(&self) -> u64 {
+        self.machine_speed
+            .last()
+            .map_or(1, |&(_, multiplier)| multiplier)
+    }
+
+    /// Makes every machine craft `multiplier` times as fast from the current tick on.
+    pub(crate) fn set_machine_speed_multiplier(&mut self, multiplier: u64) {
+        if self
+            .machine_speed
+            .last()
+            .is_some_and(|&(from, _)| from == self.tick)
+        {
+            self.machine_speed.pop();
+        }
+        self.machine_speed.push((self.tick, multiplier));
+    }
+
+    /// How many ticks' worth of crafting a normal machine does from tick `from` to tick `to`,
+    /// taking the [machine speed multiplier](Tick::machine_speed_multiplier) at each tick into account.
+    pub(crate) fn boosted_ticks(&self, from: u64, to: u64) -> u64 {
+        let mut ticks = 0u64;
+        let mut multiplier = 1;
+        let mut segment_start = from;
+        for &(change, new_multiplier) in &self.machine_speed {
+            if change > from {
+                let segment_end = change.min(to);
+                ticks =
+                    ticks.saturating_add((segment_end - segment_start).saturating_mul(multiplier));
+                segment_start = segment_end;
+            }
+            if change >= to {
+                break;
+            }
+            multiplier = new_multiplier;
+        }
+        ticks.saturating_add((to - segment_start).saturating_mul(multiplier))
+    }
+
+    /// Sets whether the starting resources are being created.
+    pub(crate) const fn set_starting(&mut self, starting: bool) {
+        self.starting = starting;
+    }
+
+    /// Whether the game is still starting, i.e. its [starting resources](crate::gamemodes::StartingResources::init) are being created.
+    /// Mods can use this to hand out things during setup that players must not be able to create while playing.
+    pub const fn is_starting(&self) -> bool {
+        self.starting
+    }
+
+    /// The difficulty of the game, as set by its [game mode](crate::gamemodes::GameMode::DIFFICULTY).
+    pub const fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// How many ticks machines built during the game can be starved before they break, if at all.
+    pub(crate) const fn starvation_limit(&self) -> Option<u64> {
+        self.starvation_limit
+    }
+
+    /// Starts recording a [replay](crate::replay) of the game. Does nothing if the game is already being recorded.
+    pub fn start_recording(&mut self) {
+        if self.recording.is_none() {
+            self.recording = Some(Rc::default());
+        }
+    }
+
+    /// The replay being recorded, if any, for things that record actions after handing out access to the game,
+    /// like [`Feeding`](crate::machine::Feeding).
+    pub(crate) fn replay(&self) -> Option<Rc<RefCell<Replay>>> {
+        self.recording.clone()
+    }
+
+    /// Records an action that happened at tick `at`, if the game is being recorded.
+    pub(crate) fn record(&self, at: u64, kind: ActionKind) {
+        if let Some(recording) = &self.recording {
+            recording.borrow_mut().push(at, kind);
+        }
+    }
+
+    /// Stops recording, returning what was recorded.
+    pub(crate) fn stop_recording(&mut self) -> Option<Replay> {
+        self.recording
+            .take()
+            .map(|recording| Rc::unwrap_or_clone(recording).into_inner())
+    }
+
+    /// Starts collecting [production statistics](crate::stats) for the game. Does nothing if they are already being collected.
+    pub fn collect_stats(&mut self) {
+        if self.stats.is_none() {
+            self.stats = Some(RefCell::default());
+        }
+    }
+
+    /// Whether the game is collecting statistics.
+    pub(crate) const fn is_collecting_stats(&self) -> bool {
+        self.stats.is_some()
+    }
+
+    /// Updates the statistics, if the game is collecting them.
+    pub(crate) fn with_stats(&self, f: impl FnOnce(&mut Stats)) {
+        if let Some(stats) = &self.stats {
+            f(&mut stats.borrow_mut());
+        }
+    }
+
+    /// Everything created and destroyed so far, see [`conservation`](crate::conservation).
+    /// Updating machines while holding on to the ledger panics, so read what you need and let go of it.
+    #[cfg(feature = "debug_conservation")]
+    pub fn ledger(&self) -> core::cell::Ref<'_, Ledger> {
+        self.ledger.borrow()
+    }
+
+    /// Updates the [conservation](crate::conservation) ledger.
+    #[cfg(feature = "debug_conservation")]
+    pub(crate) fn with_ledger(&self, f: impl FnOnce(&mut Ledger)) {
+        f(&mut self.ledger.borrow_mut());
+    }
+
+    /// Starts logging why time passes, keeping the last `capacity` [events](crate::events).
+    /// Does nothing if the log is already started.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn start_event_log(&mut self, capacity: usize) {
+        if self.events.is_none() {
+            self.events = Some(EventLog::new(capacity));
         }
     }
 
+    /// The [events](crate::events) logged so far, if [`start_event_log`](Tick::start_event_log) was called.
+    pub const fn event_log(&self) -> Option<&EventLog> {
+        self.events.as_ref()
+    }
+
+    /// Stops collecting statistics, returning what was collected.
+    pub(crate) fn stop_collecting_stats(&mut self) -> Option<Stats> {
+        let ticks = self.tick - self.start;
+        self.stats
+            .take()
+            .map(|stats| stats.into_inner().finish(ticks))
+    }
+
+    /// Disables logging for the rest of the game, regardless of [`log`](Tick::log).
+    #[cfg(feature = "std")]
+    pub(crate) const fn silence(&mut self) {
+        self.quiet = true;
+    }
+
     /// Sets whether or not to log on tick advancement.
+    /// Has no effect if the game was started with the [`QUIET_ENV_VAR`](crate::QUIET_ENV_VAR) environment variable set.
     pub const fn log(&mut self, log: bool) {
         self.log = log;
     }
 
+    /// Sets how the tick number is written when logging, e.g. [`AmountStyle::Grouped`] for `Tick 1,234,567`.
+    /// Defaults to [`AmountStyle::Exact`], like [`Display`].
+    pub const fn log_style(&mut self, style: AmountStyle) {
+        self.log_style = style;
+    }
+
+    /// Calls `f` once when time reaches tick `at`, so code can act at a set time without checking the tick itself,
+    /// e.g. to switch a machine's recipe at tick 500.
+    ///
+    /// The callback sees the tick at exactly `at`, even if a single [`advance_by`](Tick::advance_by) jumps past it.
+    /// Callbacks due during the same jump are called in order of their tick, and ones due at the same tick in the order they were scheduled.
+    /// If `at` isn't in the future, `f` is called right away.
+    pub fn schedule(&mut self, at: u64, f: impl FnOnce(&Tick) + 'static) {
+        if at <= self.tick {
+            f(self);
+            return;
+        }
+        self.scheduled
+            .insert((at, self.scheduled_count), ScheduledCallback(Box::new(f)));
+        self.scheduled_count += 1;
+    }
+
     /// Advances the game by one tick.
     ///
     /// By default prints the current tick number to the console.
@@ -52,9 +366,67 @@ impl Tick {
     /// By default prints the current tick number to the console.
     /// If you want to disable this, use the [`log`](Tick::log) method.
     pub fn advance_by(&mut self, ticks: u64) {
-        self.tick = self.tick.checked_add(ticks).expect("Tick overflow. Well done you've found an exploit! Or you would have if `https://github.com/albertsgarde/rustorio/issues/3` hadn't beaten you to it!");
-        if self.log {
-            println!("{self}");
+        self.advance_because(ticks, AdvanceCause::Advance);
+    }
+
+    /// Like [`advance_by`](Tick::advance_by), but logs `cause` as the reason time passed in the [event log](crate::events).
+    /// For mods, to tell apart the time their own actions take, like mining by hand.
+    pub fn advance_because(&mut self, ticks: u64, cause: AdvanceCause) {
+        self.log_event(self.tick, ticks, cause);
+        self.step(ticks);
+    }
+
+    /// Logs that `ticks` ticks pass from tick `start` because of `cause`, if the event log is started.
+    fn log_event(&mut self, start: u64, ticks: u64, cause: AdvanceCause) {
+        if let Some(events) = &mut self.events
+            && ticks > 0
+        {
+            events.push(AdvanceEvent {
+                tick: start,
+                ticks,
+                cause,
+            });
+        }
+    }
+
+    /// Advances the game by `ticks` without logging an event.
+    fn step(&mut self, ticks: u64) {
+        if ticks > 0 {
+            self.record(self.tick, ActionKind::Advance { ticks });
+        }
+        let target = self.tick.checked_add(ticks).expect("Tick overflow. Well done you've found an exploit! Or you would have if `https://github.com/albertsgarde/rustorio/issues/3` hadn't beaten you to it!");
+        self.pass_to(target);
+        #[cfg(feature = "std")]
+        if self.log && !self.quiet {
+            println!("Tick {}", self.log_style.format(self.tick));
+        }
+    }
+
+    /// Moves time on to `target`, calling the callbacks due on the way.
+    fn pass_to(&mut self, target: u64) {
+        while let Some(entry) = self.scheduled.first_entry()
+            && entry.key().0 <= target
+        {
+            let ((at, _), ScheduledCallback(f)) = entry.remove_entry();
+            self.tick = at;
+            f(self);
+        }
+        self.tick = target;
+    }
+
+    /// Whether any time has passed since the game started.
+    #[cfg(feature = "std")]
+    pub(crate) const fn has_advanced(&self) -> bool {
+        self.tick != self.start
+    }
+
+    /// Moves a game on to tick `target` as if it had already been played up to there, e.g. in the game a save was made in.
+    /// Unlike advancing, nothing is recorded or logged, since the time wasn't played in this game.
+    /// Does nothing if `target` isn't in the future.
+    #[cfg(feature = "std")]
+    pub(crate) fn resume_at(&mut self, target: u64) {
+        if target > self.tick {
+            self.pass_to(target);
         }
     }
 
@@ -80,8 +452,44 @@ impl Tick {
     {
         let start_tick = self.tick;
         while !condition(self) && self.tick - start_tick < max_ticks {
-            self.advance();
+            self.step(1);
         }
+        self.log_event(
+            start_tick,
+            self.tick - start_tick,
+            AdvanceCause::AdvanceUntil,
+        );
+        self.tick - start_tick < max_ticks
+    }
+
+    /// Like [`advance_until`](Tick::advance_until), but skips ahead to the next tick where something happens instead of checking every tick.
+    /// `next_event` returns how many ticks until the next thing that could make `condition` true,
+    /// e.g. the smallest [`ticks_until_next_output`](crate::machine::Machine::ticks_until_next_output) of the machines `condition` looks at,
+    /// or `None` if nothing will happen without the player stepping in.
+    ///
+    /// Gives the same result as [`advance_until`](Tick::advance_until) as long as `condition` can only change when `next_event` says something happens.
+    /// The tick is only logged once per skip.
+    pub fn advance_until_scheduled<F, N>(
+        &mut self,
+        mut condition: F,
+        mut next_event: N,
+        max_ticks: u64,
+    ) -> bool
+    where
+        F: FnMut(&Tick) -> bool,
+        N: FnMut(&Tick) -> Option<u64>,
+    {
+        let start_tick = self.tick;
+        while !condition(self) && self.tick - start_tick < max_ticks {
+            let remaining = max_ticks - (self.tick - start_tick);
+            let skip = next_event(self).map_or(remaining, |ticks| ticks.clamp(1, remaining));
+            self.step(skip);
+        }
+        self.log_event(
+            start_tick,
+            self.tick - start_tick,
+            AdvanceCause::AdvanceUntil,
+        );
         self.tick - start_tick < max_ticks
     }
 
@@ -98,13 +506,13 @@ impl From<&Tick> for u64 {
 }
 
 impl PartialOrd<u64> for &Tick {
-    fn partial_cmp(&self, other: &u64) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &u64) -> Option<core::cmp::Ordering> {
         Some(self.tick.cmp(other))
     }
 }
 
 impl PartialOrd<&Tick> for u64 {
-    fn partial_cmp(&self, other: &&Tick) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &&Tick) -> Option<core::cmp::Ordering> {
         Some(self.cmp(&other.tick))
     }
 }
@@ -122,7 +530,113 @@ impl PartialEq<&Tick> for u64 {
 }
 
 impl Display for Tick {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "Tick {}", self.tick)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, vec::Vec};
+
+    use super::*;
+
+    #[test]
+    fn scheduled_callbacks_fire_in_order_across_a_jump() {
+        let fired = Rc::new(RefCell::new(Vec::new()));
+        let mut tick = Tick::start();
+        tick.advance_by(10);
+        for (at, name) in [(500, "c"), (50, "a"), (500, "d"), (120, "b"), (2000, "e")] {
+            let fired = Rc::clone(&fired);
+            tick.schedule(at, move |tick| fired.borrow_mut().push((name, tick.cur())));
+        }
+        // Already past, so called right away.
+        {
+            let fired = Rc::clone(&fired);
+            tick.schedule(5, move |tick| fired.borrow_mut().push(("now", tick.cur())));
+        }
+        assert_eq!(*fired.borrow(), [("now", 10)]);
+
+        tick.advance_by(990);
+        assert_eq!(tick.cur(), 1000);
+        assert_eq!(
+            *fired.borrow(),
+            [("now", 10), ("a", 50), ("b", 120), ("c", 500), ("d", 500)]
+        );
+
+        tick.advance_to_tick(2000);
+        assert_eq!(fired.borrow().last(), Some(&("e", 2000)));
+        assert_eq!(fired.borrow().len(), 6);
+        assert!(tick.scheduled.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn resuming_skips_ahead_without_logging() {
+        let mut tick = Tick::start_at(100);
+        assert!(!tick.has_advanced());
+        tick.start_event_log(4);
+        tick.resume_at(150);
+        assert_eq!(tick.cur(), 150);
+        assert!(tick.has_advanced());
+        assert_eq!(tick.event_log().unwrap().events().count(), 0);
+
+        // Time never goes backwards.
+        tick.resume_at(120);
+        assert_eq!(tick.cur(), 150);
+    }
+
+    #[test]
+    fn event_log_records_causes_in_order() {
+        let mut tick = Tick::start();
+        tick.advance_by(5);
+        assert!(tick.event_log().is_none());
+
+        tick.start_event_log(4);
+        tick.advance();
+        tick.advance_by(0);
+        assert!(tick.advance_until(|tick| tick.cur() >= 20, 100));
+        tick.advance_because(3, AdvanceCause::HandMine);
+        tick.advance_to_tick(30);
+        let log = tick.event_log().unwrap();
+        assert_eq!(
+            log.events().copied().collect::<Vec<_>>(),
+            [
+                AdvanceEvent {
+                    tick: 5,
+                    ticks: 1,
+                    cause: AdvanceCause::Advance
+                },
+                AdvanceEvent {
+                    tick: 6,
+                    ticks: 14,
+                    cause: AdvanceCause::AdvanceUntil
+                },
+                AdvanceEvent {
+                    tick: 20,
+                    ticks: 3,
+                    cause: AdvanceCause::HandMine
+                },
+                AdvanceEvent {
+                    tick: 23,
+                    ticks: 7,
+                    cause: AdvanceCause::Advance
+                },
+            ]
+        );
+
+        // Only the last 4 events are kept.
+        tick.advance_until_scheduled(|_| false, |_| Some(5), 10);
+        let log = tick.event_log().unwrap();
+        assert_eq!(
+            log.causes().collect::<Vec<_>>(),
+            [
+                AdvanceCause::AdvanceUntil,
+                AdvanceCause::HandMine,
+                AdvanceCause::Advance,
+                AdvanceCause::AdvanceUntil
+            ]
+        );
+        assert_eq!(log.dropped(), 1);
+    }
+}
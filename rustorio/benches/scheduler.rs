@@ -0,0 +1,80 @@
+//! Compares stepping one tick at a time with skipping ahead using [`Tick::advance_until_scheduled`],
+//! on 100 furnaces over a million ticks. Run with `cargo bench --bench scheduler`.
+
+use std::time::Instant;
+
+use rustorio::{
+    Tick,
+    buildings::{Furnace, MachineGroup},
+    gamemodes::{SANDBOX_RESOURCE_AMOUNT, Sandbox},
+    recipes::IronSmelting,
+};
+use rustorio_engine::run_gamemode;
+
+type GameMode = Sandbox;
+
+type StartingResources = <GameMode as rustorio::GameMode>::StartingResources;
+type VictoryResources = <GameMode as rustorio::GameMode>::VictoryResources;
+
+const FURNACES: u32 = 100;
+const TICKS: u64 = 1_000_000;
+
+fn main() {
+    for (name, main) in [
+        ("stepped", stepped_main as fn(_, _) -> _),
+        ("scheduled", scheduled_main),
+    ] {
+        let start = Instant::now();
+        let ticks = run_gamemode::<GameMode>(main).ticks;
+        println!(
+            "{name}: {TICKS} ticks of {FURNACES} furnaces took {:?}",
+            start.elapsed()
+        );
+        assert_eq!(ticks, TICKS);
+    }
+}
+
+fn build(
+    tick: &Tick,
+    starting_resources: StartingResources,
+) -> MachineGroup<Furnace<IronSmelting>> {
+    let StartingResources {
+        mut iron_ore,
+        mut iron,
+        ..
+    } = starting_resources;
+    let mut furnaces = MachineGroup::new();
+    for _ in 0..FURNACES {
+        furnaces.push(Furnace::build(tick, IronSmelting, iron.bundle().unwrap()));
+    }
+    let mut ore = iron_ore.split_off(SANDBOX_RESOURCE_AMOUNT / 2).unwrap();
+    furnaces.fill_input::<0>(tick, &mut ore);
+    furnaces
+}
+
+/// Never true, so the whole budget is used.
+fn done(tick: &Tick, furnaces: &mut MachineGroup<Furnace<IronSmelting>>) -> bool {
+    furnaces
+        .buildings()
+        .iter_mut()
+        .any(|furnace| furnace.outputs(tick).0.amount() > SANDBOX_RESOURCE_AMOUNT)
+}
+
+fn stepped_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, VictoryResources) {
+    let mut furnaces = build(&tick, starting_resources);
+    tick.advance_until(|tick| done(tick, &mut furnaces), TICKS);
+    (tick, ())
+}
+
+fn scheduled_main(
+    mut tick: Tick,
+    starting_resources: StartingResources,
+) -> (Tick, VictoryResources) {
+    let furnaces = std::cell::RefCell::new(build(&tick, starting_resources));
+    tick.advance_until_scheduled(
+        |tick| done(tick, &mut furnaces.borrow_mut()),
+        |tick| furnaces.borrow_mut().ticks_until_next_output(tick),
+        TICKS,
+    );
+    (tick, ())
+}
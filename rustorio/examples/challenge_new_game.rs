@@ -0,0 +1,27 @@
+#![forbid(unsafe_code)]
+
+use rustorio::{self, Bundle, Tick, gamemodes::Challenge, resources::Point};
+
+type GameMode = Challenge;
+
+type StartingResources = <GameMode as rustorio::GameMode>::StartingResources;
+
+fn main() {
+    rustorio::play::<GameMode>(user_main);
+}
+
+#[allow(unused_variables)]
+#[allow(unused_mut)]
+fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Bundle<Point, 200>) {
+    // What you start with is set in the `[challenge]` table of `rustorio.toml`.
+    let StartingResources {
+        mut iron,
+        mut copper,
+        mut iron_territory,
+        mut copper_territory,
+        steel_technology,
+        guide,
+    } = starting_resources;
+
+    todo!("Return the `tick` and the victory resources to win the game!")
+}
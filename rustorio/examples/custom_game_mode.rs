@@ -0,0 +1,49 @@
+//! A community challenge defined with the [`rustorio_engine::game_mode`] macro instead of in the crate itself.
+//! There is no territory to mine from, so the starting bundles have to be crafted into circuits by hand.
+
+#![forbid(unsafe_code)]
+
+use rustorio::{
+    self, Bundle, HandRecipe, Resource, Tick,
+    recipes::{CopperWireRecipe, ElectronicCircuitRecipe},
+    resources::{Copper, ElectronicCircuit, Iron},
+};
+use rustorio_engine::game_mode;
+
+game_mode! {
+    /// Craft two electronic circuits from a handful of plates.
+    CircuitChallenge {
+        starting: CircuitChallengeStartingResources {
+            /// Iron for the circuits themselves.
+            iron: Bundle<Iron, 2>,
+            /// Copper to draw into wire.
+            copper: Bundle<Copper, 2>,
+        },
+        victory: Bundle<ElectronicCircuit, 2>,
+    }
+}
+
+type GameMode = CircuitChallenge;
+
+type StartingResources = <GameMode as rustorio::GameMode>::StartingResources;
+
+fn main() {
+    rustorio::play::<GameMode>(user_main);
+}
+
+fn user_main(
+    mut tick: Tick,
+    starting_resources: StartingResources,
+) -> (Tick, Bundle<ElectronicCircuit, 2>) {
+    let StartingResources { iron, copper } = starting_resources;
+
+    let (iron1, iron2) = iron.split::<1, 1>();
+    let (copper1, copper2) = copper.split::<1, 1>();
+    let mut circuits = Resource::new_empty();
+    for (iron, copper) in [(iron1, copper1), (iron2, copper2)] {
+        let (wire,) = CopperWireRecipe::craft(&mut tick, (copper,));
+        let (circuit,) = ElectronicCircuitRecipe::craft(&mut tick, (iron, wire));
+        circuits += circuit;
+    }
+    (tick, circuits.bundle().unwrap())
+}
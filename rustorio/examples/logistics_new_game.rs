@@ -0,0 +1,25 @@
+#![forbid(unsafe_code)]
+
+use rustorio::{self, Bundle, Tick, gamemodes::Logistics, resources::Point};
+
+type GameMode = Logistics;
+
+type StartingResources = <GameMode as rustorio::GameMode>::StartingResources;
+
+fn main() {
+    rustorio::play::<GameMode>(user_main);
+}
+
+#[allow(unused_variables)]
+#[allow(unused_mut)]
+fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Bundle<Point, 200>) {
+    let StartingResources {
+        iron,
+        mut iron_territory,
+        mut copper_territory,
+        steel_technology,
+        guide,
+    } = starting_resources;
+
+    todo!("Return the `tick` and the victory resources to win the game!")
+}
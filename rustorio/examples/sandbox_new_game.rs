@@ -0,0 +1,42 @@
+#![forbid(unsafe_code)]
+
+use rustorio::{self, Tick, gamemodes::Sandbox};
+
+type GameMode = Sandbox;
+
+type StartingResources = <GameMode as rustorio::GameMode>::StartingResources;
+
+fn main() {
+    rustorio::play::<GameMode>(user_main);
+}
+
+#[allow(unused_variables)]
+#[allow(unused_mut)]
+#[allow(clippy::missing_const_for_fn)]
+fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, ()) {
+    let StartingResources {
+        mut iron_ore,
+        mut copper_ore,
+        mut iron,
+        mut copper,
+        mut steel,
+        mut copper_wire,
+        mut electronic_circuit,
+        mut red_science,
+        mut green_science,
+        mut blue_science,
+        mut iron_territory,
+        mut copper_territory,
+        steel_smelting,
+        point_recipe,
+        blue_science_recipe,
+        steel_furnace_unlock,
+        electric_furnace_unlock,
+        steam_iron_smelting,
+        offshore_pump_unlock,
+        steel_technology,
+    } = starting_resources;
+
+    // There is no goal in the sandbox. Experiment freely, and return when you're done.
+    (tick, ())
+}
@@ -18,6 +18,7 @@ fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Bu
         mut iron_territory,
         mut copper_territory,
         steel_technology,
+        guide,
     } = starting_resources;
 
     todo!("Return the `tick` and the victory resources to win the game!")
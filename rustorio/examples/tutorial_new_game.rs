@@ -1,6 +1,6 @@
 #![forbid(unsafe_code)]
 
-use rustorio::{self, Bundle, Tick, gamemodes::Tutorial, resources::Copper};
+use rustorio::{self, AtLeast, Tick, gamemodes::Tutorial, resources::Copper};
 
 type GameMode = Tutorial;
 
@@ -12,7 +12,7 @@ fn main() {
 
 #[allow(unused_variables)]
 #[allow(unused_mut)]
-fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Bundle<Copper, 4>) {
+fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, AtLeast<Copper, 4>) {
     tick.log(true);
 
     let StartingResources {
@@ -24,5 +24,6 @@ fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Bu
 
     // To start, run the game using `rustorio play tutorial` (or whatever this save is called), and follow the hint.
     // If you get stuck, try giving the guide other objects you've found, like the `tick` object.
+    // You can also ask it what to do next, e.g. `println!("{}", guide.next_step(&guide.state().with(&iron)));`.
     guide.hint(iron)
 }
@@ -1,13 +1,27 @@
 use std::{
+    collections::BTreeMap,
+    env,
     fmt::Display,
-    fs, io,
+    fs,
+    io::{self, BufRead, IsTerminal, Read, Write},
     path::{Path, PathBuf},
-    process::{Command, ExitStatus},
+    process::{Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Context, Result, bail};
-use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use dialoguer::Confirm;
+use rustorio::{
+    Technology,
+    recipes::{RecipeVisitor, visit_recipes},
+    research::{TechnologyVisitor, visit_technologies},
+};
+use rustorio_engine::{
+    OUTPUT_ENV_VAR, QUIET_ENV_VAR, recipe::RecipeEx, research::PrerequisiteNames,
+};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 // Macro to build paths to game bin files relative to workspace root
@@ -22,9 +36,35 @@ macro_rules! game_bin_file {
     };
 }
 
+/// The toolchain this version of Rustorio is built with, written into new projects.
 const RUST_TOOLCHAIN: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rust-toolchain.toml"));
 
+/// Written into new projects as `rustorio.toml`, which marks the project root and configures the `Challenge` game mode.
+const RUSTORIO_TOML: &str = "\
+# Marks the root of a Rustorio project.
+# The optional `[challenge]` table sets the parameters of saves in the `challenge` game mode, see
+# https://docs.rs/rustorio/latest/rustorio/gamemodes/struct.ChallengeConfig.html
+";
+
+/// The channel of [`RUST_TOOLCHAIN`], e.g. `nightly-2025-11-23`.
+fn toolchain_channel() -> &'static str {
+    parse_toolchain_channel(RUST_TOOLCHAIN).expect("rust-toolchain.toml has no channel")
+}
+
+/// Extracts the channel from the contents of a `rust-toolchain.toml` file.
+fn parse_toolchain_channel(contents: &str) -> Option<&str> {
+    contents.lines().find_map(|line| {
+        Some(
+            line.trim()
+                .strip_prefix("channel")?
+                .trim_start_matches([' ', '='])
+                .trim()
+                .trim_matches('"'),
+        )
+    })
+}
+
 #[derive(Error, Debug)]
 pub enum RunCommandError {
     CommandFailed(ExitStatus),
@@ -44,9 +84,19 @@ impl Display for RunCommandError {
 
 pub trait RunCommandExt {
     fn run(&mut self) -> Result<(), RunCommandError>;
+
+    /// Adds `--release` to a cargo invocation if `release` is set.
+    fn release(&mut self, release: bool) -> &mut Self;
 }
 
 impl RunCommandExt for Command {
+    fn release(&mut self, release: bool) -> &mut Self {
+        if release {
+            self.arg("--release");
+        }
+        self
+    }
+
     fn run(&mut self) -> Result<(), RunCommandError> {
         let status = self.status().map_err(RunCommandError::IoError)?;
         if !status.success() {
@@ -69,6 +119,15 @@ impl Cli {
             Commands::Setup(args) => args.run(),
             Commands::NewGame(args) => args.run(),
             Commands::Play(args) => args.run(),
+            Commands::Benchmark(args) => args.run(),
+            Commands::Test(args) => args.run(),
+            Commands::List(args) => args.run(),
+            Commands::Delete(args) => args.run(),
+            Commands::Graph(args) => args.run(),
+            Commands::Score(args) => args.run(),
+            Commands::ModNew(args) => args.run(),
+            Commands::Compare(args) => args.run(),
+            Commands::Info(args) => args.run(),
         }
     }
 }
@@ -84,7 +143,35 @@ enum Commands {
     ///
     /// For example, in most Rustorio folders, there'll be a `tutorial` save game.
     /// To run it, use `rustorio play tutorial`.
+    /// With the `watch` feature enabled, `--watch` re-runs the save game whenever it changes.
     Play(PlayArgs),
+    /// Run an existing save game without logging and report how many ticks it took to win.
+    /// Can only be run in a Rustorio project.
+    Benchmark(BenchmarkArgs),
+    /// Check that an existing save game compiles and wins, exiting with an error if it doesn't.
+    /// Can only be run in a Rustorio project.
+    ///
+    /// Useful for checking solutions in CI.
+    Test(TestArgs),
+    /// List the save games in the current Rustorio project.
+    List(ListArgs),
+    /// Delete an existing save game after asking for confirmation.
+    /// Can only be run in a Rustorio project.
+    Delete(DeleteArgs),
+    /// Write a Graphviz `.dot` file of the recipes and technologies in the game.
+    Graph(GraphArgs),
+    /// Run an existing save game and record its tick count in `rustorio_scores.json`, reporting personal bests.
+    /// Can only be run in a Rustorio project.
+    Score(ScoreArgs),
+    /// Generate boilerplate for a new recipe or resource in a mod crate.
+    ///
+    /// Appends to the target file if it exists, and creates it otherwise.
+    ModNew(ModNewArgs),
+    /// Run two save games one after the other and compare how many ticks they take to win.
+    /// Can only be run in a Rustorio project.
+    Compare(CompareArgs),
+    /// Print a summary of the current Rustorio project, useful when reporting bugs.
+    Info(InfoArgs),
 }
 
 #[derive(Args)]
@@ -93,6 +180,9 @@ pub struct SetupArgs {
     path: PathBuf,
     #[clap(long, default_value_t = true)]
     include_tutorial: bool,
+    /// Initialize a git repository in the new project, even inside an existing repository.
+    #[clap(long)]
+    git: bool,
 }
 
 impl SetupArgs {
@@ -118,9 +208,13 @@ impl SetupArgs {
 
         println!("Setting up Rustorio at '{}'...", canonical_path.display());
         // Run `cargo new --bin self.name` with the same `cargo` binary as used to build this CLI
-        Command::new(env!("CARGO"))
-            .arg("new")
-            .arg("--bin")
+        let mut cargo_new = Command::new(env!("CARGO"));
+        cargo_new.arg("new").arg("--bin");
+        // Only create a repository when asked to.
+        cargo_new
+            .arg("--vcs")
+            .arg(if self.git { "git" } else { "none" });
+        cargo_new
             .arg("--name")
             .arg("rustorio-game")
             .arg("rustorio")
@@ -128,16 +222,31 @@ impl SetupArgs {
             .run()
             .context("Failed to create new Rustorio project")?;
         let path = canonical_path.join("rustorio");
+        // Pin the exact version of this CLI, as the game relies on nightly features
+        // that only work with the toolchain written below.
         Command::new(env!("CARGO"))
             .arg("add")
-            .arg("rustorio")
+            .arg(format!("rustorio@={}", env!("CARGO_PKG_VERSION")))
             .arg("--no-default-features")
             .current_dir(&path)
             .run()
             .context("Failed to add Rustorio as a dependency")?;
-        fs::write(path.join("rustorio.toml"), "").context("Failed to create rustorio.toml")?;
+        fs::write(path.join("rustorio.toml"), RUSTORIO_TOML)
+            .context("Failed to create rustorio.toml")?;
         fs::write(path.join("rust-toolchain.toml"), RUST_TOOLCHAIN)
             .context("Failed to create rust-toolchain file")?;
+        // Rustorio doesn't compile on stable, so check that the pinned toolchain is usable.
+        // Use a raw "rustc" to allow the toolchain file to take effect.
+        if let Err(err) = Command::new("rustc")
+            .arg("--version")
+            .current_dir(&path)
+            .run()
+        {
+            eprintln!(
+                "Warning: Could not run the Rust toolchain Rustorio needs ({err}). Rustorio only compiles on the '{channel}' toolchain. Install it with 'rustup toolchain install {channel}' before playing.",
+                channel = toolchain_channel()
+            );
+        }
         let save_path = path.join("src").join("bin");
         fs::create_dir_all(&save_path).context("Failed to create save directory")?;
         if self.include_tutorial {
@@ -149,6 +258,16 @@ impl SetupArgs {
                 .context("Failed to create tutorial/main.rs")?;
         }
         fs::remove_file(path.join("src").join("main.rs")).context("Failed to remove main.rs")?;
+        // Cargo only writes a .gitignore along with a new repository.
+        let gitignore_path = path.join(".gitignore");
+        if gitignore_path.exists() {
+            let mut gitignore = fs::OpenOptions::new()
+                .append(true)
+                .open(&gitignore_path)
+                .context("Failed to open .gitignore")?;
+            write!(gitignore, "/{SCORES_FILE}\n/rustorio_scores.lock\n")
+                .context("Failed to update .gitignore")?;
+        }
         println!(
             "Rustorio set up at '{}'! Open the directory in your favorite Rust editor to get started.",
             path.display()
@@ -157,17 +276,62 @@ impl SetupArgs {
     }
 }
 
-#[derive(ValueEnum, Clone)]
+/// The type a save game's `type GameMode = ...;` alias names, without module path or generic arguments,
+/// e.g. `Standard` for `type GameMode = rustorio::gamemodes::Standard<Hard>;`.
+fn game_mode_type(save_file: &str) -> Option<&str> {
+    let path = save_file.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("type GameMode")?
+            .trim_start()
+            .strip_prefix('=')
+    })?;
+    let path = &path[..path.find(['<', ';'])?];
+    path.rsplit("::").next().map(str::trim)
+}
+
+#[derive(ValueEnum, Clone, PartialEq, Eq)]
 pub enum GameMode {
     Tutorial,
     Standard,
+    Sandbox,
+    Speedrun,
+    Hardcore,
+    Logistics,
+    Seeded,
+    Challenge,
 }
 
 impl GameMode {
+    const ALL: [GameMode; 8] = [
+        GameMode::Tutorial,
+        GameMode::Standard,
+        GameMode::Sandbox,
+        GameMode::Speedrun,
+        GameMode::Hardcore,
+        GameMode::Logistics,
+        GameMode::Seeded,
+        GameMode::Challenge,
+    ];
+
+    /// Guesses the game mode of a save game from the contents of its `main.rs`,
+    /// by comparing its `type GameMode = ...;` alias with the one in each game mode's [start file](GameMode::start_file).
+    pub fn detect(save_file: &str) -> Option<Self> {
+        let save_type = game_mode_type(save_file)?;
+        Self::ALL
+            .into_iter()
+            .find(|game_mode| game_mode_type(&game_mode.start_file()) == Some(save_type))
+    }
+
     pub const fn as_str(&self) -> &str {
         match self {
             GameMode::Tutorial => "tutorial",
             GameMode::Standard => "standard",
+            GameMode::Sandbox => "sandbox",
+            GameMode::Speedrun => "speedrun",
+            GameMode::Hardcore => "hardcore",
+            GameMode::Logistics => "logistics",
+            GameMode::Seeded => "seeded",
+            GameMode::Challenge => "challenge",
         }
     }
 
@@ -175,9 +339,16 @@ impl GameMode {
         match self {
             GameMode::Tutorial => include_str!(game_bin_file!("tutorial")),
             GameMode::Standard => include_str!(game_bin_file!("standard")),
+            GameMode::Sandbox => include_str!(game_bin_file!("sandbox")),
+            GameMode::Speedrun => include_str!(game_bin_file!("speedrun")),
+            GameMode::Hardcore => include_str!(game_bin_file!("hardcore")),
+            GameMode::Logistics => include_str!(game_bin_file!("logistics")),
+            GameMode::Seeded => include_str!(game_bin_file!("seeded")),
+            GameMode::Challenge => include_str!(game_bin_file!("challenge")),
         }
         .replace("\n#[allow(unused_variables)]", "")
         .replace("\n#[allow(unused_mut)]", "")
+        .replace("\n#[allow(clippy::missing_const_for_fn)]", "")
     }
 }
 
@@ -196,6 +367,63 @@ fn find_rustorio_root() -> Result<Option<std::path::PathBuf>> {
     Ok(None)
 }
 
+/// Like [`find_rustorio_root`], but fails if not in a Rustorio project.
+fn require_rustorio_root() -> Result<PathBuf> {
+    if let Some(rustorio_root) =
+        find_rustorio_root().context("Failed while looking for Rustorio root")?
+    {
+        Ok(rustorio_root)
+    } else {
+        bail!(
+            "Can only run command in a Rustorio project. Please either navigate to a Rustorio project or run 'rustorio setup' first."
+        );
+    }
+}
+
+/// Finds the Rustorio root and checks that the given save game exists in it.
+fn find_save_game(save_name: &str) -> Result<PathBuf> {
+    let rustorio_root = require_rustorio_root()?;
+    let save_game_path = rustorio_root.join("src").join("bin").join(save_name);
+    if !save_game_path.exists() {
+        bail!("Save game '{}' does not exist.", save_name);
+    }
+    Ok(rustorio_root)
+}
+
+/// Names Cargo refuses to use for binaries, as they clash with its output directories.
+const RESERVED_SAVE_NAMES: [&str; 4] = ["build", "deps", "examples", "incremental"];
+
+/// Trims the save game name and checks that it can be used as a Cargo binary name,
+/// so that `cargo run --bin <name>` can run it.
+fn sanitize_save_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("Save game name cannot be empty.");
+    }
+    if name
+        .chars()
+        .any(|c| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+    {
+        let suggestion: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        bail!(
+            "Save game name '{name}' is invalid. Only ASCII letters, digits, '_' and '-' are allowed. Try '{suggestion}' instead."
+        );
+    }
+    if RESERVED_SAVE_NAMES.contains(&name) {
+        bail!("Save game name '{name}' is reserved by Cargo. Please choose another name.");
+    }
+    Ok(name.to_string())
+}
+
 #[derive(Args)]
 pub struct NewGameArgs {
     #[clap()]
@@ -206,6 +434,7 @@ pub struct NewGameArgs {
 
 impl NewGameArgs {
     pub fn run(&self) -> Result<()> {
+        let name = self.name.as_deref().map(sanitize_save_name).transpose()?;
         let rustorio_root = match find_rustorio_root()
             .context("Failed while looking for Rustorio root")?
         {
@@ -221,6 +450,7 @@ impl NewGameArgs {
                     let setup_args = SetupArgs {
                         path: PathBuf::from("./"),
                         include_tutorial: false,
+                        git: false,
                     };
                     setup_args
                         .run()
@@ -240,7 +470,7 @@ impl NewGameArgs {
         fs::create_dir_all(saves_dir.as_path()).context("Failed to create saves directory")?;
         let start_file = self.game_mode.start_file();
         let (save_game_path, save_game_name) = {
-            let save_game_name = self.name.clone().unwrap_or_else(|| {
+            let save_game_name = name.unwrap_or_else(|| {
                 println!("No save game name specified, generating one based on game mode...");
                 let mut save_game_name = self.game_mode.as_str().to_string();
                 while saves_dir.join(save_game_name.as_str()).exists() {
@@ -257,6 +487,22 @@ impl NewGameArgs {
         fs::create_dir_all(&save_game_path).context("Failed to create save game directory")?;
         fs::write(save_game_path.join("main.rs").as_path(), start_file)
             .context("Failed to create save game file")?;
+        if self.game_mode == GameMode::Challenge {
+            // Projects depend on Rustorio without default features, which leaves out the challenge game mode.
+            Command::new(env!("CARGO"))
+                .arg("add")
+                .arg("rustorio")
+                .arg("--features")
+                .arg("challenge")
+                .current_dir(rustorio_root)
+                .run()
+                .context("Failed to enable the challenge game mode")?;
+        }
+        let manifest_path = rustorio_root.join("Cargo.toml");
+        let manifest = fs::read_to_string(&manifest_path).context("Failed to read Cargo.toml")?;
+        if let Some(manifest) = add_bin_target(&manifest, &save_game_name) {
+            fs::write(&manifest_path, manifest).context("Failed to add save game to Cargo.toml")?;
+        }
         println!(
             "New game '{}' with game mode '{}' created at {}! For help getting started, go to https://albertsgarde.github.io/rustorio",
             save_game_name,
@@ -267,40 +513,1075 @@ impl NewGameArgs {
     }
 }
 
+/// Cargo turns `src/bin/<name>/main.rs` into a binary called `<name>` unless `autobins` is disabled.
+/// In that case, returns the manifest with an explicit `[[bin]]` target added for the save game,
+/// so that `cargo run --bin <name>` keeps working.
+fn add_bin_target(manifest: &str, save_name: &str) -> Option<String> {
+    let autobins_disabled = manifest
+        .lines()
+        .any(|line| line.split_whitespace().collect::<String>() == "autobins=false");
+    let path = format!("src/bin/{save_name}/main.rs");
+    if !autobins_disabled || manifest.contains(&format!("\"{path}\"")) {
+        return None;
+    }
+    let mut manifest = manifest.to_string();
+    if !manifest.ends_with('\n') {
+        manifest.push('\n');
+    }
+    manifest.push_str(&format!(
+        "\n[[bin]]\nname = \"{save_name}\"\npath = \"{path}\"\n"
+    ));
+    Some(manifest)
+}
+
 #[derive(Args)]
 pub struct PlayArgs {
     /// The name of the save game to run.
     save_name: String,
+    /// Build the save game with optimizations. Slower to compile, but much faster for long games.
+    #[clap(long)]
+    release: bool,
+    /// Re-run the save game every time its source changes.
+    #[cfg(feature = "watch")]
+    #[clap(long)]
+    watch: bool,
+    /// Set an environment variable for the save game, as `KEY=VALUE`. Can be repeated.
+    /// It is up to the save game to read and interpret it.
+    #[clap(long = "env", value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    env: Vec<(String, String)>,
+    /// Arguments passed to the save game after `--`, e.g. `rustorio play my_save -- 4 2`.
+    /// It is up to the save game to read and interpret them.
+    #[clap(last = true)]
+    args: Vec<String>,
+    /// Write the save game's output, such as the tick log, to this file instead of the terminal.
+    /// The victory message is still printed.
+    #[clap(long)]
+    log_file: Option<PathBuf>,
+}
+
+fn parse_env_var(env_var: &str) -> Result<(String, String)> {
+    let Some((key, value)) = env_var.split_once('=') else {
+        bail!("Expected 'KEY=VALUE', found '{env_var}'");
+    };
+    if key.is_empty() {
+        bail!("Environment variable name cannot be empty");
+    }
+    Ok((key.to_string(), value.to_string()))
 }
 
 impl PlayArgs {
     pub fn run(&self) -> Result<()> {
-        let rustorio_root = if let Some(rustorio_root) =
-            find_rustorio_root().context("Failed while looking for Rustorio root")?
-        {
-            rustorio_root
+        let rustorio_root = find_save_game(&self.save_name)?;
+        #[cfg(feature = "watch")]
+        if self.watch {
+            return self.watch(&rustorio_root);
+        }
+        self.run_once(&rustorio_root)
+    }
+
+    fn run_once(&self, rustorio_root: &Path) -> Result<()> {
+        // Keep cargo's colors even though its output is piped through us.
+        let color = if io::stderr().is_terminal() {
+            "--color=always"
         } else {
-            bail!(
-                "Can only run command in a Rustorio project. Please either navigate to a Rustorio project or run 'rustorio setup' first."
-            );
+            "--color=auto"
         };
-        let save_game_path = rustorio_root.join("src").join("bin").join(&self.save_name);
-        if !save_game_path.exists() {
-            bail!("Save game '{}' does not exist.", self.save_name);
-        }
         // Use a raw "cargo" to allow the toolchain file to take effect.
-        Command::new("cargo")
+        let mut command = Command::new("cargo");
+        command
             .arg("run")
+            .arg(color)
+            .release(self.release)
             .arg("--bin")
             .arg(&self.save_name)
+            .arg("--")
+            .args(&self.args)
+            .envs(self.env.iter().cloned())
             .current_dir(rustorio_root)
-            .run()
-            .context("Failed to run Rustorio game")?;
+            .stderr(Stdio::piped());
+        // Cargo hides its progress bar when stderr isn't a terminal, which it never is through the pipe,
+        // so ask for it unless the player configured it themselves.
+        if io::stderr().is_terminal() && env::var_os("CARGO_TERM_PROGRESS_WHEN").is_none() {
+            let width = env::var("COLUMNS").unwrap_or_else(|_| "80".to_string());
+            command
+                .env("CARGO_TERM_PROGRESS_WHEN", "always")
+                .env("CARGO_TERM_PROGRESS_WIDTH", width);
+        }
+        let mut log = match &self.log_file {
+            Some(log_file) => {
+                command.stdout(Stdio::piped());
+                Some(io::BufWriter::new(
+                    fs::File::create(log_file)
+                        .with_context(|| format!("Failed to create '{}'", log_file.display()))?,
+                ))
+            }
+            None => None,
+        };
+        let mut child = command.spawn().context("Failed to run Rustorio game")?;
+
+        // Echo cargo's output as it arrives, including progress updates that don't end in a newline,
+        // but keep a copy to look for known compiler errors.
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let stderr_reader = thread::spawn(move || {
+            let mut captured = Vec::new();
+            let mut buffer = [0; 4096];
+            while let Ok(read @ 1..) = stderr.read(&mut buffer) {
+                let mut terminal = io::stderr().lock();
+                let _ = terminal.write_all(&buffer[..read]);
+                let _ = terminal.flush();
+                captured.extend_from_slice(&buffer[..read]);
+            }
+            String::from_utf8_lossy(&captured).into_owned()
+        });
+        if let Some(log) = &mut log {
+            let stdout = child.stdout.take().expect("stdout is piped");
+            for line in io::BufReader::new(stdout).lines() {
+                let line = line.context("Failed to read game output")?;
+                writeln!(log, "{line}").context("Failed to write log file")?;
+                if parse_victory(&line).is_some() || line.starts_with("You won in ") {
+                    println!("{line}");
+                }
+            }
+            log.flush().context("Failed to write log file")?;
+        }
+        let status = child.wait().context("Failed to run Rustorio game")?;
+        let stderr = stderr_reader.join().expect("Error reader thread panicked");
+
+        if !status.success() {
+            if let Some(hint) = compile_error_hint(&stderr) {
+                eprintln!("\nHint: {hint}");
+            }
+            return Err(RunCommandError::CommandFailed(status))
+                .context("Failed to run Rustorio game");
+        }
+        if let Some(log_file) = &self.log_file {
+            println!("Game output written to {}.", log_file.display());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "watch")]
+    fn watch(&self, rustorio_root: &Path) -> Result<()> {
+        use notify::{RecursiveMode, Watcher};
+
+        let save_game_path = rustorio_root.join("src").join("bin").join(&self.save_name);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).context("Failed to create file watcher")?;
+        watcher
+            .watch(&save_game_path, RecursiveMode::Recursive)
+            .context("Failed to watch save game directory")?;
+        loop {
+            if let Err(err) = self.run_once(rustorio_root) {
+                eprintln!("{err:#}");
+            }
+            println!(
+                "---------- Waiting for changes to '{}' ----------",
+                self.save_name
+            );
+            loop {
+                let event = receiver
+                    .recv()
+                    .context("File watcher stopped")?
+                    .context("Failed to watch save game directory")?;
+                if !event.kind.is_access() {
+                    break;
+                }
+            }
+            // Editors often write several times when saving, so let the changes settle before rebuilding.
+            thread::sleep(Duration::from_millis(200));
+            while receiver.try_recv().is_ok() {}
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct ListArgs {}
+
+/// Finds the save games in the project, sorted by name, along with their detected game modes.
+fn list_save_games(rustorio_root: &Path) -> Result<Vec<(String, Option<GameMode>)>> {
+    let saves_dir = rustorio_root.join("src").join("bin");
+    let mut saves = Vec::new();
+    if saves_dir.exists() {
+        for entry in fs::read_dir(&saves_dir).context("Failed to read saves directory")? {
+            let save_file = entry
+                .context("Failed to read saves directory entry")?
+                .path()
+                .join("main.rs");
+            if let Ok(contents) = fs::read_to_string(&save_file) {
+                let name = save_file
+                    .parent()
+                    .and_then(Path::file_name)
+                    .expect("Save file is inside a save directory")
+                    .to_string_lossy()
+                    .into_owned();
+                saves.push((name, GameMode::detect(&contents)));
+            }
+        }
+    }
+    saves.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(saves)
+}
+
+impl ListArgs {
+    pub fn run(&self) -> Result<()> {
+        let rustorio_root = require_rustorio_root()?;
+        let saves = list_save_games(&rustorio_root)?;
+        if saves.is_empty() {
+            println!("No save games found. Create one with 'rustorio new-game'.");
+            return Ok(());
+        }
+        for (name, game_mode) in saves {
+            let game_mode = game_mode.as_ref().map_or("unknown", GameMode::as_str);
+            println!("{name} ({game_mode})");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct InfoArgs {}
+
+impl InfoArgs {
+    pub fn run(&self) -> Result<()> {
+        let rustorio_root = require_rustorio_root()?;
+        let saves = list_save_games(&rustorio_root)?;
+        let toolchain = fs::read_to_string(rustorio_root.join("rust-toolchain.toml")).ok();
+        let toolchain = toolchain.as_deref().and_then(parse_toolchain_channel);
+        println!("Project root: {}", rustorio_root.display());
+        println!("Toolchain:    {}", toolchain.unwrap_or("not pinned"));
+        println!("CLI version:  {}", env!("CARGO_PKG_VERSION"));
+        println!("Save games:   {}", saves.len());
+        for game_mode in GameMode::ALL {
+            let count = saves
+                .iter()
+                .filter(|(_, mode)| mode.as_ref() == Some(&game_mode))
+                .count();
+            println!("  {:<10}  {count}", game_mode.as_str());
+        }
+        let unknown = saves.iter().filter(|(_, mode)| mode.is_none()).count();
+        if unknown > 0 {
+            println!("  {:<10}  {unknown}", "unknown");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct DeleteArgs {
+    /// The name of the save game to delete.
+    save_name: String,
+    /// Delete without asking for confirmation.
+    #[clap(long, short)]
+    yes: bool,
+}
+
+impl DeleteArgs {
+    pub fn run(&self) -> Result<()> {
+        let rustorio_root = find_save_game(&self.save_name)?;
+        let saves_dir = rustorio_root
+            .join("src")
+            .join("bin")
+            .canonicalize()
+            .context("Failed to canonicalize saves directory")?;
+        let save_game_path = saves_dir
+            .join(&self.save_name)
+            .canonicalize()
+            .context("Failed to canonicalize save game path")?;
+        if save_game_path.parent() != Some(saves_dir.as_path()) {
+            bail!(
+                "'{}' is not a save game in '{}'.",
+                self.save_name,
+                saves_dir.display()
+            );
+        }
+        let delete = self.yes
+            || Confirm::new()
+                .with_prompt(format!(
+                    "Are you sure you want to delete save game '{}'? This cannot be undone.",
+                    self.save_name
+                ))
+                .default(false)
+                .interact()
+                .context("Failed to confirm deletion")?;
+        if !delete {
+            println!("Save game '{}' was not deleted.", self.save_name);
+            return Ok(());
+        }
+        fs::remove_dir_all(&save_game_path).context("Failed to delete save game directory")?;
+        println!(
+            "Deleted save game '{}' at {}.",
+            self.save_name,
+            save_game_path.display()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct BenchmarkArgs {
+    /// The name of the save game to benchmark.
+    save_name: String,
+    /// Give up if the save game has not won after this many seconds.
+    #[clap(long, default_value_t = 600)]
+    timeout: u64,
+    /// Build the save game with optimizations.
+    #[clap(long)]
+    release: bool,
+}
+
+impl BenchmarkArgs {
+    pub fn run(&self) -> Result<()> {
+        let rustorio_root = find_save_game(&self.save_name)?;
+        // Build first so compilation doesn't count towards the wall-clock time.
+        let build = Command::new("cargo")
+            .arg("build")
+            .release(self.release)
+            .arg("--bin")
+            .arg(&self.save_name)
+            .arg("--message-format=json-render-diagnostics")
+            .current_dir(&rustorio_root)
+            .stderr(Stdio::inherit())
+            .output()
+            .context("Failed to build Rustorio game")?;
+        if !build.status.success() {
+            return Err(RunCommandError::CommandFailed(build.status))
+                .context("Failed to build Rustorio game");
+        }
+        let binary = built_executable(&String::from_utf8_lossy(&build.stdout), &self.save_name)
+            .context("Cargo did not report the built save game")?;
+
+        let start = Instant::now();
+        let mut child = Command::new(&binary)
+            .env(QUIET_ENV_VAR, "1")
+            .env(OUTPUT_ENV_VAR, "json")
+            .current_dir(&rustorio_root)
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start '{}'", binary.display()))?;
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let reader = thread::spawn(move || {
+            let mut output = String::new();
+            stdout.read_to_string(&mut output).map(|_| output)
+        });
+        let timeout = Duration::from_secs(self.timeout);
+        let status = loop {
+            if let Some(status) = child
+                .try_wait()
+                .context("Failed to wait for Rustorio game")?
+            {
+                break status;
+            }
+            if start.elapsed() >= timeout {
+                child.kill().context("Failed to stop Rustorio game")?;
+                child.wait().context("Failed to wait for Rustorio game")?;
+                bail!(
+                    "Save game '{}' did not win within {} seconds.",
+                    self.save_name,
+                    self.timeout
+                );
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        let elapsed = start.elapsed();
+        let output = reader
+            .join()
+            .expect("Output reader thread panicked")
+            .context("Failed to read game output")?;
+        if !status.success() {
+            bail!(
+                "Save game '{}' failed with exit status: {}",
+                self.save_name,
+                status
+            );
+        }
+        let Victory { ticks, .. } = parse_victory(&output)
+            .with_context(|| format!("Save game '{}' exited without winning.", self.save_name))?;
+        println!(
+            "Save game '{}' won in {} ticks ({:.3}s wall-clock).",
+            self.save_name,
+            ticks,
+            elapsed.as_secs_f64()
+        );
         Ok(())
     }
 }
 
+/// Finds the executable of the `bin` target in the output of `cargo build --message-format=json`,
+/// which accounts for the target directory, profile and platform.
+fn built_executable(messages: &str, bin: &str) -> Option<PathBuf> {
+    messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-artifact")
+        .filter(|message| message["target"]["name"] == bin)
+        .find_map(|message| message["executable"].as_str().map(PathBuf::from))
+}
+
+#[derive(Args)]
+pub struct TestArgs {
+    /// The name of the save game to test.
+    save_name: String,
+    /// Fail if the save game takes more than this many ticks to win.
+    #[clap(long)]
+    max_ticks: Option<u64>,
+    /// Build the save game with optimizations.
+    #[clap(long)]
+    release: bool,
+}
+
+/// Runs a save game quietly and returns its exit status and, if it won, its [`Victory`].
+fn run_save_game(
+    rustorio_root: &Path,
+    save_name: &str,
+    release: bool,
+) -> Result<(ExitStatus, Option<Victory>)> {
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("--quiet")
+        .release(release)
+        .arg("--bin")
+        .arg(save_name)
+        .env(QUIET_ENV_VAR, "1")
+        .env(OUTPUT_ENV_VAR, "json")
+        .current_dir(rustorio_root)
+        .stderr(Stdio::inherit())
+        .output()
+        .context("Failed to run Rustorio game")?;
+    let victory = parse_victory(&String::from_utf8_lossy(&output.stdout));
+    Ok((output.status, victory))
+}
+
+impl TestArgs {
+    pub fn run(&self) -> Result<()> {
+        let rustorio_root = find_save_game(&self.save_name)?;
+        let (status, victory) = run_save_game(&rustorio_root, &self.save_name, self.release)?;
+        if !status.success() {
+            bail!(
+                "FAIL: save game '{}' failed with exit status: {}",
+                self.save_name,
+                status
+            );
+        }
+        let Some(Victory { ticks, .. }) = victory else {
+            bail!(
+                "FAIL: save game '{}' exited without winning.",
+                self.save_name
+            );
+        };
+        if let Some(max_ticks) = self.max_ticks
+            && ticks > max_ticks
+        {
+            bail!(
+                "FAIL: save game '{}' won in {} ticks, more than the allowed {}.",
+                self.save_name,
+                ticks,
+                max_ticks
+            );
+        }
+        println!(
+            "PASS: save game '{}' won in {} ticks.",
+            self.save_name, ticks
+        );
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct CompareArgs {
+    /// The first save game to compare.
+    save_a: String,
+    /// The second save game to compare.
+    save_b: String,
+    /// Build the save games with optimizations.
+    #[clap(long)]
+    release: bool,
+}
+
+impl CompareArgs {
+    pub fn run(&self) -> Result<()> {
+        let mut results = Vec::new();
+        for save_name in [&self.save_a, &self.save_b] {
+            let rustorio_root = find_save_game(save_name)?;
+            let (status, victory) = run_save_game(&rustorio_root, save_name, self.release)?;
+            match &victory {
+                Some(Victory { ticks, .. }) if status.success() => {
+                    println!("Save game '{save_name}' won in {ticks} ticks.");
+                }
+                _ => println!("Save game '{save_name}' did not win (exit status: {status})."),
+            }
+            results.push(victory.filter(|_| status.success()));
+        }
+        if let [Some(a), Some(b)] = &results[..]
+            && a.config != b.config
+        {
+            println!("Note: the save games were played with different game mode configs.");
+        }
+        let ticks = |result: &Option<Victory>| result.as_ref().map(|victory| victory.ticks);
+        match (ticks(&results[0]), ticks(&results[1])) {
+            (Some(a), Some(b)) if a == b => {
+                println!("It's a tie! Both save games won in {a} ticks.");
+            }
+            (Some(a), Some(b)) => {
+                let (winner, loser, fast, slow) = if a < b {
+                    (&self.save_a, &self.save_b, a, b)
+                } else {
+                    (&self.save_b, &self.save_a, b, a)
+                };
+                println!(
+                    "'{winner}' wins by {} ticks ({:.1}% faster than '{loser}').",
+                    slow - fast,
+                    (slow - fast) as f64 / slow as f64 * 100.0
+                );
+            }
+            (Some(_), None) => {
+                println!("'{}' wins, as '{}' did not win.", self.save_a, self.save_b)
+            }
+            (None, Some(_)) => {
+                println!("'{}' wins, as '{}' did not win.", self.save_b, self.save_a)
+            }
+            (None, None) => bail!("Neither save game won."),
+        }
+        Ok(())
+    }
+}
+
+const SCORES_FILE: &str = "rustorio_scores.json";
+
+#[derive(Args)]
+pub struct ScoreArgs {
+    /// The name of the save game to score.
+    save_name: String,
+    /// Build the save game with optimizations.
+    #[clap(long)]
+    release: bool,
+}
+
+impl ScoreArgs {
+    pub fn run(&self) -> Result<()> {
+        let rustorio_root = find_save_game(&self.save_name)?;
+        let (status, victory) = run_save_game(&rustorio_root, &self.save_name, self.release)?;
+        if !status.success() {
+            bail!(
+                "Save game '{}' failed with exit status: {}",
+                self.save_name,
+                status
+            );
+        }
+        let Some(Victory { ticks, config }) = victory else {
+            bail!("Save game '{}' exited without winning.", self.save_name);
+        };
+
+        let scores_path = rustorio_root.join(SCORES_FILE);
+        let (previous_best, config_changed) = with_file_lock(&scores_path, || {
+            let mut scores = match fs::read_to_string(&scores_path) {
+                Ok(contents) => parse_scores(&contents)
+                    .with_context(|| format!("'{}' is malformed", scores_path.display()))?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Scores::new(),
+                Err(err) => return Err(err).context("Failed to read scores file"),
+            };
+            // A best from a different config was set under different rules, so it is replaced rather than compared.
+            let previous = scores.get(&self.save_name);
+            let config_changed = previous.is_some_and(|best| best.config != config);
+            let previous_best = previous.filter(|_| !config_changed).map(|best| best.ticks);
+            scores.insert(
+                self.save_name.clone(),
+                Score {
+                    ticks: previous_best.map_or(ticks, |best| best.min(ticks)),
+                    config,
+                },
+            );
+            // Write to a temporary file first so a crash never leaves a half-written scores file.
+            let temp_path = scores_path.with_extension("json.tmp");
+            fs::write(&temp_path, format_scores(&scores)).context("Failed to write scores file")?;
+            fs::rename(&temp_path, &scores_path).context("Failed to write scores file")?;
+            Ok((previous_best, config_changed))
+        })?;
+
+        match previous_best {
+            None if config_changed => println!(
+                "Save game '{}' won in {} ticks. Its game mode config changed, so this replaces its previous best.",
+                self.save_name, ticks
+            ),
+            None => println!(
+                "Save game '{}' won in {} ticks. This is its first recorded score!",
+                self.save_name, ticks
+            ),
+            Some(best) if ticks < best => println!(
+                "Save game '{}' won in {} ticks. New personal best, beating {} ticks!",
+                self.save_name, ticks, best
+            ),
+            Some(best) => println!(
+                "Save game '{}' won in {} ticks. Personal best is {} ticks.",
+                self.save_name, ticks, best
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// Runs `f` while holding a lock file next to `path`, so concurrent runs don't overwrite each other's changes.
+fn with_file_lock<T>(path: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let lock_path = path.with_extension("lock");
+    let start = Instant::now();
+    loop {
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+        {
+            Ok(_) => break,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                if start.elapsed() > Duration::from_secs(10) {
+                    bail!(
+                        "Timed out waiting for '{}'. If no other Rustorio command is running, delete it.",
+                        lock_path.display()
+                    );
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(err) => return Err(err).context("Failed to create lock file"),
+        }
+    }
+    let result = f();
+    fs::remove_file(&lock_path).context("Failed to remove lock file")?;
+    result
+}
+
+/// The best score of a save game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Score {
+    /// The fewest ticks the save game won in.
+    ticks: u64,
+    /// The [config hash](rustorio_engine::GameResult::config_hash) of the game it was set in.
+    config: String,
+}
+
+/// The best score of each save game, by name.
+type Scores = BTreeMap<String, Score>;
+
+/// Parses a scores file, a JSON object mapping save names to their best scores.
+fn parse_scores(contents: &str) -> Option<Scores> {
+    serde_json::from_str(contents).ok()
+}
+
+fn format_scores(scores: &Scores) -> String {
+    serde_json::to_string_pretty(scores).expect("Scores are always valid JSON") + "\n"
+}
+
+#[derive(Args)]
+pub struct GraphArgs {
+    /// Where to write the graph.
+    #[clap(long, short, default_value = "rustorio.dot")]
+    output: PathBuf,
+}
+
+/// A recipe or technology in the production graph, with the resources it consumes and produces.
+struct GraphNode {
+    id: String,
+    label: &'static str,
+    shape: &'static str,
+    inputs: Vec<(&'static str, u32)>,
+    outputs: Vec<(&'static str, u32)>,
+    prerequisites: Vec<String>,
+}
+
+/// The graph node id of the technology named `name`, which keeps it apart from resources of the same name like steel.
+fn technology_id(name: &str) -> String {
+    format!("{name} technology")
+}
+
+impl GraphNode {
+    fn recipe<R: RecipeEx>() -> Self {
+        let mut inputs = R::new_inputs();
+        let mut outputs = R::new_outputs();
+        Self {
+            id: R::NAME.to_string(),
+            label: R::NAME,
+            shape: "box",
+            inputs: R::iter_inputs(&mut inputs)
+                .map(|(name, amount, _)| (name, amount))
+                .collect(),
+            outputs: R::iter_outputs(&mut outputs)
+                .map(|(name, amount, _)| (name, amount))
+                .collect(),
+            prerequisites: Vec::new(),
+        }
+    }
+
+    fn technology<T: Technology>() -> Self {
+        Self {
+            id: technology_id(T::NAME),
+            label: T::NAME,
+            shape: "hexagon",
+            inputs: T::cost_description().collect(),
+            outputs: Vec::new(),
+            prerequisites: T::Prerequisites::names()
+                .into_iter()
+                .map(technology_id)
+                .collect(),
+        }
+    }
+}
+
+/// Collects a [`GraphNode`] for every recipe and technology it visits.
+struct GraphNodes(Vec<GraphNode>);
+
+impl RecipeVisitor for GraphNodes {
+    fn visit<R: RecipeEx>(&mut self) {
+        self.0.push(GraphNode::recipe::<R>());
+    }
+}
+
+impl TechnologyVisitor for GraphNodes {
+    fn visit<T: Technology>(&mut self) {
+        self.0.push(GraphNode::technology::<T>());
+    }
+}
+
+/// Finds the resources that can be turned back into themselves through some chain of recipes.
+fn resources_in_cycles(nodes: &[GraphNode]) -> Vec<&'static str> {
+    let mut edges: Vec<(&'static str, &'static str)> = Vec::new();
+    for node in nodes {
+        for (input, _) in &node.inputs {
+            for (output, _) in &node.outputs {
+                edges.push((input, output));
+            }
+        }
+    }
+    let mut resources: Vec<&'static str> = edges.iter().map(|&(from, _)| from).collect();
+    resources.sort_unstable();
+    resources.dedup();
+    resources
+        .into_iter()
+        .filter(|&start| {
+            let mut stack = vec![start];
+            let mut visited = Vec::new();
+            while let Some(resource) = stack.pop() {
+                for &(from, to) in &edges {
+                    if from == resource {
+                        if to == start {
+                            return true;
+                        }
+                        if !visited.contains(&to) {
+                            visited.push(to);
+                            stack.push(to);
+                        }
+                    }
+                }
+            }
+            false
+        })
+        .collect()
+}
+
+impl GraphArgs {
+    pub fn run(&self) -> Result<()> {
+        let mut nodes = GraphNodes(Vec::new());
+        visit_recipes(&mut nodes);
+        visit_technologies(&mut nodes);
+        let GraphNodes(nodes) = nodes;
+        let cycles = resources_in_cycles(&nodes);
+
+        let mut dot = String::from("digraph rustorio {\n    rankdir=LR;\n");
+        if !cycles.is_empty() {
+            dot.push_str(&format!(
+                "    // Resources in cycles: {}\n",
+                cycles.join(", ")
+            ));
+        }
+        for node in &nodes {
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{}\", shape={}];\n",
+                node.id, node.label, node.shape
+            ));
+            for (input, amount) in &node.inputs {
+                dot.push_str(&format!(
+                    "    \"{input}\" -> \"{}\" [label=\"{amount}\"];\n",
+                    node.id
+                ));
+            }
+            for (output, amount) in &node.outputs {
+                dot.push_str(&format!(
+                    "    \"{}\" -> \"{output}\" [label=\"{amount}\"];\n",
+                    node.id
+                ));
+            }
+            for prerequisite in &node.prerequisites {
+                dot.push_str(&format!(
+                    "    \"{prerequisite}\" -> \"{}\" [style=dashed];\n",
+                    node.id
+                ));
+            }
+        }
+        dot.push_str("}\n");
+
+        fs::write(&self.output, dot).context("Failed to write graph file")?;
+        println!("Wrote recipe graph to {}.", self.output.display());
+        if !cycles.is_empty() {
+            println!(
+                "Note: the graph contains cycles through {}.",
+                cycles.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("item").required(true).args(["recipe", "resource"])))]
+pub struct ModNewArgs {
+    /// Generate a recipe struct with the given name.
+    #[clap(long)]
+    recipe: Option<String>,
+    /// Generate a resource type with the given name.
+    #[clap(long)]
+    resource: Option<String>,
+    /// The file to add the generated code to.
+    /// Defaults to `src/recipes.rs` for recipes and `src/resources.rs` for resources.
+    #[clap(long, short)]
+    file: Option<PathBuf>,
+}
+
+impl ModNewArgs {
+    pub fn run(&self) -> Result<()> {
+        let (name, default_file, header, item) = match (&self.recipe, &self.resource) {
+            (Some(name), _) => (
+                name,
+                "src/recipes.rs",
+                "use rustorio_engine::{\n    Sealed,\n    recipe::{Recipe, RecipeEx, recipe_doc},\n};\n",
+                format!(
+                    "\n/// TODO: Describe the recipe.\n#[derive(Debug, Clone, Copy, Recipe, RecipeEx)]\n#[recipe_doc]\n#[recipe_inputs(\n    (1, InputResource),\n)]\n#[recipe_outputs(\n    (1, OutputResource),\n)]\n#[recipe_ticks(1)]\npub struct {name};\nimpl Sealed for {name} {{}}\n"
+                ),
+            ),
+            (None, Some(name)) => (
+                name,
+                "src/resources.rs",
+                "use rustorio_engine::resource_type;\n",
+                format!(
+                    "\nresource_type!(\n    /// TODO: Describe the resource.\n    {name}\n);\n"
+                ),
+            ),
+            (None, None) => unreachable!("clap requires either --recipe or --resource"),
+        };
+        let is_type_name = name.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_type_name {
+            bail!(
+                "'{name}' is not a valid type name. Use UpperCamelCase letters, digits and underscores, like 'IronGear'."
+            );
+        }
+        let file = self
+            .file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(default_file));
+        if file.exists() {
+            let mut contents = fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read '{}'", file.display()))?;
+            if contents.contains(&format!("pub struct {name};"))
+                || contents.contains(&format!("    {name}\n);"))
+            {
+                bail!("'{}' already defines '{name}'.", file.display());
+            }
+            contents.push_str(&item);
+            fs::write(&file, contents)
+                .with_context(|| format!("Failed to write '{}'", file.display()))?;
+            println!("Added '{name}' to '{}'.", file.display());
+        } else {
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent).context("Failed to create directory")?;
+            }
+            fs::write(&file, format!("{header}{item}"))
+                .with_context(|| format!("Failed to write '{}'", file.display()))?;
+            println!(
+                "Created '{}' with '{name}'. Remember to declare the module in your crate root.",
+                file.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Recognizes compiler errors caused by Rustorio's compile-time resource checks,
+/// which are notoriously hard to read, and returns an explanation.
+fn compile_error_hint(stderr: &str) -> Option<&'static str> {
+    const PATTERNS: [&str; 4] = [
+        "IsTrue` is not satisfied",
+        "evaluation of constant value failed",
+        "unconstrained generic constant",
+        "attempt to compute",
+    ];
+    PATTERNS.iter().any(|pattern| stderr.contains(pattern)).then_some(
+        "Rustorio checks resource amounts at compile time using the nightly `generic_const_exprs` feature, and the error above comes from one of those checks. \
+Usually a `Bundle` amount doesn't add up, like splitting a bundle into parts that don't sum to its amount, \
+or a generic function is missing a `where [(); AMOUNT as usize]:` bound. \
+See https://docs.rs/rustorio/latest/rustorio/struct.Bundle.html for how bundles work.",
+    )
+}
+
+/// A win, as printed by `play` when [`OUTPUT_ENV_VAR`] is `json`.
+#[derive(Debug, PartialEq, Eq)]
+struct Victory {
+    /// How many ticks the game took.
+    ticks: u64,
+    /// The [config hash](rustorio_engine::GameResult::config_hash) of the game, in hexadecimal.
+    config: String,
+}
+
+/// Finds the last JSON victory line in the output of a save game.
+fn parse_victory(output: &str) -> Option<Victory> {
+    output.lines().rev().find_map(|line| {
+        let line: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+        if line["won"] != true {
+            return None;
+        }
+        Some(Victory {
+            ticks: line["ticks"].as_u64()?,
+            config: line["config"].as_str()?.to_string(),
+        })
+    })
+}
+
 pub fn main() -> Result<()> {
     let cli = Cli::parse();
     cli.run()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toolchain_is_pinned_nightly() {
+        // `generic_const_exprs` needs nightly, and a floating channel could break on any update.
+        let channel = RUST_TOOLCHAIN
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("channel"))
+            .expect("rust-toolchain.toml has no channel");
+        let channel = channel
+            .trim_start_matches([' ', '='])
+            .trim()
+            .trim_matches('"');
+        assert!(channel.starts_with("nightly-"), "{channel}");
+    }
+
+    #[test]
+    fn save_names_are_sanitized() {
+        assert_eq!(sanitize_save_name("  my_game-2 ").unwrap(), "my_game-2");
+        assert!(sanitize_save_name("   ").is_err());
+        let err = sanitize_save_name("my game").unwrap_err().to_string();
+        assert!(err.contains("'my_game'"), "{err}");
+        assert!(sanitize_save_name("../escape").is_err());
+        assert!(sanitize_save_name("deps").is_err());
+    }
+
+    #[test]
+    fn bin_target_only_added_without_autobins() {
+        let manifest = "[package]\nname = \"rustorio-game\"\n";
+        assert_eq!(add_bin_target(manifest, "tutorial"), None);
+
+        let manifest = "[package]\nname = \"rustorio-game\"\nautobins = false\n";
+        let patched = add_bin_target(manifest, "tutorial").unwrap();
+        assert_eq!(
+            patched,
+            "[package]\nname = \"rustorio-game\"\nautobins = false\n\n[[bin]]\nname = \"tutorial\"\npath = \"src/bin/tutorial/main.rs\"\n"
+        );
+        assert_eq!(add_bin_target(&patched, "tutorial"), None);
+    }
+
+    #[test]
+    fn scores_round_trip() {
+        let score = |ticks| Score {
+            ticks,
+            config: "00000000000000ff".to_string(),
+        };
+        let scores = Scores::from([
+            ("tutorial".to_string(), score(40)),
+            ("standard".to_string(), score(1234)),
+            ("my \"best\", run: 2".to_string(), score(7)),
+        ]);
+        assert_eq!(parse_scores(&format_scores(&scores)), Some(scores));
+        assert_eq!(parse_scores("{}"), Some(Scores::new()));
+        assert_eq!(parse_scores("{\"tutorial\": forty}"), None);
+        assert_eq!(parse_scores("{\"tutorial\": 40}"), None);
+    }
+
+    #[test]
+    fn env_vars_parsed_as_key_value() {
+        assert_eq!(
+            parse_env_var("FURNACES=4=2").unwrap(),
+            ("FURNACES".to_string(), "4=2".to_string())
+        );
+        assert!(parse_env_var("FURNACES").is_err());
+        assert!(parse_env_var("=4").is_err());
+    }
+
+    #[test]
+    fn compile_error_hint_recognizes_bundle_errors() {
+        let stderr = "error[E0277]: the trait bound `Assert<rustorio_engine::::resources::{impl#12}::split::{constant#0}>: IsTrue` is not satisfied\n";
+        assert!(compile_error_hint(stderr).is_some());
+        assert!(compile_error_hint("error[E0425]: cannot find value `iron`\n").is_none());
+    }
+
+    #[test]
+    fn victory_parsed_from_json_line() {
+        let output = "Tick 1\n{\"won\": true, \"ticks\": 40, \"config\": \"00000000000000ff\"}\n";
+        assert_eq!(
+            parse_victory(output),
+            Some(Victory {
+                ticks: 40,
+                config: "00000000000000ff".to_string()
+            })
+        );
+        assert_eq!(parse_victory("You won in 40 ticks!\n"), None);
+        let lost = "{\"won\": false, \"ticks\": 40, \"config\": \"00000000000000ff\", \"reason\": \"Too slow\"}\n";
+        assert_eq!(parse_victory(lost), None);
+    }
+
+    #[test]
+    fn built_executable_read_from_cargo_messages() {
+        let messages = r#"{"reason":"compiler-artifact","target":{"name":"rustorio"},"executable":null}
+{"reason":"compiler-artifact","target":{"name":"my_save"},"executable":"/tmp/out/debug/my_save.exe"}
+{"reason":"build-finished","success":true}
+"#;
+        assert_eq!(
+            built_executable(messages, "my_save"),
+            Some(PathBuf::from("/tmp/out/debug/my_save.exe"))
+        );
+        assert_eq!(built_executable(messages, "rustorio"), None);
+    }
+
+    #[test]
+    fn speedrun_detected_with_tick_budget() {
+        let save_file = "type GameMode = Speedrun<500>;\n";
+        assert!(GameMode::detect(save_file) == Some(GameMode::Speedrun));
+    }
+
+    #[test]
+    fn seeded_detected_with_seed() {
+        let save_file = "type GameMode = Seeded<1234>;\n";
+        assert!(GameMode::detect(save_file) == Some(GameMode::Seeded));
+    }
+
+    #[test]
+    fn new_projects_get_default_challenge() {
+        let config = rustorio::gamemodes::ChallengeConfig::from_toml(RUSTORIO_TOML).unwrap();
+        assert_eq!(config, rustorio::gamemodes::ChallengeConfig::default());
+    }
+
+    #[test]
+    fn standard_detected_with_difficulty() {
+        let save_file = "type GameMode = Standard<Hard>;\n";
+        assert!(GameMode::detect(save_file) == Some(GameMode::Standard));
+    }
+
+    #[test]
+    fn game_mode_detected_from_alias_only() {
+        for game_mode in GameMode::ALL {
+            assert!(GameMode::detect(&game_mode.start_file()) == Some(game_mode));
+        }
+        let save_file = "// Was a Sandbox save.\ntype GameMode = rustorio::gamemodes::Hardcore;\n";
+        assert!(GameMode::detect(save_file) == Some(GameMode::Hardcore));
+        assert!(GameMode::detect("type GameMode = CircuitChallenge;\n").is_none());
+    }
+}
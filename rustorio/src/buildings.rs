@@ -7,16 +7,29 @@
 //! When created, a building is set to a specific [`Recipe`](crate::recipes), which defines the inputs and outputs.
 //! This can be changed using the `change_recipe` method, but only if the building is empty (no inputs or outputs).
 
+use std::{cell::RefCell, marker::PhantomData};
+
 use rustorio_engine::{
-    machine::{Machine, MachineNotEmptyError},
-    recipe::{Recipe, RecipeEx},
-    research::{TechRecipe, Technology, TechnologyEx, tech_recipe},
+    ResourceType, Sealed,
+    game_state::Persist,
+    gamemodes::Difficulty,
+    machine::{
+        BufferLocation, Feeding, Machine, MachineNotEmptyError, TooMuchInputError, find_nonempty,
+    },
+    recipe::{Buffer, Recipe, RecipeEx},
+    research::{
+        GenericResearchRecipe, TechRecipe, Technology, generic_research_recipe, tech_recipe,
+    },
+    resource, stats,
 };
 
 use crate::{
-    Bundle, Tick,
-    recipes::{AssemblerRecipe, FurnaceRecipe},
-    resources::{Copper, CopperWire, Iron},
+    Bundle, Resource, Tick,
+    recipes::{
+        AssemblerRecipe, ElectricFurnaceRecipe, FurnaceRecipe, LabRecipe, SteelFurnaceRecipe,
+    },
+    resources::{Copper, CopperWire, ElectronicCircuit, Iron, Steel, Water},
+    territory::Territory,
 };
 
 /// The assembler can craft most items in the game.
@@ -45,6 +58,8 @@ impl<R: AssemblerRecipe> Assembler<R> {
 
     /// Changes the [`Recipe`](crate::recipes) of the assembler.
     /// Returns the original assembler if the the input and output buffers are not empty.
+    // Like `Machine::change_recipe`, the error hands the building back.
+    #[allow(clippy::result_large_err)]
     pub fn change_recipe<R2: AssemblerRecipe>(
         self,
         recipe: R2,
@@ -56,7 +71,7 @@ impl<R: AssemblerRecipe> Assembler<R> {
     }
 
     /// Update internal state and access input buffers.
-    pub fn inputs(&mut self, tick: &Tick) -> &mut <R as Recipe>::Inputs {
+    pub fn inputs(&mut self, tick: &Tick) -> Feeding<'_, <R as Recipe>::Inputs> {
         self.0.inputs(tick)
     }
 
@@ -74,6 +89,17 @@ impl<R: AssemblerRecipe> Assembler<R> {
     pub const fn output_amounts(&self) -> <R as Recipe>::OutputAmountsType {
         <R as Recipe>::OUTPUT_AMOUNTS
     }
+
+    /// Whether the assembler has broken from being starved of inputs for too long, and no longer crafts.
+    /// Only happens in [`Hardcore`](crate::gamemodes::Hardcore).
+    pub fn is_broken(&mut self, tick: &Tick) -> bool {
+        self.0.is_broken(tick)
+    }
+
+    /// Whether the assembler's input and output buffers are all empty, so [`change_recipe`](Assembler::change_recipe) will succeed.
+    pub fn is_empty(&mut self, tick: &Tick) -> bool {
+        self.0.is_empty(tick)
+    }
 }
 
 /// The furnace is used to smelt ores into base resources.
@@ -88,15 +114,27 @@ impl<R: AssemblerRecipe> Assembler<R> {
 #[derive(Debug)]
 pub struct Furnace<R: FurnaceRecipe>(Machine<R>);
 
+/// How many times as fast furnaces smelt on [`Difficulty::Easy`].
+pub const EASY_FURNACE_SPEED: u64 = 2;
+
+/// How many times as fast furnaces built at `tick` smelt, given the game's [difficulty](Tick::difficulty).
+const fn furnace_speed(tick: &Tick) -> u64 {
+    match tick.difficulty() {
+        Difficulty::Easy => EASY_FURNACE_SPEED,
+        Difficulty::Normal | Difficulty::Hard => 1,
+    }
+}
+
 impl<R: FurnaceRecipe> Furnace<R> {
     /// Builds a furnace. Costs 10 [iron](crate::resources::Iron).
     pub fn build(tick: &Tick, recipe: R, iron: Bundle<Iron, 10>) -> Self {
         let _ = (recipe, iron);
-        Self(Machine::new(tick))
+        Self(Machine::with_speed(tick, furnace_speed(tick)))
     }
 
     /// Changes the [`Recipe`](crate::recipes) of the furnace.
     /// Returns the original furnace if the the input and output buffers are not empty.
+    #[allow(clippy::result_large_err)]
     pub fn change_recipe<R2: FurnaceRecipe>(
         self,
         recipe: R2,
@@ -108,7 +146,199 @@ impl<R: FurnaceRecipe> Furnace<R> {
     }
 
     /// Update internal state and access input buffers.
-    pub fn inputs(&mut self, tick: &Tick) -> &mut <R as Recipe>::Inputs {
+    pub fn inputs(&mut self, tick: &Tick) -> Feeding<'_, <R as Recipe>::Inputs> {
+        self.0.inputs(tick)
+    }
+
+    /// Amount of each input resource needed for one recipe cycle
+    pub const fn input_amounts(&self) -> <R as Recipe>::InputAmountsType {
+        <R as Recipe>::INPUT_AMOUNTS
+    }
+
+    /// Update internal state and access output buffers.
+    pub fn outputs(&mut self, tick: &Tick) -> &mut <R as Recipe>::Outputs {
+        self.0.outputs(tick)
+    }
+
+    /// Amount of each output resource created per recipe cycle
+    pub const fn output_amounts(&self) -> <R as Recipe>::OutputAmountsType {
+        <R as Recipe>::OUTPUT_AMOUNTS
+    }
+
+    /// Whether the furnace has broken from being starved of inputs for too long, and no longer crafts.
+    /// Only happens in [`Hardcore`](crate::gamemodes::Hardcore).
+    pub fn is_broken(&mut self, tick: &Tick) -> bool {
+        self.0.is_broken(tick)
+    }
+
+    /// Whether the furnace's input and output buffers are all empty, so [`change_recipe`](Furnace::change_recipe) will succeed.
+    pub fn is_empty(&mut self, tick: &Tick) -> bool {
+        self.0.is_empty(tick)
+    }
+}
+
+/// Proof that the [`SteelFurnace`] has been unlocked, required to build one.
+/// Obtained by researching [`SteelFurnaceTechnology`](crate::research::SteelFurnaceTechnology).
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct SteelFurnaceUnlock;
+
+impl SteelFurnaceUnlock {
+    pub(crate) const fn new() -> Self {
+        Self
+    }
+}
+
+/// A furnace made of [steel](crate::resources::Steel) that smelts twice as fast as a regular [`Furnace`].
+///
+/// Works just like a [`Furnace`] and uses the same [recipes](SteelFurnaceRecipe#implementors),
+/// but can only be built after it has been unlocked by researching [`SteelFurnaceTechnology`](crate::research::SteelFurnaceTechnology).
+///
+/// ```
+/// # use rustorio::{Bundle, Tick, buildings::{SteelFurnace, SteelFurnaceUnlock}, recipes::IronSmelting, resources::Steel};
+/// fn build(tick: &Tick, unlock: &SteelFurnaceUnlock, steel: Bundle<Steel, 10>) -> SteelFurnace<IronSmelting> {
+///     SteelFurnace::build(tick, unlock, IronSmelting, steel)
+/// }
+/// ```
+/// Without the unlock, there is no way to build one:
+/// ```compile_fail
+/// # use rustorio::{Bundle, Tick, buildings::{SteelFurnace, SteelFurnaceUnlock}, recipes::IronSmelting, resources::Steel};
+/// fn build(tick: &Tick, steel: Bundle<Steel, 10>) -> SteelFurnace<IronSmelting> {
+///     SteelFurnace::build(tick, &SteelFurnaceUnlock, IronSmelting, steel)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SteelFurnace<R: SteelFurnaceRecipe>(Machine<R>);
+
+impl<R: SteelFurnaceRecipe> SteelFurnace<R> {
+    /// How many times as fast as a regular [`Furnace`] a steel furnace smelts.
+    pub const SPEED: u64 = 2;
+
+    /// Builds a steel furnace. Costs 10 [steel](crate::resources::Steel).
+    pub fn build(
+        tick: &Tick,
+        unlock: &SteelFurnaceUnlock,
+        recipe: R,
+        steel: Bundle<Steel, 10>,
+    ) -> Self {
+        let _ = (unlock, recipe, steel);
+        Self(Machine::with_speed(tick, Self::SPEED * furnace_speed(tick)))
+    }
+
+    /// Changes the [`Recipe`](crate::recipes) of the steel furnace.
+    /// Returns the original steel furnace if the the input and output buffers are not empty.
+    #[allow(clippy::result_large_err)]
+    pub fn change_recipe<R2: SteelFurnaceRecipe>(
+        self,
+        recipe: R2,
+    ) -> Result<SteelFurnace<R2>, MachineNotEmptyError<Self>> {
+        match self.0.change_recipe(recipe) {
+            Ok(machine) => Ok(SteelFurnace(machine)),
+            Err(err) => Err(err.map_machine(SteelFurnace)),
+        }
+    }
+
+    /// Update internal state and access input buffers.
+    pub fn inputs(&mut self, tick: &Tick) -> Feeding<'_, <R as Recipe>::Inputs> {
+        self.0.inputs(tick)
+    }
+
+    /// Amount of each input resource needed for one recipe cycle
+    pub const fn input_amounts(&self) -> <R as Recipe>::InputAmountsType {
+        <R as Recipe>::INPUT_AMOUNTS
+    }
+
+    /// Update internal state and access output buffers.
+    pub fn outputs(&mut self, tick: &Tick) -> &mut <R as Recipe>::Outputs {
+        self.0.outputs(tick)
+    }
+
+    /// Amount of each output resource created per recipe cycle
+    pub const fn output_amounts(&self) -> <R as Recipe>::OutputAmountsType {
+        <R as Recipe>::OUTPUT_AMOUNTS
+    }
+
+    /// Whether the steel furnace has broken from being starved of inputs for too long, and no longer crafts.
+    /// Only happens in [`Hardcore`](crate::gamemodes::Hardcore).
+    pub fn is_broken(&mut self, tick: &Tick) -> bool {
+        self.0.is_broken(tick)
+    }
+
+    /// Whether the steel furnace's input and output buffers are all empty, so [`change_recipe`](SteelFurnace::change_recipe) will succeed.
+    pub fn is_empty(&mut self, tick: &Tick) -> bool {
+        self.0.is_empty(tick)
+    }
+}
+
+/// Proof that the [`ElectricFurnace`] has been unlocked, required to build one.
+/// Obtained by researching [`ElectricFurnaceTechnology`](crate::research::ElectricFurnaceTechnology).
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ElectricFurnaceUnlock;
+
+impl ElectricFurnaceUnlock {
+    pub(crate) const fn new() -> Self {
+        Self
+    }
+}
+
+/// The top-tier furnace, smelting four times as fast as a regular [`Furnace`].
+///
+/// Works just like a [`Furnace`] and uses the same [recipes](ElectricFurnaceRecipe#implementors),
+/// but can only be built after it has been unlocked by researching [`ElectricFurnaceTechnology`](crate::research::ElectricFurnaceTechnology).
+///
+/// ```
+/// # use rustorio::{Bundle, Tick, buildings::{ElectricFurnace, ElectricFurnaceUnlock}, recipes::IronSmelting, resources::{ElectronicCircuit, Steel}};
+/// fn build(
+///     tick: &Tick,
+///     unlock: &ElectricFurnaceUnlock,
+///     steel: Bundle<Steel, 10>,
+///     circuits: Bundle<ElectronicCircuit, 5>,
+/// ) -> ElectricFurnace<IronSmelting> {
+///     ElectricFurnace::build(tick, unlock, IronSmelting, steel, circuits)
+/// }
+/// ```
+/// Without the unlock, there is no way to build one:
+/// ```compile_fail
+/// # use rustorio::{Bundle, Tick, buildings::{ElectricFurnace, ElectricFurnaceUnlock}, recipes::IronSmelting, resources::{ElectronicCircuit, Steel}};
+/// fn build(tick: &Tick, steel: Bundle<Steel, 10>, circuits: Bundle<ElectronicCircuit, 5>) -> ElectricFurnace<IronSmelting> {
+///     ElectricFurnace::build(tick, &ElectricFurnaceUnlock, IronSmelting, steel, circuits)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ElectricFurnace<R: ElectricFurnaceRecipe>(Machine<R>);
+
+impl<R: ElectricFurnaceRecipe> ElectricFurnace<R> {
+    /// How many times as fast as a regular [`Furnace`] an electric furnace smelts.
+    pub const SPEED: u64 = 4;
+
+    /// Builds an electric furnace. Costs 10 [steel](crate::resources::Steel) and 5 [electronic circuits](crate::resources::ElectronicCircuit).
+    pub fn build(
+        tick: &Tick,
+        unlock: &ElectricFurnaceUnlock,
+        recipe: R,
+        steel: Bundle<Steel, 10>,
+        circuits: Bundle<ElectronicCircuit, 5>,
+    ) -> Self {
+        let _ = (unlock, recipe, steel, circuits);
+        Self(Machine::with_speed(tick, Self::SPEED * furnace_speed(tick)))
+    }
+
+    /// Changes the [`Recipe`](crate::recipes) of the electric furnace.
+    /// Returns the original electric furnace if the the input and output buffers are not empty.
+    #[allow(clippy::result_large_err)]
+    pub fn change_recipe<R2: ElectricFurnaceRecipe>(
+        self,
+        recipe: R2,
+    ) -> Result<ElectricFurnace<R2>, MachineNotEmptyError<Self>> {
+        match self.0.change_recipe(recipe) {
+            Ok(machine) => Ok(ElectricFurnace(machine)),
+            Err(err) => Err(err.map_machine(ElectricFurnace)),
+        }
+    }
+
+    /// Update internal state and access input buffers.
+    pub fn inputs(&mut self, tick: &Tick) -> Feeding<'_, <R as Recipe>::Inputs> {
         self.0.inputs(tick)
     }
 
@@ -126,13 +356,27 @@ impl<R: FurnaceRecipe> Furnace<R> {
     pub const fn output_amounts(&self) -> <R as Recipe>::OutputAmountsType {
         <R as Recipe>::OUTPUT_AMOUNTS
     }
+
+    /// Whether the electric furnace has broken from being starved of inputs for too long, and no longer crafts.
+    /// Only happens in [`Hardcore`](crate::gamemodes::Hardcore).
+    pub fn is_broken(&mut self, tick: &Tick) -> bool {
+        self.0.is_broken(tick)
+    }
+
+    /// Whether the electric furnace's input and output buffers are all empty, so [`change_recipe`](ElectricFurnace::change_recipe) will succeed.
+    pub fn is_empty(&mut self, tick: &Tick) -> bool {
+        self.0.is_empty(tick)
+    }
 }
 
 /// Performs research to unlock new technologies.
 /// Set it to produce research points for a specific technology either when [`build`](Lab::build)ing it,
 /// or using [`change_technology`](Lab::change_technology).
+///
+/// Instead of research points, a lab can also turn the science packs of its technology into generic research points
+/// using [`research_generic`](Lab::research_generic), running the [`GenericResearchRecipe`] rather than the [`TechRecipe`].
 #[derive(Debug)]
-pub struct Lab<T: Technology>(Machine<TechRecipe<T>>)
+pub struct Lab<T: Technology, R: LabRecipe = TechRecipe<T>>(Machine<R>, PhantomData<T>)
 where
     TechRecipe<T>: RecipeEx;
 
@@ -148,10 +392,20 @@ where
         copper: Bundle<Copper, 15>,
     ) -> Self {
         let _ = (technology, iron, copper);
-        Self(Machine::new(tick))
+        Self(Machine::new(tick), PhantomData)
     }
+}
 
+impl<T: Technology, R: LabRecipe> Lab<T, R>
+where
+    TechRecipe<T>: RecipeEx,
+{
     /// Changes the technology this `Lab` is producing research points for.
+    /// Returns the original lab if the input and output buffers are not empty.
+    ///
+    /// The new technology may require different science packs, so any leftover science packs and research points must be removed first.
+    /// Research points removed from an abandoned technology can be partially refunded with [`ResearchPoint::convert_generic`](rustorio_engine::research::ResearchPoint::convert_generic).
+    #[allow(clippy::result_large_err)]
     pub fn change_technology<T2: Technology>(
         self,
         technology: &T2,
@@ -161,23 +415,1464 @@ where
     {
         let _ = technology;
         match self.0.change_recipe(tech_recipe()) {
-            Ok(machine) => Ok(Lab(machine)),
-            Err(err) => Err(err.map_machine(Lab)),
+            Ok(machine) => Ok(Lab(machine, PhantomData)),
+            Err(err) => Err(err.map_machine(|machine| Lab(machine, PhantomData))),
+        }
+    }
+
+    /// Switches the lab to turning the science packs of its technology into [generic research points](rustorio_engine::research::GenericResearchPoint), one per science pack.
+    /// They can be redeemed for the research points of any technology taking the same science packs
+    /// with [`ResearchPoint::from_generic`](rustorio_engine::research::ResearchPoint::from_generic).
+    /// Returns the original lab if the input and output buffers are not empty.
+    ///
+    /// Switch back with [`change_technology`](Lab::change_technology).
+    #[allow(clippy::result_large_err)]
+    pub fn research_generic(
+        self,
+    ) -> Result<Lab<T, GenericResearchRecipe<T>>, MachineNotEmptyError<Self>>
+    where
+        GenericResearchRecipe<T>: RecipeEx,
+    {
+        match self.0.change_recipe(generic_research_recipe()) {
+            Ok(machine) => Ok(Lab(machine, PhantomData)),
+            Err(err) => Err(err.map_machine(|machine| Lab(machine, PhantomData))),
         }
     }
 
     /// Get a mutable reference to input buffers.
-    pub fn inputs(&mut self, tick: &Tick) -> &mut <TechRecipe<T> as Recipe>::Inputs {
+    pub fn inputs(&mut self, tick: &Tick) -> Feeding<'_, R::Inputs> {
         self.0.inputs(tick)
     }
 
     /// Amount of each input resource needed for one recipe cycle
-    pub const fn input_amounts(&self) -> <T as TechnologyEx>::InputAmountsType {
-        <T as TechnologyEx>::INPUT_AMOUNTS
+    pub const fn input_amounts(&self) -> R::InputAmountsType {
+        R::INPUT_AMOUNTS
     }
 
     /// Get a mutable reference to output buffers.
-    pub fn outputs(&mut self, tick: &Tick) -> &mut <TechRecipe<T> as Recipe>::Outputs {
+    pub fn outputs(&mut self, tick: &Tick) -> &mut R::Outputs {
         self.0.outputs(tick)
     }
+
+    /// Whether the lab has broken from being starved of inputs for too long, and no longer researches.
+    /// Only happens in [`Hardcore`](crate::gamemodes::Hardcore).
+    pub fn is_broken(&mut self, tick: &Tick) -> bool {
+        self.0.is_broken(tick)
+    }
+
+    /// Whether the lab's input and output buffers are all empty, so [`change_technology`](Lab::change_technology) will succeed.
+    pub fn is_empty(&mut self, tick: &Tick) -> bool {
+        self.0.is_empty(tick)
+    }
+
+    /// Runs the lab until it holds at least `points` research points, or generic research points, or `max_ticks` ticks have passed.
+    /// Returns `true` if the points were reached, like [`Tick::advance_until`].
+    ///
+    /// `feed` is called with the lab's input buffers to top them up from wherever science packs come from,
+    /// e.g. `|tick, inputs| inputs.0 += assembler.outputs(tick).0.empty()`.
+    /// While the lab has the inputs for its next point, time skips straight to when it is done.
+    pub fn run_until(
+        &mut self,
+        tick: &mut Tick,
+        points: u32,
+        mut feed: impl FnMut(&Tick, &mut R::Inputs),
+        max_ticks: u64,
+    ) -> bool {
+        let machine = RefCell::new(&mut self.0);
+        tick.advance_until_scheduled(
+            |tick| {
+                let mut machine = machine.borrow_mut();
+                feed(tick, &mut machine.inputs(tick));
+                // The only output is the research points.
+                let researched = R::iter_outputs(machine.outputs(tick))
+                    .map(|(_, _, amount)| *amount)
+                    .sum::<u32>();
+                researched >= points
+            },
+            // Without the inputs for a point, check again next tick in case `feed` has them by then.
+            |tick| {
+                Some(
+                    machine
+                        .borrow_mut()
+                        .ticks_until_next_output(tick)
+                        .unwrap_or(1),
+                )
+            },
+            max_ticks,
+        )
+    }
+
+    /// Number of ticks it takes to produce one research point if the lab is never starved.
+    ///
+    /// To research a technology `T` in `n` ticks, you need `T::REQUIRED_RESEARCH_POINTS * lab.ticks_per_point() / n` labs, rounded up.
+    pub const fn ticks_per_point(&self) -> u64 {
+        T::POINT_RECIPE_TICKS
+    }
+}
+
+/// How much [water](crate::resources::Water) an [`OffshorePump`] pumps per tick.
+pub const WATER_PER_TICK: u32 = 1;
+
+/// Proof that the [`OffshorePump`] has been unlocked, required to build one.
+/// Obtained by researching [`SteamSmeltingTechnology`](crate::research::SteamSmeltingTechnology).
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct OffshorePumpUnlock;
+
+impl OffshorePumpUnlock {
+    pub(crate) const fn new() -> Self {
+        Self
+    }
+}
+
+/// Pumps [water](crate::resources::Water) for [steam smelting](crate::recipes::SteamIronSmelting).
+/// Can only be built after it has been unlocked by researching [`SteamSmeltingTechnology`](crate::research::SteamSmeltingTechnology).
+///
+/// Water is a fluid, so it can't be carried around in bundles.
+/// The pump pumps [`WATER_PER_TICK`] every tick and holds on to it until [`pipe_to`](OffshorePump::pipe_to) moves it into a building.
+#[derive(Debug)]
+pub struct OffshorePump {
+    /// The tick up to which the pumped water has been piped out.
+    piped_until: u64,
+}
+
+impl OffshorePump {
+    /// Builds an offshore pump. Costs 5 [iron](crate::resources::Iron) and 2 [electronic circuits](crate::resources::ElectronicCircuit).
+    pub const fn build(
+        tick: &Tick,
+        unlock: &OffshorePumpUnlock,
+        iron: Bundle<Iron, 5>,
+        circuits: Bundle<ElectronicCircuit, 2>,
+    ) -> Self {
+        let _ = (unlock, iron, circuits);
+        Self {
+            piped_until: tick.cur(),
+        }
+    }
+
+    /// Moves all water pumped so far into input `I` of `dst`, and returns how much was moved.
+    /// E.g. `pump.pipe_to::<1, _>(&tick, &mut furnace)` feeds a [`SteamIronSmelting`](crate::recipes::SteamIronSmelting) furnace.
+    pub fn pipe_to<const I: usize, D: Building>(&mut self, tick: &Tick, dst: &mut D) -> u32
+    where
+        InputsOf<D>: Buffer<I, Content = Water>,
+    {
+        let ticks = tick.cur() - self.piped_until;
+        self.piped_until = tick.cur();
+        let pumped = u32::try_from(ticks)
+            .unwrap_or(u32::MAX)
+            .saturating_mul(WATER_PER_TICK);
+        stats::record_produced(tick, Water::NAME, pumped);
+        dst.input::<I>(tick).add(resource(pumped));
+        pumped
+    }
+}
+
+/// A building with input and output buffers, so several of them can be managed together in a [`MachineGroup`].
+pub trait Building: Sealed {
+    /// The recipe the building runs.
+    type Recipe: Recipe;
+
+    /// Update internal state and access input buffers.
+    fn inputs(&mut self, tick: &Tick) -> Feeding<'_, <Self::Recipe as Recipe>::Inputs>;
+
+    /// Update internal state and access output buffers.
+    fn outputs(&mut self, tick: &Tick) -> &mut <Self::Recipe as Recipe>::Outputs;
+
+    /// Update internal state and access input buffer `I`, e.g. `assembler.input::<1>(&tick)` for the second input.
+    /// Indices past the recipe's inputs don't compile.
+    fn input<const I: usize>(
+        &mut self,
+        tick: &Tick,
+    ) -> Feeding<'_, Resource<<InputsOf<Self> as Buffer<I>>::Content>>
+    where
+        InputsOf<Self>: Buffer<I>;
+
+    /// Update internal state and access output buffer `I`.
+    /// Indices past the recipe's outputs don't compile.
+    fn output<const I: usize>(
+        &mut self,
+        tick: &Tick,
+    ) -> &mut Resource<<OutputsOf<Self> as Buffer<I>>::Content>
+    where
+        OutputsOf<Self>: Buffer<I>,
+    {
+        self.outputs(tick).buffer()
+    }
+
+    /// How much input `I` one cycle of the building's recipe needs.
+    /// Indices past the recipe's inputs don't compile.
+    ///
+    /// ```
+    /// # use rustorio::{buildings::{Building, Furnace}, recipes::IronSmelting};
+    /// // Smelting iron takes one ore per cycle.
+    /// fn ore_per_cycle(furnace: &Furnace<IronSmelting>) -> u32 {
+    ///     furnace.input_needed::<0>()
+    /// }
+    /// ```
+    fn input_needed<const I: usize>(&self) -> u32
+    where
+        Self::Recipe: RecipeEx,
+        InputsOf<Self>: Buffer<I>,
+    {
+        let Some((_, needed, _)) =
+            Self::Recipe::iter_inputs(&mut Self::Recipe::new_inputs()).nth(I)
+        else {
+            unreachable!("Every buffer of the inputs is iterated")
+        };
+        needed
+    }
+
+    /// Tops up input buffer `I` from `source` to at most `capacity`, leaving the rest in `source`.
+    /// Returns whether the buffer is now full.
+    ///
+    /// Buffers have no capacity of their own, so `capacity` is whatever the caller wants to keep in the building,
+    /// e.g. a few cycles' worth so resources aren't tied up in one building while others starve.
+    fn feed_up_to<const I: usize>(
+        &mut self,
+        tick: &Tick,
+        source: &mut Resource<<InputsOf<Self> as Buffer<I>>::Content>,
+        capacity: u32,
+    ) -> bool
+    where
+        InputsOf<Self>: Buffer<I>,
+    {
+        let mut input = self.input::<I>(tick);
+        let room = capacity.saturating_sub(input.amount());
+        input.add(source.split_off_max(room));
+        input.amount() >= capacity
+    }
+
+    /// Moves all the ore mined in `territory` so far into input buffer `I`, returning how much was moved.
+    /// Shorthand for `building.inputs(&tick).0 += territory.resources(&tick).empty()`.
+    ///
+    /// ```
+    /// # use rustorio::{Tick, buildings::{Building, Furnace}, recipes::IronSmelting, resources::IronOre, territory::Territory};
+    /// fn collect(tick: &Tick, furnace: &mut Furnace<IronSmelting>, territory: &mut Territory<IronOre>) {
+    ///     furnace.pull_from::<0>(tick, territory);
+    /// }
+    /// ```
+    fn pull_from<const I: usize>(
+        &mut self,
+        tick: &Tick,
+        territory: &mut Territory<<InputsOf<Self> as Buffer<I>>::Content>,
+    ) -> u32
+    where
+        InputsOf<Self>: Buffer<I>,
+    {
+        let mined = territory.resources(tick).empty();
+        let amount = mined.amount();
+        *self.input::<I>(tick) += mined;
+        amount
+    }
+
+    /// Tops up input buffer `I` from both `a` and `b` to at most `capacity`, like a merging inserter fed by two producers.
+    /// Returns whether the buffer is now full.
+    ///
+    /// The room in the buffer is shared fairly: each source gives half, with any odd one out coming from the fuller source, or `a` if they are even.
+    /// If one source runs short, the other makes up the difference.
+    fn merge_into_input<const I: usize>(
+        &mut self,
+        tick: &Tick,
+        a: &mut Resource<<InputsOf<Self> as Buffer<I>>::Content>,
+        b: &mut Resource<<InputsOf<Self> as Buffer<I>>::Content>,
+        capacity: u32,
+    ) -> bool
+    where
+        InputsOf<Self>: Buffer<I>,
+    {
+        let mut input = self.input::<I>(tick);
+        let room = capacity.saturating_sub(input.amount());
+        let (fuller, emptier) = if a.amount() >= b.amount() {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let from_fuller = fuller.amount().min(room - emptier.amount().min(room / 2));
+        let from_emptier = emptier.amount().min(room - from_fuller);
+        input.add(fuller.split_off_max(from_fuller));
+        input.add(emptier.split_off_max(from_emptier));
+        input.amount() >= capacity
+    }
+
+    /// Adds all of `resource` to input buffer `I` if the buffer then holds at most `capacity`.
+    /// Otherwise nothing is added and `resource` is handed back in the error.
+    /// See [`Machine::try_feed`].
+    fn try_feed<const I: usize>(
+        &mut self,
+        tick: &Tick,
+        resource: Resource<<InputsOf<Self> as Buffer<I>>::Content>,
+        capacity: u32,
+    ) -> Result<(), TooMuchInputError<<InputsOf<Self> as Buffer<I>>::Content>>
+    where
+        InputsOf<Self>: Buffer<I>;
+
+    /// Update internal state and return how much more input buffer `I` can take before it holds `capacity`.
+    /// See [`Machine::input_headroom`].
+    fn input_headroom<const I: usize>(&mut self, tick: &Tick, capacity: u32) -> u32
+    where
+        InputsOf<Self>: Buffer<I>,
+    {
+        capacity.saturating_sub(self.input::<I>(tick).amount())
+    }
+
+    /// Feeds a building with two inputs from `first` and `second` so both buffers hold the same number of cycles' worth,
+    /// as many as the sources allow. What would only pile up in one buffer stays in its source,
+    /// so one-sided feeding never leaves the building waiting on the other input.
+    /// Returns how many cycles' worth both buffers now hold.
+    ///
+    /// For example, with [`RedScienceRecipe`](crate::recipes::RedScienceRecipe)
+    /// `assembler.feed_balanced(&tick, &mut iron, &mut circuits)` only moves as much iron as there are circuits to go with it.
+    fn feed_balanced(
+        &mut self,
+        tick: &Tick,
+        first: &mut Resource<<InputsOf<Self> as Buffer<0>>::Content>,
+        second: &mut Resource<<InputsOf<Self> as Buffer<1>>::Content>,
+    ) -> u32
+    where
+        InputsOf<Self>: Buffer<0> + Buffer<1>,
+    {
+        let first_needed = Self::Recipe::input_amount(0).expect("Recipe has a first input");
+        let second_needed = Self::Recipe::input_amount(1).expect("Recipe has a second input");
+        let mut inputs = self.inputs(tick);
+        let inputs = &mut *inputs;
+        let first_available = Buffer::<0>::buffer(inputs).amount() + first.amount();
+        let second_available = Buffer::<1>::buffer(inputs).amount() + second.amount();
+        let cycles = (first_available / first_needed).min(second_available / second_needed);
+
+        let first_buffer = Buffer::<0>::buffer(inputs);
+        first_buffer.add(
+            first.split_off_max((cycles * first_needed).saturating_sub(first_buffer.amount())),
+        );
+        let second_buffer = Buffer::<1>::buffer(inputs);
+        second_buffer.add(
+            second.split_off_max((cycles * second_needed).saturating_sub(second_buffer.amount())),
+        );
+        cycles
+    }
+
+    /// How many ticks until the building next finishes a cycle, or `None` if it won't without more inputs.
+    /// Use it with [`Tick::advance_until_scheduled`] to skip straight to the next tick where something happens.
+    fn ticks_until_next_output(&mut self, tick: &Tick) -> Option<u64>;
+
+    /// Update internal state and describe the building for logging, with each buffer as `Name: current/needed` and the progress of the current cycle.
+    /// See [`Machine::describe`] for the format.
+    fn describe(&mut self, tick: &Tick) -> String;
+
+    /// Calls `f` with the number of cycles completed whenever the building completes any.
+    /// See [`Machine::on_cycle`] for when it is called.
+    fn on_cycle(&mut self, f: impl FnMut(u32) + 'static);
+
+    /// Update internal state and return the fraction of recent ticks the building spent crafting rather than starved, from 0 to 1.
+    /// See [`Machine::utilization`].
+    fn utilization(&mut self, tick: &Tick) -> f64;
+}
+
+macro_rules! machine_building {
+    ($building:ident<$($param:ident: $bound:ident),+> runs $recipe:ident $(where $($predicate:tt)+)?) => {
+        impl<$($param: $bound),+> Sealed for $building<$($param),+> $(where $($predicate)+)? {}
+        impl<$($param: $bound),+> Building for $building<$($param),+> $(where $($predicate)+)? {
+            type Recipe = $recipe;
+
+            fn inputs(&mut self, tick: &Tick) -> Feeding<'_, $recipe::Inputs> {
+                self.0.inputs(tick)
+            }
+
+            fn input<const I: usize>(
+                &mut self,
+                tick: &Tick,
+            ) -> Feeding<'_, Resource<<InputsOf<Self> as Buffer<I>>::Content>>
+            where
+                InputsOf<Self>: Buffer<I>,
+            {
+                self.0.input(tick)
+            }
+
+            fn try_feed<const I: usize>(
+                &mut self,
+                tick: &Tick,
+                resource: Resource<<InputsOf<Self> as Buffer<I>>::Content>,
+                capacity: u32,
+            ) -> Result<(), TooMuchInputError<<InputsOf<Self> as Buffer<I>>::Content>>
+            where
+                InputsOf<Self>: Buffer<I>,
+            {
+                self.0.try_feed::<I>(tick, resource, capacity)
+            }
+
+            fn outputs(&mut self, tick: &Tick) -> &mut $recipe::Outputs {
+                self.0.outputs(tick)
+            }
+
+            fn ticks_until_next_output(&mut self, tick: &Tick) -> Option<u64> {
+                self.0.ticks_until_next_output(tick)
+            }
+
+            fn describe(&mut self, tick: &Tick) -> String {
+                self.0.describe(tick)
+            }
+
+            fn on_cycle(&mut self, f: impl FnMut(u32) + 'static) {
+                self.0.on_cycle(f);
+            }
+
+            fn utilization(&mut self, tick: &Tick) -> f64 {
+                self.0.utilization(tick)
+            }
+        }
+    };
+}
+
+machine_building!(Assembler<R: AssemblerRecipe> runs R);
+machine_building!(Furnace<R: FurnaceRecipe> runs R);
+machine_building!(SteelFurnace<R: SteelFurnaceRecipe> runs R);
+machine_building!(ElectricFurnace<R: ElectricFurnaceRecipe> runs R);
+machine_building!(Lab<T: Technology, R: LabRecipe> runs R where TechRecipe<T>: RecipeEx);
+
+/// A [`Building`] whose recipe can be changed to `R2`, turning it into the same kind of building running `R2`.
+/// Lets generic code, like [`MachineGroup::change_recipe`], change recipes whatever the building.
+pub trait ChangeRecipe<R2: Recipe>: Building + Sized {
+    /// The building running `R2`.
+    type Changed: Building<Recipe = R2>;
+
+    /// Changes the recipe of the building, like its own `change_recipe` method.
+    /// Returns the original building if the input and output buffers are not empty.
+    fn change_recipe(self, recipe: R2) -> Result<Self::Changed, MachineNotEmptyError<Self>>;
+}
+
+macro_rules! change_recipe_building {
+    ($building:ident<$param:ident: $bound:ident>) => {
+        impl<$param: $bound, R2: $bound> ChangeRecipe<R2> for $building<$param> {
+            type Changed = $building<R2>;
+
+            fn change_recipe(
+                self,
+                recipe: R2,
+            ) -> Result<$building<R2>, MachineNotEmptyError<Self>> {
+                $building::change_recipe(self, recipe)
+            }
+        }
+    };
+}
+
+change_recipe_building!(Assembler<R: AssemblerRecipe>);
+change_recipe_building!(Furnace<R: FurnaceRecipe>);
+change_recipe_building!(SteelFurnace<R: SteelFurnaceRecipe>);
+change_recipe_building!(ElectricFurnace<R: ElectricFurnaceRecipe>);
+
+macro_rules! persist_building {
+    ($building:ident<$param:ident: $bound:ident>) => {
+        impl<$param: $bound> Persist for $building<$param>
+        where
+            Machine<$param>: Persist,
+        {
+            type Snapshot = <Machine<$param> as Persist>::Snapshot;
+
+            fn snapshot(self) -> Self::Snapshot {
+                self.0.snapshot()
+            }
+
+            fn restore(snapshot: Self::Snapshot, tick: &Tick) -> Self {
+                Self(Machine::restore(snapshot, tick))
+            }
+        }
+    };
+}
+
+persist_building!(Assembler<R: AssemblerRecipe>);
+persist_building!(Furnace<R: FurnaceRecipe>);
+persist_building!(SteelFurnace<R: SteelFurnaceRecipe>);
+persist_building!(ElectricFurnace<R: ElectricFurnaceRecipe>);
+
+impl<T: Technology, R: LabRecipe> Persist for Lab<T, R>
+where
+    TechRecipe<T>: RecipeEx,
+    Machine<R>: Persist,
+{
+    type Snapshot = <Machine<R> as Persist>::Snapshot;
+
+    fn snapshot(self) -> Self::Snapshot {
+        self.0.snapshot()
+    }
+
+    fn restore(snapshot: Self::Snapshot, tick: &Tick) -> Self {
+        Self(Machine::restore(snapshot, tick), PhantomData)
+    }
+}
+
+// Water that hasn't been piped out yet is lost when saving, so a save can't be edited to pump more.
+impl Persist for OffshorePump {
+    type Snapshot = ();
+
+    fn snapshot(self) {}
+
+    fn restore((): (), tick: &Tick) -> Self {
+        Self {
+            piped_until: tick.cur(),
+        }
+    }
+}
+
+type InputsOf<B> = <<B as Building>::Recipe as Recipe>::Inputs;
+type OutputsOf<B> = <<B as Building>::Recipe as Recipe>::Outputs;
+
+/// Moves everything in output `SI` of `src` into input `DI` of `dst`, like an inserter between the two buildings.
+/// Returns how much was moved.
+///
+/// The buffers must hold the same resource, e.g. `pipe::<0, 1, _, _>(&tick, &mut wire_assembler, &mut circuit_assembler)`
+/// feeds copper wire into the second input of an [`ElectronicCircuitRecipe`](crate::recipes::ElectronicCircuitRecipe) assembler.
+pub fn pipe<const SI: usize, const DI: usize, S: Building, D: Building>(
+    tick: &Tick,
+    src: &mut S,
+    dst: &mut D,
+) -> u32
+where
+    OutputsOf<S>: Buffer<SI>,
+    InputsOf<D>: Buffer<DI, Content = <OutputsOf<S> as Buffer<SI>>::Content>,
+{
+    let moved = src.output::<SI>(tick).empty();
+    let amount = moved.amount();
+    dst.input::<DI>(tick).add(moved);
+    amount
+}
+
+/// Several buildings of the same kind, fed and emptied together.
+///
+/// Buffers are picked by their index in the recipe, so `group.fill_input::<1>(&tick, &mut copper_wire)`
+/// spreads copper wire over the second input of every [`ElectronicCircuitRecipe`](crate::recipes::ElectronicCircuitRecipe) assembler in the group.
+#[derive(Debug)]
+pub struct MachineGroup<B: Building> {
+    buildings: Vec<B>,
+}
+
+impl<B: Building> MachineGroup<B> {
+    /// Creates an empty group.
+    pub const fn new() -> Self {
+        Self {
+            buildings: Vec::new(),
+        }
+    }
+
+    /// Adds a building to the group.
+    pub fn push(&mut self, building: B) {
+        self.buildings.push(building);
+    }
+
+    /// The buildings in the group.
+    pub fn buildings(&mut self) -> &mut [B] {
+        &mut self.buildings
+    }
+
+    /// Takes the buildings back out of the group.
+    pub fn into_buildings(self) -> Vec<B> {
+        self.buildings
+    }
+
+    /// How many ticks until any building in the group next finishes a cycle, or `None` if none will without more inputs.
+    pub fn ticks_until_next_output(&mut self, tick: &Tick) -> Option<u64> {
+        self.buildings
+            .iter_mut()
+            .filter_map(|building| building.ticks_until_next_output(tick))
+            .min()
+    }
+
+    /// Spreads all of `source` as evenly as possible over input `I` of every building in the group.
+    /// Earlier buildings get the remainder. Does nothing if the group is empty.
+    pub fn fill_input<const I: usize>(
+        &mut self,
+        tick: &Tick,
+        source: &mut Resource<<InputsOf<B> as Buffer<I>>::Content>,
+    ) where
+        InputsOf<B>: Buffer<I>,
+    {
+        let Ok(count) = u32::try_from(self.buildings.len()) else {
+            unreachable!("A group can't have more buildings than there are resources to build them")
+        };
+        if count == 0 {
+            return;
+        }
+        let share = source.amount() / count;
+        let remainder = source.amount() % count;
+        for (i, building) in (0..).zip(&mut self.buildings) {
+            let amount = share + u32::from(i < remainder);
+            building
+                .inputs(tick)
+                .buffer()
+                .add(source.split_off_max(amount));
+        }
+    }
+
+    /// Spreads all of `source` over input `I` of the buildings in the group so they end up as even as possible,
+    /// topping up the emptiest buildings first. Unlike [`fill_input`](MachineGroup::fill_input),
+    /// buildings that already have more than their share get nothing.
+    /// Does nothing if the group is empty.
+    pub fn distribute<const I: usize>(
+        &mut self,
+        tick: &Tick,
+        source: &mut Resource<<InputsOf<B> as Buffer<I>>::Content>,
+    ) where
+        InputsOf<B>: Buffer<I>,
+    {
+        if self.buildings.is_empty() {
+            return;
+        }
+        let amounts: Vec<u32> = self
+            .buildings
+            .iter_mut()
+            .map(|building| building.inputs(tick).buffer().amount())
+            .collect();
+        let shares = balanced_shares(&amounts, source.amount());
+        for (building, share) in self.buildings.iter_mut().zip(shares) {
+            building
+                .inputs(tick)
+                .buffer()
+                .add(source.split_off_max(share));
+        }
+    }
+
+    /// Takes everything in output `I` of every building in the group.
+    pub fn collect_output<const I: usize>(
+        &mut self,
+        tick: &Tick,
+    ) -> Resource<<OutputsOf<B> as Buffer<I>>::Content>
+    where
+        OutputsOf<B>: Buffer<I>,
+    {
+        self.buildings
+            .iter_mut()
+            .map(|building| building.outputs(tick).buffer().empty())
+            .sum()
+    }
+
+    /// Moves every output of every building in the group that holds `C` into `dest`, whatever its index,
+    /// so recipes with several outputs of the same resource are drained in one go.
+    /// Returns how much was moved.
+    pub fn collect_all_into<C: ResourceType>(&mut self, tick: &Tick, dest: &mut Resource<C>) -> u32
+    where
+        B::Recipe: RecipeEx,
+    {
+        let mut collected = 0;
+        for building in &mut self.buildings {
+            for (name, _, amount) in B::Recipe::iter_outputs(building.outputs(tick)) {
+                if name == C::NAME {
+                    collected += std::mem::take(amount);
+                }
+            }
+        }
+        dest.add(resource::<C>(collected));
+        collected
+    }
+
+    /// Takes back everything left in input `I` of every building in the group, e.g. before changing their recipe.
+    pub fn empty_input<const I: usize>(
+        &mut self,
+        tick: &Tick,
+    ) -> Resource<<InputsOf<B> as Buffer<I>>::Content>
+    where
+        InputsOf<B>: Buffer<I>,
+    {
+        self.buildings
+            .iter_mut()
+            .map(|building| building.inputs(tick).buffer().empty())
+            .sum()
+    }
+
+    /// Changes the recipe of every building in the group, e.g. to switch a group of furnaces from iron to copper.
+    ///
+    /// Every building must be empty, see [`empty_input`](MachineGroup::empty_input) and [`collect_output`](MachineGroup::collect_output).
+    /// Otherwise nothing changes, and the error returns the group along with the first resource left in it.
+    pub fn change_recipe<R2: Recipe + Copy>(
+        mut self,
+        tick: &Tick,
+        recipe: R2,
+    ) -> Result<MachineGroup<B::Changed>, MachineNotEmptyError<Self>>
+    where
+        B: ChangeRecipe<R2>,
+        B::Recipe: RecipeEx,
+    {
+        let nonempty = self
+            .buildings
+            .iter_mut()
+            .find_map(|building| first_nonempty(tick, building));
+        if let Some((resource_type, amount, location)) = nonempty {
+            return Err(MachineNotEmptyError {
+                machine: self,
+                resource_type,
+                amount,
+                location,
+            });
+        }
+        let mut buildings = Vec::with_capacity(self.buildings.len());
+        let mut rest = self.buildings.into_iter();
+        while let Some(building) = rest.next() {
+            match building.change_recipe(recipe) {
+                Ok(building) => buildings.push(building),
+                Err(error) => {
+                    return Err(error.map_machine(|building| MachineGroup {
+                        buildings: core::iter::once(building).chain(rest).collect(),
+                    }));
+                }
+            }
+        }
+        Ok(MachineGroup { buildings })
+    }
+
+    /// The average [utilization](Building::utilization) of the buildings in the group, or `None` if the group is empty.
+    pub fn utilization(&mut self, tick: &Tick) -> Option<f64> {
+        if self.buildings.is_empty() {
+            return None;
+        }
+        let total: f64 = self
+            .buildings
+            .iter_mut()
+            .map(|building| building.utilization(tick))
+            .sum();
+        Some(total / self.buildings.len() as f64)
+    }
+
+    /// Moves one building between this group and `other`, e.g. furnaces between iron and copper smelting,
+    /// from whichever group is less [utilized](MachineGroup::utilization) to the busier one.
+    /// `recipe` and `other_recipe` are the recipes of this group and `other`, which the moved building switches to.
+    ///
+    /// A building only moves if the busier group's utilization is more than `threshold` higher, see [`DEFAULT_REBALANCE_THRESHOLD`].
+    /// That keeps buildings from going back and forth between groups that are about as busy,
+    /// as changing the recipe starts the moved building's utilization over.
+    /// Only an empty building moves, the last one in its group, and never the group's only building.
+    pub fn rebalance<B2>(
+        &mut self,
+        tick: &Tick,
+        other: &mut MachineGroup<B2>,
+        recipe: <B as Building>::Recipe,
+        other_recipe: <B2 as Building>::Recipe,
+        threshold: f64,
+    ) -> Rebalanced
+    where
+        B: ChangeRecipe<<B2 as Building>::Recipe, Changed = B2>,
+        B2: ChangeRecipe<<B as Building>::Recipe, Changed = B>,
+        <B as Building>::Recipe: RecipeEx,
+        <B2 as Building>::Recipe: RecipeEx,
+    {
+        let (Some(ours), Some(theirs)) = (self.utilization(tick), other.utilization(tick)) else {
+            return Rebalanced::Unchanged;
+        };
+        if ours - theirs > threshold {
+            if let Some(building) = other.take_empty(tick) {
+                self.buildings.push(
+                    building
+                        .change_recipe(recipe)
+                        .unwrap_or_else(|_| unreachable!("Only empty buildings are taken")),
+                );
+                return Rebalanced::Pulled;
+            }
+        } else if theirs - ours > threshold
+            && let Some(building) = self.take_empty(tick)
+        {
+            other.buildings.push(
+                building
+                    .change_recipe(other_recipe)
+                    .unwrap_or_else(|_| unreachable!("Only empty buildings are taken")),
+            );
+            return Rebalanced::Pushed;
+        }
+        Rebalanced::Unchanged
+    }
+
+    /// Removes the last empty building from the group, unless it is the only building.
+    fn take_empty(&mut self, tick: &Tick) -> Option<B>
+    where
+        B::Recipe: RecipeEx,
+    {
+        if self.buildings.len() <= 1 {
+            return None;
+        }
+        let index = self
+            .buildings
+            .iter_mut()
+            .rposition(|building| first_nonempty(tick, building).is_none())?;
+        Some(self.buildings.remove(index))
+    }
+}
+
+/// How much more [utilized](MachineGroup::utilization) one group must be than another before [`MachineGroup::rebalance`] moves a building to it.
+pub const DEFAULT_REBALANCE_THRESHOLD: f64 = 0.25;
+
+/// What [`MachineGroup::rebalance`] did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rebalanced {
+    /// No building moved.
+    Unchanged,
+    /// A building moved from the other group into this one.
+    Pulled,
+    /// A building moved from this group into the other one.
+    Pushed,
+}
+
+/// The first resource left in `building`, along with its amount and where it is, or `None` if the building is empty.
+fn first_nonempty<B: Building>(
+    tick: &Tick,
+    building: &mut B,
+) -> Option<(&'static str, u32, BufferLocation)>
+where
+    B::Recipe: RecipeEx,
+{
+    let input = find_nonempty(
+        B::Recipe::iter_inputs(&mut building.inputs(tick)),
+        BufferLocation::Input,
+    );
+    input.or_else(|| {
+        find_nonempty(
+            B::Recipe::iter_outputs(building.outputs(tick)),
+            BufferLocation::Output,
+        )
+    })
+}
+
+/// Splits `total` into shares that bring `amounts` as close to even as possible.
+/// Ties go to earlier entries.
+fn balanced_shares(amounts: &[u32], total: u32) -> Vec<u32> {
+    let mut order: Vec<usize> = (0..amounts.len()).collect();
+    order.sort_by_key(|&i| amounts[i]);
+    let Some(&lowest) = order.first() else {
+        return Vec::new();
+    };
+    // Raise the lowest `raised` amounts to a common `level`, as far as `remaining` allows.
+    let mut remaining = total;
+    let mut level = amounts[lowest];
+    let mut raised = 1;
+    while raised < order.len() {
+        let next = amounts[order[raised]];
+        let cost = (next - level) * u32::try_from(raised).unwrap();
+        if cost > remaining {
+            break;
+        }
+        remaining -= cost;
+        level = next;
+        raised += 1;
+    }
+    let count = u32::try_from(raised).unwrap();
+    level += remaining / count;
+    remaining %= count;
+
+    let mut shares = vec![0; amounts.len()];
+    let mut lowest = order[..raised].to_vec();
+    lowest.sort_unstable();
+    for (rank, i) in (0..).zip(lowest) {
+        shares[i] = level - amounts[i] + u32::from(rank < remaining);
+    }
+    shares
+}
+
+impl<B: Building> Default for MachineGroup<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: Building> From<Vec<B>> for MachineGroup<B> {
+    fn from(buildings: Vec<B>) -> Self {
+        Self { buildings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AdvanceCause, AdvanceEvent, ResearchPoint,
+        gamemodes::{SandboxStartingResources, run_sandbox},
+        recipes::{
+            CopperSmelting, CopperWireRecipe, ElectronicCircuitRecipe, IronSmelting,
+            RedScienceRecipe,
+        },
+        research::ElectricFurnaceTechnology,
+    };
+
+    #[test]
+    fn feed_up_to_only_tops_up() {
+        run_sandbox(
+            |mut tick,
+             SandboxStartingResources {
+                 mut iron_ore,
+                 mut iron,
+                 ..
+             }| {
+                let mut furnace = Furnace::build(&tick, IronSmelting, iron.bundle().unwrap());
+                let mut ore = iron_ore.split_off(25).unwrap();
+
+                assert!(furnace.feed_up_to::<0>(&tick, &mut ore, 10));
+                assert_eq!((furnace.input::<0>(&tick).amount(), ore.amount()), (10, 15));
+
+                // Already full, so nothing moves.
+                assert!(furnace.feed_up_to::<0>(&tick, &mut ore, 10));
+                assert_eq!((furnace.input::<0>(&tick).amount(), ore.amount()), (10, 15));
+
+                // Repeated top-ups only replace what was smelted.
+                let mut fed = 10;
+                while ore.amount() > 0 {
+                    tick.advance_by(7);
+                    let before = furnace.input::<0>(&tick).amount();
+                    let full = furnace.feed_up_to::<0>(&tick, &mut ore, 10);
+                    let after = furnace.input::<0>(&tick).amount();
+                    assert!(after <= 10);
+                    fed += after - before;
+                    assert_eq!(fed + ore.amount(), 25);
+                    assert_eq!(full, after == 10);
+                }
+                assert!(!furnace.feed_up_to::<0>(&tick, &mut ore, 100));
+                (tick, ())
+            },
+        );
+    }
+
+    #[test]
+    fn feed_balanced_feeds_whole_cycles() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut iron,
+                mut copper_wire,
+                mut electronic_circuit,
+                ..
+            } = starting_resources;
+            let mut assembler = Assembler::build(
+                &tick,
+                RedScienceRecipe,
+                copper_wire.bundle().unwrap(),
+                iron.bundle().unwrap(),
+            );
+            let mut iron = iron.split_off(30).unwrap();
+            let mut circuits = Resource::new_empty();
+
+            // Nothing moves while one side is missing.
+            assert_eq!(assembler.feed_balanced(&tick, &mut iron, &mut circuits), 0);
+            assert_eq!(assembler.input::<0>(&tick).amount(), 0);
+            assert_eq!(iron.amount(), 30);
+
+            // Circuits trickle in, and every cycle fed has both inputs.
+            for _ in 0..10 {
+                circuits += electronic_circuit.split_off(3).unwrap();
+                let cycles = assembler.feed_balanced(&tick, &mut iron, &mut circuits);
+                assert_eq!(assembler.input::<0>(&tick).amount(), cycles);
+                assert_eq!(assembler.input::<1>(&tick).amount(), cycles);
+                assert_eq!(circuits.amount(), 0);
+                tick.advance_by(2);
+            }
+
+            // With both inputs balanced, the assembler works through everything it was fed.
+            assert!(assembler.ticks_until_next_output(&tick).is_some());
+            tick.advance_by(1_000);
+            assert_eq!(assembler.input::<0>(&tick).amount(), 0);
+            assert_eq!(assembler.input::<1>(&tick).amount(), 0);
+            assert_eq!(iron.amount(), 0);
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn merge_drains_both_sources_evenly() {
+        run_sandbox(
+            |tick,
+             SandboxStartingResources {
+                 mut iron,
+                 mut copper_wire,
+                 ..
+             }| {
+                let mut assembler = Assembler::build(
+                    &tick,
+                    ElectronicCircuitRecipe,
+                    copper_wire.bundle().unwrap(),
+                    iron.bundle().unwrap(),
+                );
+
+                let mut north = iron.split_off(20).unwrap();
+                let mut south = iron.split_off(20).unwrap();
+                assert!(assembler.merge_into_input::<0>(&tick, &mut north, &mut south, 10));
+                assert_eq!((north.amount(), south.amount()), (15, 15));
+                assert!(assembler.merge_into_input::<0>(&tick, &mut north, &mut south, 21));
+                assert_eq!(assembler.input::<0>(&tick).amount(), 21);
+                // The odd one out comes from the first source when both are even.
+                assert_eq!((north.amount(), south.amount()), (9, 10));
+
+                // When one source runs short, the other makes up the difference.
+                let mut scarce = iron.split_off(2).unwrap();
+                assert!(assembler.merge_into_input::<0>(&tick, &mut south, &mut scarce, 31));
+                assert_eq!((south.amount(), scarce.amount()), (2, 0));
+
+                // Not enough in either to fill up.
+                assert!(!assembler.merge_into_input::<0>(&tick, &mut north, &mut south, 50));
+                assert_eq!((north.amount(), south.amount()), (0, 0));
+                assert_eq!(assembler.input::<0>(&tick).amount(), 42);
+                (tick, ())
+            },
+        );
+    }
+
+    #[test]
+    fn pipe_moves_outputs_into_inputs() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut copper_ore,
+                mut iron,
+                mut copper_wire,
+                ..
+            } = starting_resources;
+            let mut furnace = Furnace::build(&tick, CopperSmelting, iron.bundle().unwrap());
+            let mut wire_assembler = Assembler::build(
+                &tick,
+                CopperWireRecipe,
+                copper_wire.bundle().unwrap(),
+                iron.bundle().unwrap(),
+            );
+            let mut circuit_assembler = Assembler::build(
+                &tick,
+                ElectronicCircuitRecipe,
+                copper_wire.bundle().unwrap(),
+                iron.bundle().unwrap(),
+            );
+            circuit_assembler.inputs(&tick).0 += iron.split_off(10).unwrap();
+
+            furnace.inputs(&tick).0 += copper_ore.split_off(5).unwrap();
+            tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 5, 1000);
+            assert_eq!(
+                pipe::<0, 0, _, _>(&tick, &mut furnace, &mut wire_assembler),
+                5
+            );
+            assert_eq!(furnace.outputs(&tick).0.amount(), 0);
+
+            tick.advance_until(|tick| wire_assembler.outputs(tick).0.amount() >= 10, 1000);
+            assert_eq!(
+                pipe::<0, 1, _, _>(&tick, &mut wire_assembler, &mut circuit_assembler),
+                10
+            );
+            assert_eq!(circuit_assembler.input::<1>(&tick).amount(), 10);
+
+            tick.advance_until(|tick| circuit_assembler.outputs(tick).0.amount() >= 5, 1000);
+            // Nothing left to move.
+            assert_eq!(
+                pipe::<0, 1, _, _>(&tick, &mut wire_assembler, &mut circuit_assembler),
+                0
+            );
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn faster_furnaces_smelt_faster() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut iron_ore,
+                mut iron,
+                mut steel,
+                mut electronic_circuit,
+                steel_furnace_unlock,
+                electric_furnace_unlock,
+                ..
+            } = starting_resources;
+            let mut furnace = Furnace::build(&tick, IronSmelting, iron.bundle().unwrap());
+            let mut steel_furnace = SteelFurnace::build(
+                &tick,
+                &steel_furnace_unlock,
+                IronSmelting,
+                steel.bundle().unwrap(),
+            );
+            let mut electric_furnace = ElectricFurnace::build(
+                &tick,
+                &electric_furnace_unlock,
+                IronSmelting,
+                steel.bundle().unwrap(),
+                electronic_circuit.bundle().unwrap(),
+            );
+            furnace.inputs(&tick).0 += iron_ore.split_off(100).unwrap();
+            steel_furnace.inputs(&tick).0 += iron_ore.split_off(100).unwrap();
+            electric_furnace.inputs(&tick).0 += iron_ore.split_off(100).unwrap();
+
+            tick.advance_by(IronSmelting::time() * 5);
+            let smelted = furnace.outputs(&tick).0.amount();
+            assert!(smelted > 0);
+            assert_eq!(steel_furnace.outputs(&tick).0.amount(), 2 * smelted);
+            assert_eq!(
+                u64::from(electric_furnace.outputs(&tick).0.amount()),
+                u64::from(smelted) * ElectricFurnace::<IronSmelting>::SPEED
+            );
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn lab_run_until_waits_for_science_packs() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut iron,
+                mut copper,
+                mut red_science,
+                steel_technology,
+                ..
+            } = starting_resources;
+            let mut lab = Lab::build(
+                &tick,
+                &steel_technology,
+                iron.bundle().unwrap(),
+                copper.bundle().unwrap(),
+            );
+            let points = 20;
+            let minimal_ticks = u64::from(points) * lab.ticks_per_point();
+
+            // With science packs always at hand, the lab never waits.
+            assert!(lab.run_until(
+                &mut tick,
+                points,
+                |_, inputs| inputs.0 += red_science.split_off(1).unwrap(),
+                10 * minimal_ticks,
+            ));
+            assert_eq!(tick.cur(), minimal_ticks);
+            assert_eq!(lab.outputs(&tick).0.empty().amount(), points);
+            red_science += lab.inputs(&tick).0.empty();
+
+            // Science packs only arriving later delay the research by as long.
+            let arrival = tick.cur() + 50;
+            assert!(lab.run_until(
+                &mut tick,
+                points,
+                |tick, inputs| {
+                    if tick.cur() >= arrival {
+                        inputs.0 += red_science.split_off(1).unwrap();
+                    }
+                },
+                10 * minimal_ticks,
+            ));
+            assert_eq!(tick.cur(), arrival + minimal_ticks);
+
+            // Without any science packs, it gives up after `max_ticks`, logged as a single wait.
+            let start = tick.cur();
+            let _ = lab.outputs(&tick).0.empty();
+            tick.start_event_log(4);
+            assert!(!lab.run_until(&mut tick, points, |_, _| {}, 30));
+            assert_eq!(tick.cur(), start + 30);
+            let events: Vec<_> = tick.event_log().unwrap().events().copied().collect();
+            assert_eq!(
+                events,
+                [AdvanceEvent {
+                    tick: start,
+                    ticks: 30,
+                    cause: AdvanceCause::AdvanceUntil
+                }]
+            );
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn lab_researches_generic_points() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut iron,
+                mut copper,
+                mut red_science,
+                mut green_science,
+                mut blue_science,
+                ..
+            } = starting_resources;
+            let lab = Lab::build(
+                &tick,
+                &ElectricFurnaceTechnology,
+                iron.bundle().unwrap(),
+                copper.bundle().unwrap(),
+            );
+            let mut lab = lab.research_generic().unwrap();
+            assert!(lab.run_until(
+                &mut tick,
+                180,
+                |_, inputs| {
+                    inputs.0 += red_science.split_off(1).unwrap();
+                    inputs.1 += green_science.split_off(1).unwrap();
+                    inputs.2 += blue_science.split_off(1).unwrap();
+                },
+                1000,
+            ));
+            let mut generic = lab.outputs(&tick).0.empty();
+
+            // The electric furnace takes all three science packs per point, so each point costs three generic points.
+            let mut points = ResearchPoint::<ElectricFurnaceTechnology>::from_generic(
+                &mut generic,
+                ElectricFurnaceTechnology::REQUIRED_RESEARCH_POINTS,
+            )
+            .unwrap();
+            assert_eq!(generic.amount(), 30);
+            let _ = ElectricFurnaceTechnology.research(&tick, points.bundle().unwrap());
+
+            let _ = lab.inputs(&tick).0.empty();
+            let _ = lab.inputs(&tick).1.empty();
+            let _ = lab.inputs(&tick).2.empty();
+            assert!(lab.change_technology(&ElectricFurnaceTechnology).is_ok());
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn pumped_water_is_piped_in_at_once() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut iron_ore,
+                mut iron,
+                mut electronic_circuit,
+                steam_iron_smelting,
+                offshore_pump_unlock,
+                ..
+            } = starting_resources;
+            let mut pump = OffshorePump::build(
+                &tick,
+                &offshore_pump_unlock,
+                iron.bundle().unwrap(),
+                electronic_circuit.bundle().unwrap(),
+            );
+            let mut furnace = Furnace::build(&tick, steam_iron_smelting, iron.bundle().unwrap());
+            furnace.inputs(&tick).0 += iron_ore.split_off(4).unwrap();
+
+            // Without water, the ore just sits there.
+            tick.advance_by(10);
+            assert_eq!(furnace.output::<0>(&tick).amount(), 0);
+
+            assert_eq!(
+                pump.pipe_to::<1, _>(&tick, &mut furnace),
+                10 * WATER_PER_TICK
+            );
+            assert_eq!(pump.pipe_to::<1, _>(&tick, &mut furnace), 0);
+            assert!(tick.advance_until(|tick| furnace.output::<0>(tick).amount() >= 6, 100));
+            assert_eq!(furnace.input::<0>(&tick).amount(), 0);
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn machine_group_spreads_and_collects() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut iron_ore,
+                mut copper_ore,
+                mut iron,
+                ..
+            } = starting_resources;
+            let mut furnaces = MachineGroup::new();
+            for _ in 0..3 {
+                furnaces.push(Furnace::build(&tick, IronSmelting, iron.bundle().unwrap()));
+            }
+            assert_eq!(furnaces.buildings()[0].input_needed::<0>(), 1);
+
+            // 31 ore over 3 furnaces: the first furnace gets the remainder.
+            let mut ore = iron_ore.split_off(31).unwrap();
+            furnaces.fill_input::<0>(&tick, &mut ore);
+            assert_eq!(ore.amount(), 0);
+            let loads: Vec<u32> = furnaces
+                .buildings()
+                .iter_mut()
+                .map(|furnace| furnace.input::<0>(&tick).amount())
+                .collect();
+            assert_eq!(loads, [11, 10, 10]);
+
+            tick.advance_by(60);
+            let smelted = furnaces.collect_output::<0>(&tick);
+            assert_eq!(smelted.amount(), 30);
+            assert_eq!(furnaces.empty_input::<0>(&tick).amount(), 1);
+
+            // Distributing tops up the emptiest furnaces first.
+            furnaces.buildings()[0].inputs(&tick).0 += iron_ore.split_off(5).unwrap();
+            let mut ore = iron_ore.split_off(7).unwrap();
+            furnaces.distribute::<0>(&tick, &mut ore);
+            assert_eq!(ore.amount(), 0);
+            let loads: Vec<u32> = furnaces
+                .buildings()
+                .iter_mut()
+                .map(|furnace| furnace.input::<0>(&tick).amount())
+                .collect();
+            assert_eq!(loads, [5, 4, 3]);
+
+            // Collecting into an existing pile conserves every smelted ingot.
+            tick.advance_by(60);
+            let mut pile = smelted;
+            assert_eq!(furnaces.collect_all_into(&tick, &mut pile), 12);
+            assert_eq!(furnaces.collect_all_into(&tick, &mut pile), 0);
+            assert_eq!(pile.amount(), 42);
+
+            // Switching recipes needs every furnace to be empty, and gives the group back otherwise.
+            furnaces.fill_input::<0>(&tick, &mut iron_ore.split_off(5).unwrap());
+            tick.advance_by(60);
+            let err = furnaces.change_recipe(&tick, CopperSmelting).unwrap_err();
+            assert_eq!(
+                (err.resource_type, err.amount, err.location),
+                ("Iron", 2, BufferLocation::Output)
+            );
+            let mut furnaces = err.machine;
+            pile += furnaces.collect_output::<0>(&tick);
+            let mut furnaces = furnaces.change_recipe(&tick, CopperSmelting).unwrap();
+            assert_eq!(furnaces.buildings().len(), 3);
+            furnaces.fill_input::<0>(&tick, &mut copper_ore.split_off(3).unwrap());
+            tick.advance_by(60);
+            assert_eq!(furnaces.collect_output::<0>(&tick).amount(), 3);
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn rebalance_moves_idle_buildings() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut iron_ore,
+                mut copper_ore,
+                mut iron,
+                ..
+            } = starting_resources;
+            let mut iron_furnaces = MachineGroup::new();
+            let mut copper_furnaces = MachineGroup::new();
+            for _ in 0..2 {
+                iron_furnaces.push(Furnace::build(&tick, IronSmelting, iron.bundle().unwrap()));
+                copper_furnaces.push(Furnace::build(
+                    &tick,
+                    CopperSmelting,
+                    iron.bundle().unwrap(),
+                ));
+            }
+
+            // Copper smelting idles for 5% of every round, which isn't worth moving a furnace over.
+            for _ in 0..8 {
+                iron_furnaces.fill_input::<0>(&tick, &mut iron_ore.split_off(40).unwrap());
+                copper_furnaces.fill_input::<0>(&tick, &mut copper_ore.split_off(19).unwrap());
+                tick.advance_by(60);
+                let _ = iron_furnaces.collect_output::<0>(&tick);
+                let _ = copper_furnaces.collect_output::<0>(&tick);
+                assert_eq!(copper_furnaces.utilization(&tick), Some(0.95));
+                assert_eq!(
+                    iron_furnaces.rebalance(
+                        &tick,
+                        &mut copper_furnaces,
+                        IronSmelting,
+                        CopperSmelting,
+                        DEFAULT_REBALANCE_THRESHOLD
+                    ),
+                    Rebalanced::Unchanged
+                );
+                assert_eq!(copper_furnaces.buildings().len(), 2);
+            }
+
+            // Without a threshold, the slightest difference moves an empty copper furnace over.
+            assert_eq!(
+                copper_furnaces.rebalance(
+                    &tick,
+                    &mut iron_furnaces,
+                    CopperSmelting,
+                    IronSmelting,
+                    0.0
+                ),
+                Rebalanced::Pushed
+            );
+            assert_eq!(
+                (
+                    iron_furnaces.buildings().len(),
+                    copper_furnaces.buildings().len()
+                ),
+                (3, 1)
+            );
+
+            // A group always keeps its last building, however idle.
+            tick.advance_by(1000);
+            assert!(copper_furnaces.utilization(&tick) < iron_furnaces.utilization(&tick));
+            assert_eq!(
+                iron_furnaces.rebalance(
+                    &tick,
+                    &mut copper_furnaces,
+                    IronSmelting,
+                    CopperSmelting,
+                    0.0
+                ),
+                Rebalanced::Unchanged
+            );
+            (tick, ())
+        });
+    }
+
+    const SCHEDULED_FURNACES: u32 = 10;
+    const SCHEDULED_TARGET: u32 = 100;
+
+    fn build_furnaces(
+        tick: &Tick,
+        starting_resources: SandboxStartingResources,
+    ) -> MachineGroup<Furnace<IronSmelting>> {
+        let SandboxStartingResources {
+            mut iron_ore,
+            mut iron,
+            ..
+        } = starting_resources;
+        let mut furnaces = MachineGroup::new();
+        for i in 1..=SCHEDULED_FURNACES {
+            let mut furnace = Furnace::build(tick, IronSmelting, iron.bundle().unwrap());
+            furnace.inputs(tick).0 += iron_ore.split_off(i * 3).unwrap();
+            furnaces.push(furnace);
+        }
+        furnaces
+    }
+
+    fn smelted(tick: &Tick, furnaces: &mut MachineGroup<Furnace<IronSmelting>>) -> u32 {
+        furnaces
+            .buildings()
+            .iter_mut()
+            .map(|furnace| furnace.output::<0>(tick).amount())
+            .sum()
+    }
+
+    #[test]
+    fn scheduled_advance_matches_stepping() {
+        let stepped = run_sandbox(|mut tick, starting_resources| {
+            let mut furnaces = build_furnaces(&tick, starting_resources);
+            assert!(tick.advance_until(
+                |tick| smelted(tick, &mut furnaces) >= SCHEDULED_TARGET,
+                1000
+            ));
+            assert!(!tick.advance_until(|_| false, 1000));
+            (tick, ())
+        });
+        let scheduled = run_sandbox(|mut tick, starting_resources| {
+            let furnaces = RefCell::new(build_furnaces(&tick, starting_resources));
+            assert!(tick.advance_until_scheduled(
+                |tick| smelted(tick, &mut furnaces.borrow_mut()) >= SCHEDULED_TARGET,
+                |tick| furnaces.borrow_mut().ticks_until_next_output(tick),
+                1000,
+            ));
+
+            // Once nothing is left to smelt, the rest of the budget is skipped in one go.
+            let mut furnaces = furnaces.into_inner();
+            let start = tick.cur();
+            assert!(!tick.advance_until_scheduled(
+                |_| false,
+                |tick| furnaces.ticks_until_next_output(tick),
+                1000
+            ));
+            assert_eq!(tick.cur(), start + 1000);
+            assert_eq!(furnaces.buildings()[0].ticks_until_next_output(&tick), None);
+            (tick, ())
+        });
+        assert_eq!(scheduled, stepped);
+    }
+
+    #[test]
+    fn balanced_shares_evens_out() {
+        assert_eq!(balanced_shares(&[0, 0, 0], 10), [4, 3, 3]);
+        assert_eq!(balanced_shares(&[5, 0, 2], 4), [0, 3, 1]);
+        assert_eq!(balanced_shares(&[5, 0, 2], 10), [1, 6, 3]);
+        assert_eq!(balanced_shares(&[9, 1], 0), [0, 0]);
+        assert_eq!(balanced_shares(&[], 10), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn balanced_shares_loses_nothing() {
+        let amounts = [13, 2, 7, 7, 0, 40];
+        for total in 0..100 {
+            let shares = balanced_shares(&amounts, total);
+            assert_eq!(shares.iter().sum::<u32>(), total);
+            let levels: Vec<u32> = amounts.iter().zip(&shares).map(|(a, s)| a + s).collect();
+            let raised = levels
+                .iter()
+                .zip(&shares)
+                .filter(|(_, s)| **s > 0)
+                .map(|(l, _)| *l);
+            let (min, max) = (levels.iter().min().unwrap(), raised.max().unwrap_or(0));
+            assert!(max <= min + 1, "{total}: {levels:?}");
+        }
+    }
 }
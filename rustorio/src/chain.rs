@@ -0,0 +1,252 @@
+//! Running several groups of buildings as one production line.
+//!
+//! A [`Chain`] starts with a [`MachineGroup`] fed from a source, and each [`then`](Stage::then) adds a group fed from the outputs of the one before,
+//! like a row of inserters between them. [`step`](Stage::step) moves everything one stage along,
+//! and [`run_until`](Stage::run_until) keeps doing that until the last group has made enough.
+//! ```
+//! # use rustorio::{Tick, Resource, buildings::{Assembler, Furnace, MachineGroup}, chain::{Chain, Stage}, recipes::{CopperSmelting, CopperWireRecipe}, resources::CopperOre};
+//! fn wire(
+//!     tick: &mut Tick,
+//!     furnaces: MachineGroup<Furnace<CopperSmelting>>,
+//!     assemblers: MachineGroup<Assembler<CopperWireRecipe>>,
+//!     ore: Resource<CopperOre>,
+//! ) {
+//!     let mut chain = Chain::new::<0>(furnaces).then::<0, 0, _>(assemblers);
+//!     *chain.source() += ore;
+//!     chain.run_until::<0>(tick, 20, 1000);
+//!     let wire = chain.last().collect_output::<0>(tick);
+//! #   let _ = wire;
+//! }
+//! ```
+//!
+//! Resources only move when the chain is stepped, so a chain run alongside other buildings should be stepped as often as they are fed.
+
+use std::cell::RefCell;
+
+use rustorio_engine::{
+    ResourceType,
+    recipe::{Buffer, Recipe},
+    resource,
+};
+
+use crate::{
+    Resource, Tick,
+    buildings::{Building, MachineGroup},
+};
+
+type InputsOf<B> = <<B as Building>::Recipe as Recipe>::Inputs;
+type OutputsOf<B> = <<B as Building>::Recipe as Recipe>::Outputs;
+
+/// Part of a production line: a [`Chain`] and any [`Link`]s added to it.
+pub trait Stage {
+    /// The resource the first group of the line is fed.
+    type Source: ResourceType;
+    /// The kind of building in the last group of the line.
+    type Last: Building;
+
+    /// The resources waiting to go into the first group, e.g. `*chain.source() += territory.resources(&tick).empty()`.
+    fn source(&mut self) -> &mut Resource<Self::Source>;
+
+    /// The last group of the line, to take its outputs from.
+    fn last(&mut self) -> &mut MachineGroup<Self::Last>;
+
+    /// Moves everything one stage along: the source into the first group, and the outputs of each group into the next.
+    fn step(&mut self, tick: &Tick);
+
+    /// How many ticks until any building in the line next finishes a cycle, or `None` if none will without more inputs.
+    fn ticks_until_next_output(&mut self, tick: &Tick) -> Option<u64>;
+
+    /// Adds `group` to the end of the line, fed from output `SI` of the current last group into its input `DI`.
+    fn then<const SI: usize, const DI: usize, B: Building>(
+        self,
+        group: MachineGroup<B>,
+    ) -> Link<Self, B>
+    where
+        Self: Sized,
+        OutputsOf<Self::Last>: Buffer<SI>,
+        InputsOf<B>: Buffer<DI, Content = <OutputsOf<Self::Last> as Buffer<SI>>::Content>,
+    {
+        Link {
+            upstream: self,
+            group,
+            transfer: |tick, from, to| {
+                let mut moved = from.collect_output::<SI>(tick);
+                to.distribute::<DI>(tick, &mut moved);
+            },
+        }
+    }
+
+    /// Steps the line until the last group holds at least `amount` in output `I`, or `max_ticks` ticks have passed.
+    /// Returns `true` if the amount was reached, like [`Tick::advance_until`].
+    ///
+    /// Time skips straight to the next tick a building in the line finishes a cycle.
+    fn run_until<const I: usize>(&mut self, tick: &mut Tick, amount: u32, max_ticks: u64) -> bool
+    where
+        OutputsOf<Self::Last>: Buffer<I>,
+    {
+        let line = RefCell::new(self);
+        tick.advance_until_scheduled(
+            |tick| {
+                let mut line = line.borrow_mut();
+                line.step(tick);
+                let produced = line
+                    .last()
+                    .buildings()
+                    .iter_mut()
+                    .map(|building| building.output::<I>(tick).amount())
+                    .sum::<u32>();
+                produced >= amount
+            },
+            |tick| line.borrow_mut().ticks_until_next_output(tick),
+            max_ticks,
+        )
+    }
+}
+
+/// The start of a production line: a group of buildings fed from a source of `C`. See the [module documentation](self).
+#[derive(Debug)]
+pub struct Chain<B: Building, C: ResourceType> {
+    group: MachineGroup<B>,
+    source: Resource<C>,
+    fill: fn(&Tick, &mut MachineGroup<B>, &mut Resource<C>),
+}
+
+impl<B: Building, C: ResourceType> Chain<B, C> {
+    /// Starts a line with `group`, whose input `I` is fed from the chain's [source](Stage::source).
+    pub fn new<const I: usize>(group: MachineGroup<B>) -> Self
+    where
+        InputsOf<B>: Buffer<I, Content = C>,
+    {
+        Self {
+            group,
+            source: resource(0),
+            fill: |tick, group, source| group.distribute::<I>(tick, source),
+        }
+    }
+
+    /// The first group of the line.
+    pub const fn first(&mut self) -> &mut MachineGroup<B> {
+        &mut self.group
+    }
+}
+
+impl<B: Building, C: ResourceType> Stage for Chain<B, C> {
+    type Source = C;
+    type Last = B;
+
+    fn source(&mut self) -> &mut Resource<C> {
+        &mut self.source
+    }
+
+    fn last(&mut self) -> &mut MachineGroup<B> {
+        &mut self.group
+    }
+
+    fn step(&mut self, tick: &Tick) {
+        (self.fill)(tick, &mut self.group, &mut self.source);
+    }
+
+    fn ticks_until_next_output(&mut self, tick: &Tick) -> Option<u64> {
+        self.group.ticks_until_next_output(tick)
+    }
+}
+
+/// A group of buildings fed from the last group of `Up`, added with [`Stage::then`].
+#[derive(Debug)]
+pub struct Link<Up: Stage, B: Building> {
+    upstream: Up,
+    group: MachineGroup<B>,
+    transfer: fn(&Tick, &mut MachineGroup<Up::Last>, &mut MachineGroup<B>),
+}
+
+impl<Up: Stage, B: Building> Link<Up, B> {
+    /// The rest of the line, before this group.
+    pub const fn upstream(&mut self) -> &mut Up {
+        &mut self.upstream
+    }
+}
+
+impl<Up: Stage, B: Building> Stage for Link<Up, B> {
+    type Source = Up::Source;
+    type Last = B;
+
+    fn source(&mut self) -> &mut Resource<Up::Source> {
+        self.upstream.source()
+    }
+
+    fn last(&mut self) -> &mut MachineGroup<B> {
+        &mut self.group
+    }
+
+    fn step(&mut self, tick: &Tick) {
+        self.upstream.step(tick);
+        (self.transfer)(tick, self.upstream.last(), &mut self.group);
+    }
+
+    fn ticks_until_next_output(&mut self, tick: &Tick) -> Option<u64> {
+        let upstream = self.upstream.ticks_until_next_output(tick);
+        let own = self.group.ticks_until_next_output(tick);
+        upstream.into_iter().chain(own).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AdvanceCause,
+        buildings::{Assembler, Furnace},
+        gamemodes::{SandboxStartingResources, run_sandbox},
+        recipes::{CopperSmelting, CopperWireRecipe},
+    };
+
+    #[test]
+    fn chain_runs_ore_through_to_the_last_group() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut copper_ore,
+                mut iron,
+                mut copper_wire,
+                ..
+            } = starting_resources;
+            let mut furnaces = MachineGroup::new();
+            for _ in 0..3 {
+                furnaces.push(Furnace::build(
+                    &tick,
+                    CopperSmelting,
+                    iron.bundle().unwrap(),
+                ));
+            }
+            let assembler = Assembler::build(
+                &tick,
+                CopperWireRecipe,
+                copper_wire.bundle().unwrap(),
+                iron.bundle().unwrap(),
+            );
+            let mut chain =
+                Chain::new::<0>(furnaces).then::<0, 0, _>(MachineGroup::from(vec![assembler]));
+            *chain.source() += copper_ore.split_off(12).unwrap();
+
+            // Ore goes through the furnaces and into the assembler without being moved by hand.
+            assert!(chain.run_until::<0>(&mut tick, 20, 1000));
+            let wire = chain.last().collect_output::<0>(&tick);
+            assert!(wire.amount() >= 20);
+            assert!(chain.source().amount() < 12);
+
+            // It stops short once the ore runs out, logged as a single wait.
+            let start = tick.cur();
+            tick.start_event_log(4);
+            assert!(!chain.run_until::<0>(&mut tick, 100, 1000));
+            let log = tick.event_log().unwrap();
+            assert_eq!(
+                log.causes().collect::<Vec<_>>(),
+                [AdvanceCause::AdvanceUntil]
+            );
+            assert_eq!(tick.cur(), start + 1000);
+            let wire = wire + chain.last().collect_output::<0>(&tick);
+            assert_eq!(wire.amount(), 24);
+            assert_eq!(chain.source().amount(), 0);
+            (tick, ())
+        });
+    }
+}
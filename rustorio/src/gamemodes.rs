@@ -1,23 +1,76 @@
 //! A game mode defines the starting resources and victory conditions for a game.
 //!
 //! This module defines the available game modes.
+//!
+//! # Victory
+//!
+//! To win, `user_main` returns the victory resources of the game mode, which are then checked by [`GameMode::check_victory`].
+//! The check only looks at the amounts. It doesn't know how the resources were made, and relies on `rustorio`'s public API
+//! having no way to create a non-empty [`Resource`] or [`Bundle`] without producing it.
+//! That holds for a save that only depends on `rustorio`. It doesn't hold for code that calls `rustorio-engine` directly,
+//! whose constructors exist for mods, or that uses `unsafe` to forge values.
+//! Through the public API, none of the ways to make victory resources out of thin air compile:
+//! ```compile_fail,E0624
+//! # use rustorio::{Bundle, resources::Point};
+//! fn fabricate() -> Bundle<Point, 200> {
+//!     Bundle::new()
+//! }
+//! ```
+//! ```compile_fail,E0451
+//! # use rustorio::{Bundle, resources::Point};
+//! fn fabricate() -> Bundle<Point, 200> {
+//!     Bundle { dummy: std::marker::PhantomData }
+//! }
+//! ```
+//! ```compile_fail,E0624
+//! # use rustorio::{Bundle, Resource, resources::Point};
+//! fn fabricate() -> Bundle<Point, 200> {
+//!     Resource::new(200).bundle().unwrap()
+//! }
+//! ```
+//! ```compile_fail,E0451
+//! # use rustorio::{Bundle, Resource, resources::Point};
+//! fn fabricate() -> Bundle<Point, 200> {
+//!     Resource { amount: 200, phantom: std::marker::PhantomData }.bundle().unwrap()
+//! }
+//! ```
+//! An empty [`Resource`] is the only one that can be created freely, and it has nothing to take the victory resources from:
+//! ```should_panic
+//! # use rustorio::{Bundle, Resource, resources::Point};
+//! let points: Bundle<Point, 200> = Resource::new_empty().bundle().unwrap();
+//! ```
+
+use std::marker::PhantomData;
 
 use rustorio_engine::{
-    bundle,
-    gamemodes::{GameMode, StartingResources},
+    gamemodes::{
+        AtLeast, Difficulty, GameMode, StartingResources, VictoryCondition, starting_bundle,
+        starting_resource,
+    },
     mod_reexports::Tick,
+    rng::SeededRng,
 };
 
 use crate::{
-    Bundle,
+    Bundle, Resource,
+    buildings::{ElectricFurnaceUnlock, OffshorePumpUnlock, SteelFurnaceUnlock},
     guide::Guide,
-    research::SteelTechnology,
-    resources::{Copper, CopperOre, Iron, IronOre, Point},
+    recipes::{BlueScienceRecipe, PointRecipe, SteamIronSmelting, SteelSmelting},
+    research::{BlueScience, GreenScience, RedScience, SteelTechnology},
+    resources::{Copper, CopperOre, CopperWire, ElectronicCircuit, Iron, IronOre, Point, Steel},
     territory::Territory,
 };
 
-/// Starting resources for the tutorial game mode.
-pub struct TutorialStartingResources {
+#[cfg(feature = "challenge")]
+mod challenge;
+#[cfg(feature = "challenge")]
+pub use challenge::{
+    CHALLENGE_CONFIG_FILE, Challenge, ChallengeConfig, ChallengeStartingResources,
+    ChallengeTechnology,
+};
+
+/// Starting resources for the tutorial game mode, whose guide asks for `COPPER` copper.
+pub struct TutorialStartingResources<const COPPER: u32 = TUTORIAL_COPPER> {
     /// Initial iron bundle.
     pub iron: Bundle<Iron, 10>,
     /// Initial territory to mine iron ore from.
@@ -28,23 +81,28 @@ pub struct TutorialStartingResources {
     pub guide: Guide,
 }
 
-impl StartingResources for TutorialStartingResources {
+impl<const COPPER: u32> StartingResources for TutorialStartingResources<COPPER> {
     fn init(tick: &Tick) -> Self {
         Self {
-            iron: bundle(),
+            iron: starting_bundle(tick),
             iron_territory: Territory::new(tick, 5),
             copper_territory: Territory::new(tick, 5),
-            guide: Guide,
+            guide: Guide::new(COPPER),
         }
     }
 }
 
+/// How much copper the tutorial asks for by default.
+pub const TUTORIAL_COPPER: u32 = 4;
+
 /// The tutorial game mode. Very short distance from start to victory, meant to teach the very most basic elements of play.
-pub struct Tutorial;
+///
+/// To win, produce at least `COPPER` copper, [`TUTORIAL_COPPER`] unless set otherwise, e.g. `type GameMode = Tutorial<8>;`.
+pub struct Tutorial<const COPPER: u32 = TUTORIAL_COPPER>;
 
-impl GameMode for Tutorial {
-    type StartingResources = TutorialStartingResources;
-    type VictoryResources = Bundle<Copper, 4>;
+impl<const COPPER: u32> GameMode for Tutorial<COPPER> {
+    type StartingResources = TutorialStartingResources<COPPER>;
+    type VictoryResources = AtLeast<Copper, COPPER>;
 }
 
 /// Starting resources for the standard game mode. Includes 10 iron and the ability to research points.
@@ -57,22 +115,406 @@ pub struct StandardStartingResources {
     pub copper_territory: Territory<CopperOre>,
     /// The first technology the player can research.
     pub steel_technology: SteelTechnology,
+    /// The in-game guide that provides hints to the player.
+    pub guide: Guide,
 }
 impl StartingResources for StandardStartingResources {
     fn init(tick: &Tick) -> Self {
+        let (iron_territory, copper_territory) = match tick.seed() {
+            Some(seed) => {
+                let mut rng = SeededRng::new(seed);
+                (
+                    Territory::generate(tick, 20, &mut rng),
+                    Territory::generate(tick, 20, &mut rng),
+                )
+            }
+            None => (Territory::new(tick, 20), Territory::new(tick, 20)),
+        };
         Self {
-            iron: bundle(),
-            iron_territory: Territory::new(tick, 20),
-            copper_territory: Territory::new(tick, 20),
+            iron: starting_bundle(tick),
+            iron_territory,
+            copper_territory,
             steel_technology: SteelTechnology,
+            guide: Guide::new(TUTORIAL_COPPER),
         }
     }
 }
 
+/// A difficulty level for [`Standard`], chosen through its type parameter.
+pub trait DifficultyLevel {
+    /// The difficulty of the game.
+    const DIFFICULTY: Difficulty;
+}
+
+/// Faster mining and smelting, for a more relaxed game.
+pub struct Easy;
+
+impl DifficultyLevel for Easy {
+    const DIFFICULTY: Difficulty = Difficulty::Easy;
+}
+
+/// The intended balance.
+pub struct Normal;
+
+impl DifficultyLevel for Normal {
+    const DIFFICULTY: Difficulty = Difficulty::Normal;
+}
+
+/// Slower mining, for a harder challenge.
+pub struct Hard;
+
+impl DifficultyLevel for Hard {
+    const DIFFICULTY: Difficulty = Difficulty::Hard;
+}
+
 /// The standard game mode. Forces the player through the main gameplay mechanics.
-pub struct Standard;
+///
+/// Defaults to the [`Normal`] difficulty. Choose another one in the save's game mode type, e.g. `type GameMode = Standard<Hard>;`.
+/// See [`MINING_TICK_LENGTH`](crate::territory::MINING_TICK_LENGTH) and [`EASY_FURNACE_SPEED`](crate::buildings::EASY_FURNACE_SPEED) for what changes.
+pub struct Standard<D: DifficultyLevel = Normal>(PhantomData<D>);
 
-impl GameMode for Standard {
+impl<D: DifficultyLevel> GameMode for Standard<D> {
     type StartingResources = StandardStartingResources;
     type VictoryResources = Bundle<Point, 200>;
+
+    const DIFFICULTY: Difficulty = D::DIFFICULTY;
+}
+
+/// How many ticks a machine can be starved of inputs in [`Hardcore`] before it breaks.
+pub const HARDCORE_STARVATION_LIMIT: u64 = 100;
+
+/// The standard game mode with no room for neglect. Has the same starting resources and goal as [`Standard`],
+/// but machines starved of inputs for more than [`HARDCORE_STARVATION_LIMIT`] ticks break and have to be rebuilt.
+pub struct Hardcore;
+
+impl GameMode for Hardcore {
+    type StartingResources = StandardStartingResources;
+    type VictoryResources = <Standard as GameMode>::VictoryResources;
+
+    const STARVATION_LIMIT: Option<u64> = Some(HARDCORE_STARVATION_LIMIT);
+}
+
+/// How many [miners](crate::territory::Miner) can work at once across all territories in [`Logistics`].
+pub const LOGISTICS_MINING_BUDGET: u32 = 20;
+
+/// The standard game mode where throughput is limited. Has the same starting resources and goal as [`Standard`],
+/// but only [`LOGISTICS_MINING_BUDGET`] miners work at once across all territories.
+/// With more miners than that, every miner slows down so the total stays the same,
+/// so it pays to balance miners between ores rather than spam them.
+pub struct Logistics;
+
+impl GameMode for Logistics {
+    type StartingResources = StandardStartingResources;
+    type VictoryResources = <Standard as GameMode>::VictoryResources;
+
+    const MINING_BUDGET: Option<u32> = Some(LOGISTICS_MINING_BUDGET);
+}
+
+/// The seed of [`Seeded`] if none is given.
+pub const DEFAULT_SEED: u64 = 0;
+
+/// The standard game mode in a generated world. Has the same starting resources and goal as [`Standard`],
+/// but the territories are generated from `SEED`: they have room for more or fewer miners,
+/// and their deposits run out after [`GENERATED_DEPOSIT_MIN`](crate::territory::GENERATED_DEPOSIT_MIN)
+/// to [`GENERATED_DEPOSIT_MAX`](crate::territory::GENERATED_DEPOSIT_MAX) ore.
+///
+/// The seed is set in the save's game mode type, e.g. `type GameMode = Seeded<1234>;`.
+/// Without it, the seed is [`DEFAULT_SEED`]. Share the seed so others can play in the same world.
+pub struct Seeded<const SEED: u64 = DEFAULT_SEED>;
+
+impl<const SEED: u64> GameMode for Seeded<SEED> {
+    type StartingResources = StandardStartingResources;
+    type VictoryResources = <Standard as GameMode>::VictoryResources;
+
+    const SEED: Option<u64> = Some(SEED);
+}
+
+/// The tick budget of [`Speedrun`] if none is given.
+pub const DEFAULT_SPEEDRUN_TICK_BUDGET: u64 = 1_000_000;
+
+/// The standard game mode against the clock. Has the same starting resources and goal as [`Standard`],
+/// but winning after more than `TICK_BUDGET` ticks counts as a loss.
+///
+/// The budget is set in the save's game mode type, e.g. `type GameMode = Speedrun<500_000>;`.
+/// Without it, the budget is [`DEFAULT_SPEEDRUN_TICK_BUDGET`].
+pub struct Speedrun<const TICK_BUDGET: u64 = DEFAULT_SPEEDRUN_TICK_BUDGET>;
+
+impl<const TICK_BUDGET: u64> GameMode for Speedrun<TICK_BUDGET> {
+    type StartingResources = StandardStartingResources;
+    type VictoryResources = <Standard as GameMode>::VictoryResources;
+
+    fn check_victory(
+        tick: &Tick,
+        victory_resources: &Self::VictoryResources,
+    ) -> Result<(), String> {
+        victory_resources.check(tick)?;
+        check_tick_budget(tick.cur() - Self::START_TICK, TICK_BUDGET)
+    }
+}
+
+/// Fails if a game won after `ticks` ticks went over its tick `budget`.
+fn check_tick_budget(ticks: u64, budget: u64) -> Result<(), String> {
+    if ticks > budget {
+        Err(format!(
+            "The tick budget of {budget} ticks was exceeded by {} ticks.",
+            ticks - budget
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// How much of each resource the sandbox game mode starts with.
+pub const SANDBOX_RESOURCE_AMOUNT: u32 = 100_000;
+
+/// Starting resources for the sandbox game mode.
+/// Includes [`SANDBOX_RESOURCE_AMOUNT`] of every resource and everything that would otherwise have to be researched.
+pub struct SandboxStartingResources {
+    /// Iron ore to smelt.
+    pub iron_ore: Resource<IronOre>,
+    /// Copper ore to smelt.
+    pub copper_ore: Resource<CopperOre>,
+    /// Iron for building and crafting.
+    pub iron: Resource<Iron>,
+    /// Copper for building and crafting.
+    pub copper: Resource<Copper>,
+    /// Steel for building and crafting.
+    pub steel: Resource<Steel>,
+    /// Copper wire for building and crafting.
+    pub copper_wire: Resource<CopperWire>,
+    /// Electronic circuits for building and crafting.
+    pub electronic_circuit: Resource<ElectronicCircuit>,
+    /// Red science packs for research.
+    pub red_science: Resource<RedScience>,
+    /// Green science packs for research.
+    pub green_science: Resource<GreenScience>,
+    /// Blue science packs for research.
+    pub blue_science: Resource<BlueScience>,
+    /// A territory with room for many miners.
+    pub iron_territory: Territory<IronOre>,
+    /// A territory with room for many miners.
+    pub copper_territory: Territory<CopperOre>,
+    /// The steel smelting recipe, normally unlocked by [`SteelTechnology`].
+    pub steel_smelting: SteelSmelting,
+    /// The point recipe, normally unlocked by [`PointsTechnology`](crate::research::PointsTechnology).
+    pub point_recipe: PointRecipe,
+    /// The blue science recipe, normally unlocked by [`BlueScienceTechnology`](crate::research::BlueScienceTechnology).
+    pub blue_science_recipe: BlueScienceRecipe,
+    /// Allows building steel furnaces, normally unlocked by [`SteelFurnaceTechnology`](crate::research::SteelFurnaceTechnology).
+    pub steel_furnace_unlock: SteelFurnaceUnlock,
+    /// Allows building electric furnaces, normally unlocked by [`ElectricFurnaceTechnology`](crate::research::ElectricFurnaceTechnology).
+    pub electric_furnace_unlock: ElectricFurnaceUnlock,
+    /// The steam iron smelting recipe, normally unlocked by [`SteamSmeltingTechnology`](crate::research::SteamSmeltingTechnology).
+    pub steam_iron_smelting: SteamIronSmelting,
+    /// Allows building offshore pumps, normally unlocked by [`SteamSmeltingTechnology`](crate::research::SteamSmeltingTechnology).
+    pub offshore_pump_unlock: OffshorePumpUnlock,
+    /// The first technology, for experimenting with research.
+    pub steel_technology: SteelTechnology,
+}
+
+impl StartingResources for SandboxStartingResources {
+    fn init(tick: &Tick) -> Self {
+        Self {
+            iron_ore: starting_resource(tick, SANDBOX_RESOURCE_AMOUNT),
+            copper_ore: starting_resource(tick, SANDBOX_RESOURCE_AMOUNT),
+            iron: starting_resource(tick, SANDBOX_RESOURCE_AMOUNT),
+            copper: starting_resource(tick, SANDBOX_RESOURCE_AMOUNT),
+            steel: starting_resource(tick, SANDBOX_RESOURCE_AMOUNT),
+            copper_wire: starting_resource(tick, SANDBOX_RESOURCE_AMOUNT),
+            electronic_circuit: starting_resource(tick, SANDBOX_RESOURCE_AMOUNT),
+            red_science: starting_resource(tick, SANDBOX_RESOURCE_AMOUNT),
+            green_science: starting_resource(tick, SANDBOX_RESOURCE_AMOUNT),
+            blue_science: starting_resource(tick, SANDBOX_RESOURCE_AMOUNT),
+            iron_territory: Territory::new(tick, 1000),
+            copper_territory: Territory::new(tick, 1000),
+            steel_smelting: SteelSmelting,
+            point_recipe: PointRecipe {},
+            blue_science_recipe: BlueScienceRecipe {},
+            steel_furnace_unlock: SteelFurnaceUnlock::new(),
+            electric_furnace_unlock: ElectricFurnaceUnlock::new(),
+            steam_iron_smelting: SteamIronSmelting,
+            offshore_pump_unlock: OffshorePumpUnlock::new(),
+            steel_technology: SteelTechnology,
+        }
+    }
+}
+
+/// The sandbox game mode. Starts with plenty of everything and has no goal, for experimenting with factory designs.
+///
+/// Since there is nothing to win, the game ends as soon as you return the `tick` along with `()`.
+pub struct Sandbox;
+
+impl GameMode for Sandbox {
+    type StartingResources = SandboxStartingResources;
+    type VictoryResources = ();
+}
+
+/// Runs `main` as a [`Sandbox`] game, for unit tests that need a [`Tick`] and resources to build with.
+/// Returns the tick the game ended at.
+#[cfg(test)]
+pub(crate) fn run_sandbox(main: fn(Tick, SandboxStartingResources) -> (Tick, ())) -> u64 {
+    let result = rustorio_engine::run_gamemode::<Sandbox>(main);
+    result.outcome.unwrap();
+    result.ticks
+}
+
+#[cfg(test)]
+mod tests {
+    use rustorio_engine::{
+        ResourceType,
+        gamemodes::{UnproducibleVictoryError, validate_victory},
+        recipe::{RecipeEx, RecipeFlow},
+        resource_type,
+    };
+
+    use super::*;
+    use crate::{
+        buildings::{Building, Furnace},
+        recipes::{IronSmelting, RecipeVisitor, visit_recipes},
+        territory::{EASY_MINING_TICK_LENGTH, HARD_MINING_TICK_LENGTH, MINING_TICK_LENGTH},
+    };
+
+    /// [`Sandbox`] with the rules of `G`, to try them out without having to win.
+    struct SandboxWith<G>(PhantomData<G>);
+
+    impl<G: GameMode> GameMode for SandboxWith<G> {
+        type StartingResources = SandboxStartingResources;
+        type VictoryResources = ();
+
+        const STARVATION_LIMIT: Option<u64> = G::STARVATION_LIMIT;
+        const DIFFICULTY: Difficulty = G::DIFFICULTY;
+    }
+
+    fn all_recipes() -> Vec<RecipeFlow> {
+        struct Flows(Vec<RecipeFlow>);
+        impl RecipeVisitor for Flows {
+            fn visit<R: RecipeEx>(&mut self) {
+                self.0.push(RecipeFlow::of::<R>());
+            }
+        }
+        let mut flows = Flows(Vec::new());
+        visit_recipes(&mut flows);
+        flows.0
+    }
+
+    #[test]
+    fn game_mode_victories_are_producible() {
+        let ores = [IronOre::NAME, CopperOre::NAME];
+        let recipes = all_recipes();
+        validate_victory::<Tutorial>(&ores, &recipes).unwrap();
+        validate_victory::<Standard>(&ores, &recipes).unwrap();
+        validate_victory::<Hardcore>(&ores, &recipes).unwrap();
+        validate_victory::<Logistics>(&ores, &recipes).unwrap();
+        validate_victory::<Seeded>(&ores, &recipes).unwrap();
+        validate_victory::<Speedrun>(&ores, &recipes).unwrap();
+        #[cfg(feature = "challenge")]
+        validate_victory::<Challenge>(&ores, &recipes).unwrap();
+        validate_victory::<Sandbox>(&[], &[]).unwrap();
+    }
+
+    #[test]
+    fn victory_amounts_match_victory_resources() {
+        assert_eq!(<Standard>::VICTORY_AMOUNT, 200);
+        assert_eq!(Hardcore::VICTORY_AMOUNT, <Standard>::VICTORY_AMOUNT);
+        assert_eq!(Tutorial::<7>::VICTORY_AMOUNT, 7);
+        assert_eq!(Sandbox::VICTORY_AMOUNT, 0);
+    }
+
+    #[test]
+    fn difficulty_scales_mining_and_smelting() {
+        fn mine_and_smelt<G: GameMode>() -> u64 {
+            let result =
+                rustorio_engine::run_gamemode::<SandboxWith<G>>(|mut tick, starting_resources| {
+                    let SandboxStartingResources {
+                        mut iron,
+                        mut iron_territory,
+                        ..
+                    } = starting_resources;
+                    let expected_mining_tick_length = match tick.difficulty() {
+                        Difficulty::Easy => EASY_MINING_TICK_LENGTH,
+                        Difficulty::Normal => MINING_TICK_LENGTH,
+                        Difficulty::Hard => HARD_MINING_TICK_LENGTH,
+                    };
+                    assert_eq!(
+                        iron_territory.mining_tick_length(),
+                        expected_mining_tick_length
+                    );
+
+                    let ore = iron_territory.hand_mine::<5>(&mut tick);
+                    let mut furnace = Furnace::build(&tick, IronSmelting, iron.bundle().unwrap());
+                    furnace.inputs(&tick).0 += ore;
+                    assert!(
+                        tick.advance_until(|tick| furnace.output::<0>(tick).amount() >= 5, 100)
+                    );
+                    (tick, ())
+                });
+            result.ticks
+        }
+
+        let easy = mine_and_smelt::<Standard<Easy>>();
+        let normal = mine_and_smelt::<Standard>();
+        let hard = mine_and_smelt::<Standard<Hard>>();
+        assert!(easy < normal);
+        assert!(normal < hard);
+    }
+
+    #[test]
+    fn hardcore_breaks_neglected_furnaces() {
+        let result = rustorio_engine::run_gamemode::<SandboxWith<Hardcore>>(
+            |mut tick, starting_resources| {
+                let SandboxStartingResources {
+                    mut iron,
+                    mut iron_ore,
+                    ..
+                } = starting_resources;
+                let mut furnace = Furnace::build(&tick, IronSmelting, iron.bundle().unwrap());
+                furnace.inputs(&tick).0 += iron_ore.split_off(3).unwrap();
+                assert!(tick.advance_until(|tick| furnace.output::<0>(tick).amount() >= 3, 100));
+
+                // Refilling the furnace in time keeps it running.
+                tick.advance_by(HARDCORE_STARVATION_LIMIT / 2);
+                assert!(!furnace.is_broken(&tick));
+                furnace.inputs(&tick).0 += iron_ore.split_off(3).unwrap();
+                assert!(tick.advance_until(|tick| furnace.output::<0>(tick).amount() >= 6, 100));
+                assert!(!furnace.is_broken(&tick));
+
+                // Neglecting it breaks it for good.
+                tick.advance_by(HARDCORE_STARVATION_LIMIT + 1);
+                assert!(furnace.is_broken(&tick));
+                furnace.inputs(&tick).0 += iron_ore.split_off(3).unwrap();
+                tick.advance_by(100);
+                assert_eq!(furnace.output::<0>(&tick).amount(), 6);
+                (tick, ())
+            },
+        );
+        result.outcome.unwrap();
+    }
+
+    #[test]
+    fn tick_budget_allows_exactly_the_budget() {
+        assert_eq!(check_tick_budget(100, 100), Ok(()));
+        assert_eq!(
+            check_tick_budget(100, 99),
+            Err("The tick budget of 99 ticks was exceeded by 1 ticks.".to_string())
+        );
+    }
+
+    #[test]
+    fn unproducible_victory_is_caught() {
+        resource_type!(Gold);
+
+        struct GoldRush;
+
+        impl GameMode for GoldRush {
+            type StartingResources = StandardStartingResources;
+            type VictoryResources = (Bundle<Point, 200>, Bundle<Gold, 10>);
+        }
+
+        assert_eq!(
+            validate_victory::<GoldRush>(&[IronOre::NAME, CopperOre::NAME], &all_recipes()),
+            Err(UnproducibleVictoryError {
+                missing: vec![Gold::NAME]
+            })
+        );
+    }
 }
@@ -0,0 +1,231 @@
+//! The [`Challenge`] game mode, whose parameters are read from a file. Needs the `challenge` feature.
+
+use std::path::Path;
+
+use rustorio_engine::{
+    gamemodes::{GameMode, StartingResources, VictoryCondition, starting_resource},
+    mod_reexports::Tick,
+};
+use serde::Deserialize;
+
+use super::{Standard, TUTORIAL_COPPER, check_tick_budget};
+use crate::{
+    Resource,
+    guide::Guide,
+    research::SteelTechnology,
+    resources::{Copper, CopperOre, Iron, IronOre},
+    territory::Territory,
+};
+
+/// The file [`Challenge`] parameters are read from, found in the current directory or one of its ancestors.
+pub const CHALLENGE_CONFIG_FILE: &str = "rustorio.toml";
+
+/// A technology that can be available from the start of a [`Challenge`].
+///
+/// Only technologies without [prerequisites](rustorio_engine::research::Technology::Prerequisites) can be,
+/// so that a challenge can't skip ahead in the tech tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeTechnology {
+    /// [`SteelTechnology`].
+    Steel,
+}
+
+/// The parameters of a [`Challenge`], read from the `[challenge]` table of [`CHALLENGE_CONFIG_FILE`].
+/// Missing keys keep the value of the standard game mode.
+///
+/// For example:
+/// ```toml
+#[doc = include_str!("../../examples/challenge.toml")]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChallengeConfig {
+    /// Iron to start with.
+    pub iron: u32,
+    /// Copper to start with.
+    pub copper: u32,
+    /// How many miners each of the starting territories can hold.
+    pub max_miners: u32,
+    /// Winning after more ticks than this counts as a loss. `None` means no limit.
+    pub tick_budget: Option<u64>,
+    /// Technologies available from the start.
+    pub technologies: Vec<ChallengeTechnology>,
+}
+
+impl Default for ChallengeConfig {
+    fn default() -> Self {
+        Self {
+            iron: 10,
+            copper: 0,
+            max_miners: 20,
+            tick_budget: None,
+            technologies: vec![ChallengeTechnology::Steel],
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    challenge: ChallengeConfig,
+}
+
+impl ChallengeConfig {
+    /// Parses the `[challenge]` table of the contents of a [`CHALLENGE_CONFIG_FILE`].
+    pub fn from_toml(contents: &str) -> Result<Self, toml::de::Error> {
+        Ok(toml::from_str::<ConfigFile>(contents)?.challenge)
+    }
+
+    /// Reads the parameters from the nearest [`CHALLENGE_CONFIG_FILE`], or uses the defaults if there is none.
+    ///
+    /// # Panics
+    /// Panics if the file can't be read or parsed, since the challenge can't start without it.
+    pub fn load() -> Self {
+        let Some(path) = Path::new(".").canonicalize().ok().and_then(|dir| {
+            dir.ancestors()
+                .map(|dir| dir.join(CHALLENGE_CONFIG_FILE))
+                .find(|path| path.exists())
+        }) else {
+            return Self::default();
+        };
+        let contents = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("Failed to read {}: {err}", path.display()));
+        Self::from_toml(&contents)
+            .unwrap_or_else(|err| panic!("Invalid challenge in {}: {err}", path.display()))
+    }
+
+    /// The parameters of the game being played, as loaded by [`Challenge::load_config`].
+    fn of(tick: &Tick) -> &Self {
+        tick.game_config()
+            .expect("The challenge config is loaded before the game starts")
+    }
+
+    fn has(&self, technology: ChallengeTechnology) -> bool {
+        self.technologies.contains(&technology)
+    }
+}
+
+/// Starting resources for the challenge game mode, as configured by [`ChallengeConfig`].
+pub struct ChallengeStartingResources {
+    /// Initial iron.
+    pub iron: Resource<Iron>,
+    /// Initial copper.
+    pub copper: Resource<Copper>,
+    /// Initial territory to mine iron ore from.
+    pub iron_territory: Territory<IronOre>,
+    /// Initial territory to mine copper ore from.
+    pub copper_territory: Territory<CopperOre>,
+    /// Available if [`ChallengeTechnology::Steel`] is enabled.
+    pub steel_technology: Option<SteelTechnology>,
+    /// The in-game guide that provides hints to the player.
+    pub guide: Guide,
+}
+
+impl StartingResources for ChallengeStartingResources {
+    fn init(tick: &Tick) -> Self {
+        let config = ChallengeConfig::of(tick);
+        Self {
+            iron: starting_resource(tick, config.iron),
+            copper: starting_resource(tick, config.copper),
+            iron_territory: Territory::new(tick, config.max_miners),
+            copper_territory: Territory::new(tick, config.max_miners),
+            steel_technology: config
+                .has(ChallengeTechnology::Steel)
+                .then_some(SteelTechnology),
+            guide: Guide::new(TUTORIAL_COPPER),
+        }
+    }
+}
+
+/// A game mode whose parameters are set in [`CHALLENGE_CONFIG_FILE`], so players can set challenges for each other without changing the crate.
+/// See [`ChallengeConfig`] for what can be set. The goal is the same as in [`Standard`].
+///
+/// Since the parameters can be anything, results in this mode are only comparable between games with the same file,
+/// which the [config hash](rustorio_engine::GameResult::config_hash) of each result tells apart.
+pub struct Challenge;
+
+impl GameMode for Challenge {
+    type StartingResources = ChallengeStartingResources;
+    type VictoryResources = <Standard as GameMode>::VictoryResources;
+    type Config = ChallengeConfig;
+
+    fn load_config() -> ChallengeConfig {
+        ChallengeConfig::load()
+    }
+
+    fn check_victory(
+        tick: &Tick,
+        victory_resources: &Self::VictoryResources,
+    ) -> Result<(), String> {
+        victory_resources.check(tick)?;
+        match ChallengeConfig::of(tick).tick_budget {
+            Some(tick_budget) => check_tick_budget(tick.cur() - Self::START_TICK, tick_budget),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustorio_engine::{
+        gamemodes::config_hash,
+        research::{PrerequisiteNames, Technology},
+    };
+
+    use super::*;
+
+    #[test]
+    fn example_challenge_config_loads() {
+        let config =
+            ChallengeConfig::from_toml(include_str!("../../examples/challenge.toml")).unwrap();
+        assert_eq!(
+            config,
+            ChallengeConfig {
+                iron: 40,
+                copper: 10,
+                max_miners: 10,
+                tick_budget: Some(500_000),
+                technologies: vec![ChallengeTechnology::Steel],
+            }
+        );
+    }
+
+    #[test]
+    fn empty_config_is_default() {
+        assert_eq!(
+            ChallengeConfig::from_toml("").unwrap(),
+            ChallengeConfig::default()
+        );
+        assert!(ChallengeConfig::from_toml("[challenge]\nirn = 5\n").is_err());
+    }
+
+    #[test]
+    fn config_hash_tells_configs_apart() {
+        let default = ChallengeConfig::default();
+        let harder = ChallengeConfig {
+            tick_budget: Some(100_000),
+            ..ChallengeConfig::default()
+        };
+        assert_eq!(config_hash(&default), config_hash(&default.clone()));
+        assert_ne!(config_hash(&default), config_hash(&harder));
+    }
+
+    #[test]
+    fn technologies_beyond_the_root_are_rejected() {
+        for technology in ["points", "steel_furnace", "blue_science"] {
+            let config = format!("[challenge]\ntechnologies = [\"{technology}\"]\n");
+            assert!(ChallengeConfig::from_toml(&config).is_err());
+        }
+    }
+
+    #[test]
+    fn granted_technologies_have_no_prerequisites() {
+        let prerequisites = |technology| match technology {
+            ChallengeTechnology::Steel => {
+                <<SteelTechnology as Technology>::Prerequisites as PrerequisiteNames>::names()
+            }
+        };
+        assert!(prerequisites(ChallengeTechnology::Steel).is_empty());
+    }
+}
@@ -1,71 +1,124 @@
-//! A guide that provides hints to the player during the tutorial.
+//! A guide that provides hints to the player during the tutorial and the standard game.
 
 use std::process;
 
-use rustorio_engine::ResourceType;
+use rustorio_engine::{
+    ResourceType,
+    gamemodes::GameMode,
+    recipe::RecipeEx,
+    research::{TechRecipe, Technology},
+};
 
 use crate::{
     Bundle, Resource, Tick,
-    buildings::Furnace,
-    recipes::FurnaceRecipe,
+    buildings::{Assembler, Furnace, Lab},
+    gamemodes::Standard,
+    recipes::{AssemblerRecipe, FurnaceRecipe},
+    research::{PointsTechnology, RedScience, SteelTechnology},
     resources::{Copper, CopperOre, Iron, IronOre},
     territory::{Miner, Territory},
 };
 
 /// A topic that the guide can provide hints about.
 trait GuideTopic {
-    fn hint() -> &'static str;
+    fn hint(guide: &Guide) -> String;
 }
 
 impl GuideTopic for Tick {
-    fn hint() -> &'static str {
+    fn hint(_guide: &Guide) -> String {
         "The `Tick` object you are given handles the passage of time in the game. You can use methods like `Tick::advance` or `Tick::advance_until` to make time pass, which is necessary for buildings to process resources. Some functions that represent doing some thing \"by hand\" like `Territory::hand_mine` take a mutable reference to the Tick to let them advance time internally.
-For more information, see https://docs.rs/rustorio/latest/rustorio/struct.Tick.html"
+For more information, see https://docs.rs/rustorio/latest/rustorio/struct.Tick.html".into()
     }
 }
 
 impl<R: ResourceType> GuideTopic for Territory<R> {
-    fn hint() -> &'static str {
-        "Territories are where you get your basic resources. To begin with you can mine by hand using the `hand_mine` function, but you can add `Miner`s to the territory to automate mining."
+    fn hint(_guide: &Guide) -> String {
+        "Territories are where you get your basic resources. To begin with you can mine by hand using the `hand_mine` function, but you can add `Miner`s to the territory to automate mining.".into()
     }
 }
 
 impl GuideTopic for Miner {
-    fn hint() -> &'static str {
-        "Miners can be added to a territory to automate mining. Each miner will mine resources every few ticks, so you don't have to do it by hand. Use `Territory::resources` to get the resources mined by the miners."
+    fn hint(_guide: &Guide) -> String {
+        "Miners can be added to a territory to automate mining. Each miner will mine resources every few ticks, so you don't have to do it by hand. Use `Territory::resources` to get the resources mined by the miners.".into()
     }
 }
 
 impl GuideTopic for Resource<Iron> {
-    fn hint() -> &'static str {
+    fn hint(_guide: &Guide) -> String {
         "In this tutorial you start with 10 iron. You can use iron to build buildings like Furnaces and Assemblers.
-Try building a Furnace using `Furnace::build`. If you're in doubt about what recipe to pick, try `CopperSmelting` to smelt copper ore into copper ingots."
+Try building a Furnace using `Furnace::build`. If you're in doubt about what recipe to pick, try `CopperSmelting` to smelt copper ore into copper ingots.".into()
     }
 }
 
 impl<R: FurnaceRecipe> GuideTopic for Furnace<R> {
-    fn hint() -> &'static str {
-        "Congratulations on building your first Furnace! If you haven't already, mine some copper ore using `Territory::hand_mine`. You acces the input buffers of the furnace using `Furnace::inputs` which allows you to add ore. If you then use `Tick::advance` to make ticks pass, the ore will turn into ingots and put in the output buffer, which can be accessed using `Furnace::outputs`."
+    fn hint(_guide: &Guide) -> String {
+        "Congratulations on building your first Furnace! If you haven't already, mine some copper ore using `Territory::hand_mine`. You access the input buffers of the furnace using `Furnace::inputs`, which allows you to add ore. If you then use `Tick::advance` to make ticks pass, the ore will turn into ingots and be put in the output buffer, which can be accessed using `Furnace::outputs`.".into()
     }
 }
 
 impl GuideTopic for Resource<CopperOre> {
-    fn hint() -> &'static str {
+    fn hint(_guide: &Guide) -> String {
         "Great job on mining some copper ore! Add the ore to a Furnace by adding them to `Furnace::inputs`, then advance time using `Tick::advance` to smelt the ore into copper ingots. Finally, extract the ingots from the furnace's output buffers using `Furnace::outputs`.
 
-If you don't have a Furnace yet, build one using `Furnace::build`, and use the `CopperSmelting` recipe to smelt copper ore into copper ingots."
+If you don't have a Furnace yet, build one using `Furnace::build`, and use the `CopperSmelting` recipe to smelt copper ore into copper ingots.".into()
     }
 }
 
 impl GuideTopic for Resource<IronOre> {
-    fn hint() -> &'static str {
-        "Good job on figuring out how to mine iron ore! You can smelt the iron ore into iron ingots using a Furnace, but you won't need to for this tutorial, instead try mining some copper ore using `Territory::hand_mine`."
+    fn hint(_guide: &Guide) -> String {
+        "Good job on figuring out how to mine iron ore! You can smelt the iron ore into iron ingots using a Furnace, but you won't need to for this tutorial, instead try mining some copper ore using `Territory::hand_mine`.".into()
     }
 }
 
 impl GuideTopic for Resource<Copper> {
-    fn hint() -> &'static str {
-        "Awesome! You've made some copper ingots. To win the tutorial, you need to make 4 copper ingots. If you don't have one yet, try mining some copper ore using `Territory::hand_mine`, then smelt it into copper ingots using a Furnace."
+    fn hint(guide: &Guide) -> String {
+        format!(
+            "Awesome! You've made some copper ingots. To win the tutorial, you need to make at least {} copper ingots. If you don't have enough yet, try mining some copper ore using `Territory::hand_mine`, then smelt it into copper ingots using a Furnace.",
+            guide.copper
+        )
+    }
+}
+
+impl<R: AssemblerRecipe> GuideTopic for Assembler<R> {
+    fn hint(_guide: &Guide) -> String {
+        "Assemblers craft intermediate products like copper wire, electronic circuits and science packs. Unlike hand crafting, they work in the background while time passes, so you can keep several of them busy at once.
+Add inputs using `Assembler::inputs` and collect the products using `Assembler::outputs`. An assembler can only run one recipe at a time; to switch, empty it and call `Assembler::change_recipe`.
+The first thing you need is red science, so try an assembler with the `RedScienceRecipe`. Building more assemblers costs copper wire, which another assembler with the `CopperWireRecipe` can make for you.".into()
+    }
+}
+
+impl<T: Technology> GuideTopic for Lab<T>
+where
+    TechRecipe<T>: RecipeEx,
+{
+    fn hint(_guide: &Guide) -> String {
+        "Labs turn science packs into research points for one specific technology. Add science packs using `Lab::inputs` and collect the research points using `Lab::outputs`.
+Once you have the `REQUIRED_RESEARCH_POINTS` of the technology, pass them to `Technology::research` to get what it unlocks. Research is slow, so consider building several labs, and keep them fed; a lab without science packs does nothing.
+When a technology is done, move the lab on to the next one using `Lab::change_technology`.".into()
+    }
+}
+
+impl GuideTopic for Resource<RedScience> {
+    fn hint(_guide: &Guide) -> String {
+        "Red science is the first science pack, made from iron and electronic circuits using the `RedScienceRecipe`, either by hand or in an Assembler. Put it into a Lab to produce research points.
+Every technology in the standard game needs red science, so it pays to automate its production early with Assemblers fed by furnaces.".into()
+    }
+}
+
+impl GuideTopic for SteelTechnology {
+    fn hint(_guide: &Guide) -> String {
+        "The steel technology is the first thing you can research. Build a Lab for it using `Lab::build`, fill it with red science and collect the research points.
+Researching it unlocks the `SteelSmelting` recipe, which turns iron into steel in a Furnace, along with the `PointsTechnology`.".into()
+    }
+}
+
+impl GuideTopic for PointsTechnology {
+    fn hint(_guide: &Guide) -> String {
+        format!(
+            "The points technology is the last step before victory. Research it in a Lab like any other technology to unlock the `PointRecipe`.
+Points are crafted from electronic circuits and steel in an Assembler. You need {} of them to win the standard game, so build enough assemblers, and the furnaces to feed them.",
+            <Standard as GameMode>::VICTORY_AMOUNT
+        )
     }
 }
 
@@ -73,8 +126,8 @@ impl<T> GuideTopic for &T
 where
     T: GuideTopic,
 {
-    fn hint() -> &'static str {
-        T::hint()
+    fn hint(guide: &Guide) -> String {
+        T::hint(guide)
     }
 }
 
@@ -82,22 +135,296 @@ impl<Content: ResourceType, const AMOUNT: u32> GuideTopic for Bundle<Content, AM
 where
     Resource<Content>: GuideTopic,
 {
-    fn hint() -> &'static str {
-        <Resource<Content> as GuideTopic>::hint()
+    fn hint(guide: &Guide) -> String {
+        <Resource<Content> as GuideTopic>::hint(guide)
+    }
+}
+
+/// Something whose presence the guide takes into account when suggesting a [next step](Guide::next_step).
+trait Observe {
+    fn observe(&self, state: &mut GuideState);
+}
+
+/// A resource the guide keeps count of.
+trait TrackedResource: ResourceType {
+    fn count(state: &mut GuideState) -> &mut u32;
+}
+
+impl TrackedResource for IronOre {
+    fn count(state: &mut GuideState) -> &mut u32 {
+        &mut state.iron_ore
+    }
+}
+
+impl TrackedResource for CopperOre {
+    fn count(state: &mut GuideState) -> &mut u32 {
+        &mut state.copper_ore
+    }
+}
+
+impl TrackedResource for Iron {
+    fn count(state: &mut GuideState) -> &mut u32 {
+        &mut state.iron
+    }
+}
+
+impl TrackedResource for Copper {
+    fn count(state: &mut GuideState) -> &mut u32 {
+        &mut state.copper
+    }
+}
+
+impl<Content: TrackedResource> Observe for Resource<Content> {
+    fn observe(&self, state: &mut GuideState) {
+        *Content::count(state) += self.amount();
+    }
+}
+
+impl<Content: TrackedResource, const AMOUNT: u32> Observe for Bundle<Content, AMOUNT> {
+    fn observe(&self, state: &mut GuideState) {
+        *Content::count(state) += AMOUNT;
+    }
+}
+
+impl<R: FurnaceRecipe> Observe for Furnace<R> {
+    fn observe(&self, state: &mut GuideState) {
+        state.furnaces += 1;
+    }
+}
+
+impl Observe for Miner {
+    fn observe(&self, state: &mut GuideState) {
+        state.miners += 1;
     }
 }
 
-/// A guide that provides hints to the player during the tutorial.
-#[non_exhaustive]
-pub struct Guide;
+impl<T: Observe> Observe for &T {
+    fn observe(&self, state: &mut GuideState) {
+        T::observe(self, state);
+    }
+}
+
+/// A snapshot of what the player has, for the guide to suggest a [next step](Guide::next_step) from.
+///
+/// Start from [`Guide::state`] and add everything you have using [`with`](GuideState::with), for example
+/// `guide.state().with(&copper_ore).with(&furnace)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GuideState {
+    iron_ore: u32,
+    copper_ore: u32,
+    iron: u32,
+    copper: u32,
+    furnaces: u32,
+    miners: u32,
+}
+
+impl GuideState {
+    /// Adds something the player has to the snapshot.
+    #[allow(private_bounds)]
+    pub fn with<T: Observe>(mut self, thing: T) -> Self {
+        thing.observe(&mut self);
+        self
+    }
+}
+
+/// A guide that provides hints to the player during the tutorial and the standard game.
+pub struct Guide {
+    /// How much copper the tutorial asks for.
+    copper: u32,
+}
 
 impl Guide {
+    /// Creates a guide for a tutorial that asks for `copper` copper.
+    pub(crate) const fn new(copper: u32) -> Self {
+        Self { copper }
+    }
+
     /// Provides a hint about the specified topic and exits the program.
     #[allow(unused_variables)]
     #[allow(private_bounds)]
     pub fn hint<T: GuideTopic>(&self, topic: T) -> ! {
-        let message = T::hint();
+        let message = self.describe(topic);
         println!("{message}");
         process::exit(0);
     }
+
+    /// Returns the hint about the specified topic without exiting, so several hints can be printed or inspected.
+    #[allow(unused_variables)]
+    #[allow(private_bounds)]
+    pub fn describe<T: GuideTopic>(&self, topic: T) -> String {
+        T::hint(self)
+    }
+
+    /// Starts an empty snapshot of what the player has, to pass to [`next_step`](Guide::next_step).
+    pub fn state(&self) -> GuideState {
+        GuideState::default()
+    }
+
+    /// Suggests the most useful thing to do next in the tutorial, given what the player has.
+    pub fn next_step(&self, state: &GuideState) -> String {
+        if state.copper >= self.copper {
+            "You have enough copper to win the tutorial! Return it from `user_main` together with the `tick`, using `.into()` to turn it into the victory resources.".into()
+        } else if state.copper_ore > 0 && state.furnaces == 0 {
+            if state.iron >= 10 {
+                "You have copper ore but nothing to smelt it in. Build a Furnace from 10 iron using `Furnace::build` with the `CopperSmelting` recipe.".into()
+            } else {
+                "You have copper ore but nothing to smelt it in, and not enough iron to build a Furnace. Mine iron ore using `Territory::hand_mine` and smelt it, or find a Furnace you already built.".into()
+            }
+        } else if state.copper_ore > 0 {
+            "Add your copper ore to the Furnace using `Furnace::inputs`, advance time using `Tick::advance_until`, and collect the copper from `Furnace::outputs`.".into()
+        } else if state.copper > 0 {
+            format!(
+                "You're on your way, but the tutorial needs {} copper. Mine more copper ore using `Territory::hand_mine` and smelt it in your Furnace.",
+                self.copper
+            )
+        } else if state.furnaces > 0 || state.miners > 0 {
+            "Your buildings need something to work with. Mine copper ore using `Territory::hand_mine`, or collect what your miners have mined using `Territory::resources`.".into()
+        } else if state.iron >= 10 {
+            "Start by building a Furnace from your iron using `Furnace::build` with the `CopperSmelting` recipe, then mine some copper ore to smelt in it.".into()
+        } else {
+            "Mine some copper ore from your copper territory using `Territory::hand_mine`.".into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustorio_engine::{bundle, research::ResearchPoint, resource};
+
+    use super::*;
+    use crate::{
+        gamemodes::{TUTORIAL_COPPER, Tutorial, TutorialStartingResources},
+        recipes::{CopperSmelting, CopperWireRecipe, RedScienceRecipe},
+    };
+
+    const GUIDE: Guide = Guide::new(TUTORIAL_COPPER);
+
+    /// Every method the guide refers to by path. Each one is called in [`hinted_methods_exist`],
+    /// so renaming any of them breaks the build until the hints are updated too.
+    const HINTED_METHODS: &[&str] = &[
+        "Tick::advance",
+        "Tick::advance_until",
+        "Territory::hand_mine",
+        "Territory::resources",
+        "Furnace::build",
+        "Furnace::inputs",
+        "Furnace::outputs",
+        "Assembler::inputs",
+        "Assembler::outputs",
+        "Assembler::change_recipe",
+        "Lab::build",
+        "Lab::inputs",
+        "Lab::outputs",
+        "Lab::change_technology",
+        "Technology::research",
+        "Guide::state",
+    ];
+
+    #[allow(dead_code)]
+    fn hinted_methods_exist(
+        mut tick: Tick,
+        mut territory: Territory<CopperOre>,
+        mut assembler: Assembler<RedScienceRecipe>,
+        steel_technology: SteelTechnology,
+        points_technology: PointsTechnology,
+        research_points: Bundle<
+            ResearchPoint<SteelTechnology>,
+            { SteelTechnology::REQUIRED_RESEARCH_POINTS },
+        >,
+    ) {
+        tick.advance();
+        tick.advance_until(|_| true, 1);
+        let ore = territory.hand_mine::<1>(&mut tick);
+        let _ = territory.resources(&tick).empty();
+
+        let mut furnace = Furnace::build(&tick, CopperSmelting, bundle());
+        furnace.inputs(&tick).0 += ore;
+        let _ = furnace.outputs(&tick).0.empty();
+
+        let _ = assembler.inputs(&tick).0.empty();
+        let _ = assembler.outputs(&tick).0.empty();
+        let _ = assembler.change_recipe(CopperWireRecipe);
+
+        let mut lab = Lab::build(&tick, &steel_technology, bundle(), bundle());
+        let _ = lab.inputs(&tick).0.empty();
+        let _ = lab.outputs(&tick).0.empty();
+        let _ = lab.change_technology(&points_technology);
+        let _ = steel_technology.research(&tick, research_points);
+
+        let _ = GUIDE.state();
+    }
+
+    #[test]
+    fn hints_only_reference_existing_methods() {
+        let source = include_str!("guide.rs");
+        let referenced = source.split('`').skip(1).step_by(2).filter(|quoted| {
+            quoted.contains("::")
+                && quoted
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == ':' || c == '_')
+        });
+        for path in referenced {
+            assert!(
+                HINTED_METHODS.contains(&path),
+                "The guide refers to `{path}`, which is not in HINTED_METHODS"
+            );
+        }
+    }
+
+    #[test]
+    fn copper_ore_without_furnace_suggests_building_one() {
+        let state = GUIDE
+            .state()
+            .with(resource::<CopperOre>(3))
+            .with(bundle::<Iron, 10>());
+        assert!(GUIDE.next_step(&state).contains("Furnace::build"));
+
+        let state = GUIDE.state().with(resource::<CopperOre>(3));
+        assert!(GUIDE.next_step(&state).contains("not enough iron"));
+    }
+
+    #[test]
+    fn enough_copper_suggests_winning() {
+        let state = GUIDE
+            .state()
+            .with(resource::<Copper>(1))
+            .with(resource::<Copper>(3));
+        assert!(GUIDE.next_step(&state).contains("win the tutorial"));
+
+        let state = GUIDE.state().with(resource::<Copper>(3));
+        assert!(GUIDE.next_step(&state).contains("needs 4 copper"));
+    }
+
+    #[test]
+    fn hints_follow_the_tutorial_copper_amount() {
+        let _ = rustorio_engine::run_gamemode::<Tutorial<7>>(
+            |tick, TutorialStartingResources { guide, .. }| {
+                let state = guide.state().with(resource::<Copper>(4));
+                assert!(guide.next_step(&state).contains("needs 7 copper"));
+                assert!(
+                    guide
+                        .describe(resource::<Copper>(4))
+                        .contains("at least 7 copper ingots")
+                );
+
+                let state = guide.state().with(resource::<Copper>(7));
+                assert!(guide.next_step(&state).contains("win the tutorial"));
+                (tick, Resource::new_empty().into())
+            },
+        );
+    }
+
+    #[test]
+    fn points_hint_follows_the_standard_goal() {
+        let hint = GUIDE.describe(PointsTechnology);
+        assert!(hint.contains(&format!(
+            "You need {} of them",
+            <Standard as GameMode>::VICTORY_AMOUNT
+        )));
+    }
+
+    #[test]
+    fn empty_state_suggests_mining() {
+        assert!(GUIDE.next_step(&GUIDE.state()).contains("hand_mine"));
+    }
 }
@@ -1,3 +1,5 @@
+#![feature(generic_const_exprs)]
+#![allow(incomplete_features)] // silence the “still incomplete” lint
 #![warn(missing_docs)]
 //! The base mod for Rustorio.
 //! Contains all the main content of the game.
@@ -6,6 +8,7 @@
 //! For more information, including help on getting started, see the [repo](https://github.com/albertsgarde/rustorio)
 
 pub mod buildings;
+pub mod chain;
 pub mod gamemodes;
 pub mod guide;
 pub mod recipes;
@@ -4,17 +4,26 @@
 //! For example, the [`CopperWireRecipe`] implements the [`AssemblerRecipe`] and [`HandRecipe`] traits, meaning it can be performed by hand and in an [`Assembler`](crate::buildings::Assembler).
 //!
 //! The documentation for each recipe struct provides more details about the recipe, including inputs, outputs and time taken.
+//!
+//! The road to victory in the standard game mode goes like this:
+//! ore is smelted into [iron](IronSmelting) and [copper](CopperSmelting),
+//! copper is made into [wire](CopperWireRecipe), and wire and iron into [circuits](ElectronicCircuitRecipe).
+//! Circuits are used for [red science](RedScienceRecipe), which researches [`SteelTechnology`](crate::research::SteelTechnology) to unlock [steel](SteelSmelting).
+//! Steel then unlocks [`PointsTechnology`](crate::research::PointsTechnology), whose [`PointRecipe`] turns circuits and steel into [points](crate::resources::Point).
 
 use std::fmt::Debug;
 
 use rustorio_engine::{
     Sealed,
     recipe::{HandRecipe, Recipe, RecipeEx, recipe_doc},
+    research::{GenericResearchRecipe, TechRecipe, Technology},
 };
 
 use crate::{
-    research::RedScience,
-    resources::{Copper, CopperOre, CopperWire, ElectronicCircuit, Iron, IronOre, Point, Steel},
+    research::{BlueScience, GreenScience, RedScience},
+    resources::{
+        Copper, CopperOre, CopperWire, ElectronicCircuit, Iron, IronOre, Point, Steel, Water,
+    },
 };
 
 /// Any recipe that implements this trait can be used in an [`Assembler`](crate::buildings::Assembler).
@@ -49,6 +58,24 @@ impl Sealed for ElectronicCircuitRecipe {}
 impl AssemblerRecipe for ElectronicCircuitRecipe {}
 impl HandRecipe for ElectronicCircuitRecipe {}
 
+/// Breaks an [electronic circuit](crate::resources::ElectronicCircuit) back down into [iron](crate::resources::Iron) and [copper wire](crate::resources::CopperWire).
+/// Some of the copper wire is lost in the process.
+///
+/// Produces two different resources, found in `outputs.0` and `outputs.1` respectively.
+#[derive(Debug, Clone, Copy, Recipe, RecipeEx)]
+#[recipe_doc]
+#[recipe_inputs(
+    (1, ElectronicCircuit),
+)]
+#[recipe_outputs(
+    (1, Iron),
+    (1, CopperWire),
+)]
+#[recipe_ticks(2)]
+pub struct CircuitRecycling;
+impl Sealed for CircuitRecycling {}
+impl AssemblerRecipe for CircuitRecycling {}
+
 /// A recipe for crafting red science packs.
 #[derive(Debug, Clone, Copy, Recipe, RecipeEx)]
 #[recipe_doc]
@@ -65,6 +92,39 @@ impl Sealed for RedScienceRecipe {}
 impl AssemblerRecipe for RedScienceRecipe {}
 impl HandRecipe for RedScienceRecipe {}
 
+/// A recipe for crafting green science packs. Requires [steel](crate::resources::Steel).
+#[derive(Debug, Clone, Copy, Recipe, RecipeEx)]
+#[recipe_doc]
+#[recipe_inputs(
+    (1, Steel),
+    (1, ElectronicCircuit),
+)]
+#[recipe_outputs(
+    (1, GreenScience),
+)]
+#[recipe_ticks(15)]
+pub struct GreenScienceRecipe;
+impl Sealed for GreenScienceRecipe {}
+impl AssemblerRecipe for GreenScienceRecipe {}
+
+/// A recipe for crafting blue science packs.
+///
+/// You can unlock this recipe by researching [`BlueScienceTechnology`](crate::research::BlueScienceTechnology).
+#[derive(Debug, Clone, Copy, Recipe, RecipeEx)]
+#[recipe_doc]
+#[recipe_inputs(
+    (2, Steel),
+    (4, ElectronicCircuit),
+)]
+#[recipe_outputs(
+    (1, BlueScience),
+)]
+#[recipe_ticks(30)]
+#[non_exhaustive]
+pub struct BlueScienceRecipe;
+impl Sealed for BlueScienceRecipe {}
+impl AssemblerRecipe for BlueScienceRecipe {}
+
 /// The recipe you need to win! An [`Assembler`](crate::buildings::Assembler) recipe that creates points.
 ///
 /// You can unlock this recipe by researching [`PointsTechnology`](crate::research::PointsTechnology).
@@ -84,7 +144,23 @@ impl Sealed for PointRecipe {}
 impl AssemblerRecipe for PointRecipe {}
 
 /// Any recipe that implements this trait can be used in a [`Furnace`](crate::buildings::Furnace).
-pub trait FurnaceRecipe: Debug + Sealed + RecipeEx {}
+///
+/// Every furnace recipe also works in the more advanced furnaces, see [`SteelFurnaceRecipe`] and [`ElectricFurnaceRecipe`].
+pub trait FurnaceRecipe: SteelFurnaceRecipe {}
+
+/// Any recipe that implements this trait can be used in a [`SteelFurnace`](crate::buildings::SteelFurnace).
+pub trait SteelFurnaceRecipe: ElectricFurnaceRecipe {}
+
+/// Any recipe that implements this trait can be used in an [`ElectricFurnace`](crate::buildings::ElectricFurnace), the most advanced furnace.
+///
+/// A recipe that only implements this trait needs an electric furnace, and building a less advanced furnace with it doesn't compile:
+/// ```compile_fail,E0277
+/// # use rustorio::{Bundle, Tick, buildings::Furnace, recipes::ElectricFurnaceRecipe, resources::Iron};
+/// fn build<R: ElectricFurnaceRecipe>(tick: &Tick, recipe: R, iron: Bundle<Iron, 10>) -> Furnace<R> {
+///     Furnace::build(tick, recipe, iron)
+/// }
+/// ```
+pub trait ElectricFurnaceRecipe: Debug + Sealed + RecipeEx {}
 
 /// A [`Furnace`](crate::buildings::Furnace) recipe that smelts iron ore into iron.
 #[derive(Debug, Clone, Copy, Recipe, RecipeEx)]
@@ -98,8 +174,30 @@ pub trait FurnaceRecipe: Debug + Sealed + RecipeEx {}
 #[recipe_ticks(6)]
 pub struct IronSmelting;
 impl Sealed for IronSmelting {}
+impl ElectricFurnaceRecipe for IronSmelting {}
+impl SteelFurnaceRecipe for IronSmelting {}
 impl FurnaceRecipe for IronSmelting {}
 
+/// A [`Furnace`](crate::buildings::Furnace) recipe that smelts iron ore with steam, getting more iron out of it than [`IronSmelting`].
+/// The [water](crate::resources::Water) has to be piped in from an [`OffshorePump`](crate::buildings::OffshorePump).
+/// Unlocked by researching [`SteamSmeltingTechnology`](crate::research::SteamSmeltingTechnology).
+#[derive(Debug, Clone, Copy, Recipe, RecipeEx)]
+#[recipe_doc]
+#[recipe_inputs(
+    (2, IronOre),
+    (5, Water),
+)]
+#[recipe_outputs(
+    (3, Iron),
+)]
+#[recipe_ticks(12)]
+#[non_exhaustive]
+pub struct SteamIronSmelting;
+impl Sealed for SteamIronSmelting {}
+impl ElectricFurnaceRecipe for SteamIronSmelting {}
+impl SteelFurnaceRecipe for SteamIronSmelting {}
+impl FurnaceRecipe for SteamIronSmelting {}
+
 /// A [`Furnace`](crate::buildings::Furnace) recipe that smelts copper ore into copper.
 #[derive(Debug, Clone, Copy, Recipe, RecipeEx)]
 #[recipe_doc]
@@ -112,6 +210,8 @@ impl FurnaceRecipe for IronSmelting {}
 #[recipe_ticks(6)]
 pub struct CopperSmelting;
 impl Sealed for CopperSmelting {}
+impl ElectricFurnaceRecipe for CopperSmelting {}
+impl SteelFurnaceRecipe for CopperSmelting {}
 impl FurnaceRecipe for CopperSmelting {}
 
 /// A [`Furnace`](crate::buildings::Furnace) recipe that smelts iron into steel.
@@ -127,4 +227,129 @@ impl FurnaceRecipe for CopperSmelting {}
 #[non_exhaustive]
 pub struct SteelSmelting;
 impl Sealed for SteelSmelting {}
+impl ElectricFurnaceRecipe for SteelSmelting {}
+impl SteelFurnaceRecipe for SteelSmelting {}
 impl FurnaceRecipe for SteelSmelting {}
+
+/// Any recipe that implements this trait can be used in a [`Lab`](crate::buildings::Lab).
+///
+/// A lab either researches its technology with the [`TechRecipe`],
+/// or turns the same science packs into generic research points with the [`GenericResearchRecipe`], see [`Lab::research_generic`](crate::buildings::Lab::research_generic).
+pub trait LabRecipe: Debug + Sealed + RecipeEx {}
+impl<T: Technology> LabRecipe for TechRecipe<T> {}
+impl<T: Technology> LabRecipe for GenericResearchRecipe<T> where GenericResearchRecipe<T>: RecipeEx {}
+
+/// Something to do with each recipe of the game, see [`visit_recipes`].
+pub trait RecipeVisitor {
+    /// Called once for each recipe.
+    fn visit<R: RecipeEx>(&mut self);
+}
+
+/// Calls `visitor` with every recipe in the game, e.g. to draw the production graph.
+/// This is the one list of recipes, so tools and checks don't need to keep their own.
+pub fn visit_recipes(visitor: &mut impl RecipeVisitor) {
+    visitor.visit::<CopperWireRecipe>();
+    visitor.visit::<ElectronicCircuitRecipe>();
+    visitor.visit::<CircuitRecycling>();
+    visitor.visit::<RedScienceRecipe>();
+    visitor.visit::<GreenScienceRecipe>();
+    visitor.visit::<BlueScienceRecipe>();
+    visitor.visit::<PointRecipe>();
+    visitor.visit::<IronSmelting>();
+    visitor.visit::<SteamIronSmelting>();
+    visitor.visit::<CopperSmelting>();
+    visitor.visit::<SteelSmelting>();
+}
+
+#[cfg(test)]
+mod tests {
+    use rustorio_engine::plan::{PlanRecipe, plan};
+
+    use super::*;
+    use crate::{
+        buildings::{Assembler, Building},
+        gamemodes::{SandboxStartingResources, run_sandbox},
+    };
+
+    #[test]
+    fn all_recipes_are_balanced() {
+        struct Validate;
+        impl RecipeVisitor for Validate {
+            fn visit<R: RecipeEx>(&mut self) {
+                if let Err(err) = rustorio_engine::recipe::validate::<R>() {
+                    panic!("{err}");
+                }
+            }
+        }
+        visit_recipes(&mut Validate);
+    }
+
+    #[test]
+    fn steel_smelting_takes_five_iron_per_steel() {
+        assert_eq!(SteelSmelting::input_amount(0), Some(5));
+        assert_eq!(SteelSmelting::output_amount(0), Some(1));
+        assert_eq!(SteelSmelting::input_amount(1), None);
+        assert_eq!(SteelSmelting::time(), SteelSmelting::TIME);
+    }
+
+    #[test]
+    fn circuits_recycle_into_iron_and_wire() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut iron,
+                mut copper_wire,
+                mut electronic_circuit,
+                ..
+            } = starting_resources;
+            let mut assembler = Assembler::build(
+                &tick,
+                CircuitRecycling,
+                copper_wire.bundle().unwrap(),
+                iron.bundle().unwrap(),
+            );
+            assert_eq!(assembler.output_amounts(), (1, 1));
+            assembler.inputs(&tick).0 += electronic_circuit.split_off(3).unwrap();
+            assert!(tick.advance_until(|tick| assembler.output::<0>(tick).amount() >= 3, 100));
+
+            let (recycled_iron, recycled_wire) = assembler.outputs(&tick);
+            assert_eq!(recycled_iron.empty().amount(), 3);
+            assert_eq!(recycled_wire.empty().amount(), 3);
+            assert_eq!(assembler.input::<0>(&tick).amount(), 0);
+            assert!(assembler.change_recipe(CircuitRecycling).is_ok());
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn red_science_plan() {
+        let recipes = [
+            PlanRecipe::of::<RedScienceRecipe>(1),
+            PlanRecipe::of::<ElectronicCircuitRecipe>(1),
+            PlanRecipe::of::<CopperWireRecipe>(1),
+            PlanRecipe::of::<IronSmelting>(1),
+            PlanRecipe::of::<CopperSmelting>(1),
+        ];
+        // One red science assembler's worth: a pack every 10 ticks.
+        let plan = plan(&recipes, "RedScience", 0.1).unwrap();
+
+        let expected = [
+            ("CopperSmelting", 0.6),
+            ("CopperWireRecipe", 0.1),
+            ("ElectronicCircuitRecipe", 0.3),
+            ("IronSmelting", 1.2),
+            ("RedScienceRecipe", 1.0),
+        ];
+        assert_eq!(plan.buildings.len(), expected.len());
+        for ((recipe, count), (expected_recipe, expected_count)) in
+            plan.buildings.iter().zip(expected)
+        {
+            assert_eq!(recipe, expected_recipe);
+            assert!((count - expected_count).abs() < 1e-9, "{recipe}: {count}");
+        }
+
+        let raw: Vec<&str> = plan.raw.keys().copied().collect();
+        assert_eq!(raw, ["CopperOre", "IronOre"]);
+        assert!((plan.raw["IronOre"] - 0.2).abs() < 1e-9);
+        assert!((plan.raw["CopperOre"] - 0.1).abs() < 1e-9);
+    }
+}
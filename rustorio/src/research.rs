@@ -7,14 +7,15 @@
 //! This module defines the technologies available in Rustorio.
 
 use rustorio_engine::{
-    Sealed,
-    research::{ResearchPoint, Technology, TechnologyEx, technology_doc},
+    Sealed, machine,
+    research::{ResearchCompleted, Researched, Technology, TechnologyEx, technology_doc},
     resource_type,
 };
 
 use crate::{
-    Bundle,
-    recipes::{PointRecipe, SteelSmelting},
+    Tick,
+    buildings::{ElectricFurnaceUnlock, OffshorePumpUnlock, SteelFurnaceUnlock},
+    recipes::{BlueScienceRecipe, PointRecipe, SteamIronSmelting, SteelSmelting},
 };
 
 resource_type!(
@@ -24,6 +25,20 @@ resource_type!(
     RedScience
 );
 
+resource_type!(
+    /// A science pack used together with [`RedScience`] for more advanced technologies.
+    ///
+    /// Crafted from [this](crate::recipes::GreenScienceRecipe) recipe, which requires [steel](crate::resources::Steel).
+    GreenScience
+);
+
+resource_type!(
+    /// The most advanced science pack.
+    ///
+    /// Crafted from [this](crate::recipes::BlueScienceRecipe) recipe, which is unlocked by researching [`BlueScienceTechnology`].
+    BlueScience
+);
+
 /// Allows the further refining of iron into steel.
 #[technology_doc]
 #[derive(Debug, TechnologyEx)]
@@ -37,12 +52,10 @@ impl Sealed for SteelTechnology {}
 impl Technology for SteelTechnology {
     const NAME: &'static str = "Steel";
     type Unlocks = (SteelSmelting, PointsTechnology);
+    type Prerequisites = ();
 
-    fn research(
-        self,
-        research_points: Bundle<ResearchPoint<Self>, { Self::REQUIRED_RESEARCH_POINTS }>,
-    ) -> Self::Unlocks {
-        let _ = research_points;
+    fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+        let _ = completed;
         (SteelSmelting, PointsTechnology)
     }
 }
@@ -60,12 +73,409 @@ impl Sealed for PointsTechnology {}
 impl Technology for PointsTechnology {
     const NAME: &'static str = "Points";
     type Unlocks = PointRecipe;
+    type Prerequisites = (Researched<SteelTechnology>,);
 
-    fn research(
-        self,
-        research_points: Bundle<ResearchPoint<Self>, { Self::REQUIRED_RESEARCH_POINTS }>,
-    ) -> Self::Unlocks {
-        let _ = research_points;
+    fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+        let _ = completed;
         PointRecipe {}
     }
 }
+
+/// Unlocks the ability to craft [blue science](BlueScience).
+/// Requires both [red](RedScience) and [green](GreenScience) science to research.
+///
+/// Can only be obtained using [`unlock`](BlueScienceTechnology::unlock) after [`SteelTechnology`] has been researched.
+/// Trying to get it without proof of that does not compile:
+/// ```compile_fail
+/// let technology = rustorio::research::BlueScienceTechnology;
+/// ```
+/// ```compile_fail
+/// let technology = rustorio::research::BlueScienceTechnology::unlock(());
+/// ```
+/// Neither do [generic research points](rustorio_engine::research::GenericResearchPoint) refunded from a red science technology:
+/// ```compile_fail,E0308
+/// use rustorio::{Resource, ResearchPoint, research::{BlueScienceTechnology, SteelTechnology}};
+///
+/// fn skip_green_science(steel: Resource<ResearchPoint<SteelTechnology>>) {
+///     let mut generic = ResearchPoint::convert_generic(steel);
+///     let _ = ResearchPoint::<BlueScienceTechnology>::from_generic(&mut generic, 1);
+/// }
+/// ```
+#[technology_doc]
+#[derive(Debug, TechnologyEx)]
+#[research_inputs((1, RedScience), (1, GreenScience))]
+#[research_point_cost(50)]
+#[research_ticks(10)]
+#[non_exhaustive]
+pub struct BlueScienceTechnology;
+impl Sealed for BlueScienceTechnology {}
+
+impl BlueScienceTechnology {
+    /// Unlocks the technology given proof that its [prerequisites](Technology::Prerequisites) have been researched.
+    /// Get the proof by researching [`SteelTechnology`] using [`research_with_proof`](Technology::research_with_proof).
+    pub const fn unlock(prerequisites: <Self as Technology>::Prerequisites) -> Self {
+        let _ = prerequisites;
+        Self
+    }
+}
+
+impl Technology for BlueScienceTechnology {
+    const NAME: &'static str = "Blue science";
+    type Unlocks = BlueScienceRecipe;
+    type Prerequisites = (Researched<SteelTechnology>,);
+
+    fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+        let _ = completed;
+        BlueScienceRecipe {}
+    }
+}
+
+/// Unlocks the [`SteelFurnace`](crate::buildings::SteelFurnace), a furnace that smelts twice as fast.
+///
+/// Can only be obtained using [`unlock`](SteelFurnaceTechnology::unlock) after [`SteelTechnology`] has been researched.
+#[technology_doc]
+#[derive(Debug, TechnologyEx)]
+#[research_inputs((1, RedScience), (1, GreenScience))]
+#[research_point_cost(30)]
+#[research_ticks(10)]
+#[non_exhaustive]
+pub struct SteelFurnaceTechnology;
+impl Sealed for SteelFurnaceTechnology {}
+
+impl SteelFurnaceTechnology {
+    /// Unlocks the technology given proof that its [prerequisites](Technology::Prerequisites) have been researched.
+    pub const fn unlock(prerequisites: <Self as Technology>::Prerequisites) -> Self {
+        let _ = prerequisites;
+        Self
+    }
+}
+
+impl Technology for SteelFurnaceTechnology {
+    const NAME: &'static str = "Steel furnace";
+    type Unlocks = SteelFurnaceUnlock;
+    type Prerequisites = (Researched<SteelTechnology>,);
+
+    fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+        let _ = completed;
+        SteelFurnaceUnlock::new()
+    }
+}
+
+/// Unlocks the [`ElectricFurnace`](crate::buildings::ElectricFurnace), the fastest furnace.
+/// Requires all three science packs to research.
+///
+/// Can only be obtained using [`unlock`](ElectricFurnaceTechnology::unlock) after [`SteelFurnaceTechnology`] has been researched.
+#[technology_doc]
+#[derive(Debug, TechnologyEx)]
+#[research_inputs((1, RedScience), (1, GreenScience), (1, BlueScience))]
+#[research_point_cost(50)]
+#[research_ticks(15)]
+#[non_exhaustive]
+pub struct ElectricFurnaceTechnology;
+impl Sealed for ElectricFurnaceTechnology {}
+
+impl ElectricFurnaceTechnology {
+    /// Unlocks the technology given proof that its [prerequisites](Technology::Prerequisites) have been researched.
+    pub const fn unlock(prerequisites: <Self as Technology>::Prerequisites) -> Self {
+        let _ = prerequisites;
+        Self
+    }
+}
+
+impl Technology for ElectricFurnaceTechnology {
+    const NAME: &'static str = "Electric furnace";
+    type Unlocks = ElectricFurnaceUnlock;
+    type Prerequisites = (Researched<SteelFurnaceTechnology>,);
+
+    fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+        let _ = completed;
+        ElectricFurnaceUnlock::new()
+    }
+}
+
+/// Unlocks [`SteamIronSmelting`] and the [`OffshorePump`](crate::buildings::OffshorePump) that pumps the water for it.
+///
+/// Can only be obtained using [`unlock`](SteamSmeltingTechnology::unlock) after [`SteelTechnology`] has been researched.
+#[technology_doc]
+#[derive(Debug, TechnologyEx)]
+#[research_inputs((1, RedScience), (1, GreenScience))]
+#[research_point_cost(30)]
+#[research_ticks(10)]
+#[non_exhaustive]
+pub struct SteamSmeltingTechnology;
+impl Sealed for SteamSmeltingTechnology {}
+
+impl SteamSmeltingTechnology {
+    /// Unlocks the technology given proof that its [prerequisites](Technology::Prerequisites) have been researched.
+    pub const fn unlock(prerequisites: <Self as Technology>::Prerequisites) -> Self {
+        let _ = prerequisites;
+        Self
+    }
+}
+
+impl Technology for SteamSmeltingTechnology {
+    const NAME: &'static str = "Steam smelting";
+    type Unlocks = (SteamIronSmelting, OffshorePumpUnlock);
+    type Prerequisites = (Researched<SteelTechnology>,);
+
+    fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+        let _ = completed;
+        (SteamIronSmelting, OffshorePumpUnlock::new())
+    }
+}
+
+/// The percentage by which each level of [`MiningProductivity`] increases the ore mined by [`Miner`](crate::territory::Miner)s.
+pub const MINING_PRODUCTIVITY_PER_LEVEL: u32 = 10;
+
+/// Makes [`Miner`](crate::territory::Miner)s mine [`MINING_PRODUCTIVITY_PER_LEVEL`] percent more ore per level.
+///
+/// This technology can be researched repeatedly. Researching level `LEVEL` unlocks the [next level](NextMiningProductivity) of the technology,
+/// which costs more research points than the last, along with a [`MiningProductivityBonus`] to apply to your territories.
+///
+/// Can only be obtained using [`unlock`](MiningProductivity::unlock) after [`SteelTechnology`] has been researched.
+#[technology_doc]
+#[derive(Debug, TechnologyEx)]
+#[research_inputs((1, RedScience))]
+#[research_point_cost(50 * LEVEL)]
+#[research_ticks(10)]
+#[non_exhaustive]
+pub struct MiningProductivity<const LEVEL: u32>;
+impl<const LEVEL: u32> Sealed for MiningProductivity<LEVEL> {}
+
+impl MiningProductivity<1> {
+    /// Unlocks the first level of the technology given proof that its [prerequisites](Technology::Prerequisites) have been researched.
+    pub const fn unlock(prerequisites: <Self as Technology>::Prerequisites) -> Self {
+        let _ = prerequisites;
+        Self
+    }
+}
+
+impl<const LEVEL: u32> Technology for MiningProductivity<LEVEL> {
+    const NAME: &'static str = "Mining productivity";
+    type Unlocks = (NextMiningProductivity<LEVEL>, MiningProductivityBonus);
+    type Prerequisites = (Researched<SteelTechnology>,);
+
+    fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+        let _ = completed;
+        (
+            NextMiningProductivity,
+            MiningProductivityBonus { level: LEVEL },
+        )
+    }
+}
+
+/// Unlocked by researching level `LEVEL` of [`MiningProductivity`].
+/// Use [`unlock`](NextMiningProductivity::unlock) to get the next level of the technology.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct NextMiningProductivity<const LEVEL: u32>;
+
+impl<const LEVEL: u32> NextMiningProductivity<LEVEL> {
+    /// Unlocks the next level of [`MiningProductivity`].
+    pub const fn unlock(self) -> MiningProductivity<{ LEVEL + 1 }>
+    where
+        [(); { LEVEL + 1 } as usize]:,
+    {
+        MiningProductivity
+    }
+}
+
+/// The effect of researching [`MiningProductivity`].
+/// Apply it to a territory using [`Territory::apply_productivity_bonus`](crate::territory::Territory::apply_productivity_bonus).
+///
+/// Bonuses do not stack. A territory always uses the highest level bonus applied to it,
+/// so the same bonus can be applied to all your territories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MiningProductivityBonus {
+    level: u32,
+}
+
+impl MiningProductivityBonus {
+    /// The [`MiningProductivity`] level this bonus was researched at.
+    pub const fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// The percentage of extra ore mined by miners with this bonus.
+    pub const fn percent(&self) -> u32 {
+        self.level * MINING_PRODUCTIVITY_PER_LEVEL
+    }
+}
+
+/// How many times faster ore is mined by hand with a [`Pickaxe`].
+pub const PICKAXE_SPEED: u64 = 2;
+
+/// Gives you a [`Pickaxe`], making hand mining [`PICKAXE_SPEED`] times faster.
+///
+/// Can only be obtained using [`unlock`](PickaxeTechnology::unlock) after [`SteelTechnology`] has been researched.
+#[technology_doc]
+#[derive(Debug, TechnologyEx)]
+#[research_inputs((1, RedScience))]
+#[research_point_cost(10)]
+#[research_ticks(5)]
+#[non_exhaustive]
+pub struct PickaxeTechnology;
+impl Sealed for PickaxeTechnology {}
+
+impl PickaxeTechnology {
+    /// Unlocks the technology given proof that its [prerequisites](Technology::Prerequisites) have been researched.
+    pub const fn unlock(prerequisites: <Self as Technology>::Prerequisites) -> Self {
+        let _ = prerequisites;
+        Self
+    }
+}
+
+impl Technology for PickaxeTechnology {
+    const NAME: &'static str = "Pickaxe";
+    type Unlocks = Pickaxe;
+    type Prerequisites = (Researched<SteelTechnology>,);
+
+    fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+        let _ = completed;
+        Pickaxe
+    }
+}
+
+/// The effect of researching [`PickaxeTechnology`].
+/// Equip it in a territory using [`Territory::equip_pickaxe`](crate::territory::Territory::equip_pickaxe).
+///
+/// The same pickaxe can be equipped in all your territories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Pickaxe;
+
+/// How many times as fast every machine crafts once [`AutomationSpeed`] has been researched and applied.
+pub const AUTOMATION_SPEED: u64 = 2;
+
+/// Gives you an [`AutomationSpeedBonus`], making every machine, furnaces, assemblers and labs alike, [`AUTOMATION_SPEED`] times faster.
+///
+/// Can only be obtained using [`unlock`](AutomationSpeed::unlock) after [`SteelTechnology`] has been researched.
+#[technology_doc]
+#[derive(Debug, TechnologyEx)]
+#[research_inputs((1, RedScience), (1, GreenScience))]
+#[research_point_cost(100)]
+#[research_ticks(10)]
+#[non_exhaustive]
+pub struct AutomationSpeed;
+impl Sealed for AutomationSpeed {}
+
+impl AutomationSpeed {
+    /// Unlocks the technology given proof that its [prerequisites](Technology::Prerequisites) have been researched.
+    pub const fn unlock(prerequisites: <Self as Technology>::Prerequisites) -> Self {
+        let _ = prerequisites;
+        Self
+    }
+}
+
+impl Technology for AutomationSpeed {
+    const NAME: &'static str = "Automation speed";
+    type Unlocks = AutomationSpeedBonus;
+    type Prerequisites = (Researched<SteelTechnology>,);
+
+    fn reward(self, completed: ResearchCompleted) -> Self::Unlocks {
+        let _ = completed;
+        AutomationSpeedBonus
+    }
+}
+
+/// The effect of researching [`AutomationSpeed`]. [`apply`](AutomationSpeedBonus::apply) it to speed up every machine.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct AutomationSpeedBonus;
+
+impl AutomationSpeedBonus {
+    /// Makes every machine craft [`AUTOMATION_SPEED`] times as fast from now on, including those already built.
+    pub fn apply(self, tick: &mut Tick) {
+        machine::set_speed_multiplier(tick, AUTOMATION_SPEED);
+    }
+}
+
+/// Something to do with each technology of the game, see [`visit_technologies`].
+pub trait TechnologyVisitor {
+    /// Called once for each technology.
+    fn visit<T: Technology>(&mut self);
+}
+
+/// Calls `visitor` with every technology in the game, like [`visit_recipes`](crate::recipes::visit_recipes) does for recipes.
+/// Of the [`MiningProductivity`] levels, only the first is visited.
+pub fn visit_technologies(visitor: &mut impl TechnologyVisitor) {
+    visitor.visit::<SteelTechnology>();
+    visitor.visit::<PointsTechnology>();
+    visitor.visit::<BlueScienceTechnology>();
+    visitor.visit::<SteelFurnaceTechnology>();
+    visitor.visit::<ElectricFurnaceTechnology>();
+    visitor.visit::<SteamSmeltingTechnology>();
+    visitor.visit::<MiningProductivity<1>>();
+    visitor.visit::<PickaxeTechnology>();
+    visitor.visit::<AutomationSpeed>();
+}
+
+#[cfg(test)]
+mod tests {
+    use rustorio_engine::bundle;
+
+    use super::*;
+    use crate::{
+        buildings::{Building, Furnace},
+        gamemodes::{SandboxStartingResources, run_sandbox},
+        recipes::IronSmelting,
+    };
+
+    #[test]
+    fn mining_productivity_levels_scale_in_cost() {
+        run_sandbox(|tick, _| {
+            assert_eq!(MiningProductivity::<1>::REQUIRED_RESEARCH_POINTS, 50);
+            assert_eq!(MiningProductivity::<2>::REQUIRED_RESEARCH_POINTS, 100);
+            assert_eq!(MiningProductivity::<3>::REQUIRED_RESEARCH_POINTS, 150);
+
+            let level_1 = MiningProductivity::<1>;
+            let (level_2, bonus_1) = level_1.research(&tick, bundle());
+            let (level_3, bonus_2) = level_2.unlock().research(&tick, bundle());
+            let (_level_4, bonus_3) = level_3.unlock().research(&tick, bundle());
+
+            assert_eq!(bonus_1.percent(), 10);
+            assert_eq!(bonus_2.percent(), 20);
+            assert_eq!(bonus_3.percent(), 30);
+            (tick, ())
+        });
+    }
+
+    /// Ticks it takes `furnace` to smelt 10 ore.
+    fn smelt_ten(tick: &mut Tick, furnace: &mut Furnace<IronSmelting>) -> u64 {
+        let start = tick.cur();
+        let _ = furnace.output::<0>(tick).empty();
+        assert!(tick.advance_until(|tick| furnace.output::<0>(tick).amount() >= 10, 1000));
+        tick.cur() - start
+    }
+
+    #[test]
+    fn automation_speed_speeds_up_furnaces() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut iron_ore,
+                mut iron,
+                steel_technology,
+                ..
+            } = starting_resources;
+            let mut furnace = Furnace::build(&tick, IronSmelting, iron.bundle().unwrap());
+            furnace.inputs(&tick).0 += iron_ore.split_off(20).unwrap();
+            let before = smelt_ten(&mut tick, &mut furnace);
+
+            let (_, steel_researched) = steel_technology.research_with_proof(&tick, bundle());
+            let bonus = AutomationSpeed::unlock((steel_researched,)).research(&tick, bundle());
+            bonus.apply(&mut tick);
+            assert_eq!(tick.machine_speed_multiplier(), AUTOMATION_SPEED);
+            assert_eq!(
+                smelt_ten(&mut tick, &mut furnace) * AUTOMATION_SPEED,
+                before
+            );
+            (tick, ())
+        });
+        // The bonus belongs to that game alone.
+        run_sandbox(|tick, _| {
+            assert_eq!(tick.machine_speed_multiplier(), 1);
+            (tick, ())
+        });
+    }
+}
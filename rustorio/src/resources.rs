@@ -4,7 +4,7 @@
 //!
 //! This module defines the core resources used in Rustorio.
 
-use rustorio_engine::resource_type;
+use rustorio_engine::{fluid_type, resource_type};
 
 resource_type!(
     /// Raw iron ore mined from the ground.
@@ -53,3 +53,16 @@ resource_type!(
     /// Made from [`steel`](crate::resources::Steel) and [`electronic circuits`](crate::resources::ElectronicCircuit).
     Point
 );
+
+fluid_type!(
+    /// Water pumped by an [`OffshorePump`](crate::buildings::OffshorePump), used for [steam smelting](crate::recipes::SteamIronSmelting).
+    /// A fluid, so it can't be carried in a [`Bundle`](crate::Bundle) and has to be [piped](crate::buildings::OffshorePump::pipe_to) into buildings.
+    ///
+    /// ```compile_fail,E0277
+    /// # use rustorio::{Bundle, Resource, resources::Water};
+    /// fn carry(water: &mut Resource<Water>) -> Bundle<Water, 5> {
+    ///     water.bundle().unwrap()
+    /// }
+    /// ```
+    Water
+);
@@ -6,18 +6,39 @@ use std::fmt::Display;
 
 use rustorio_engine::{
     ResourceType, bundle,
-    mod_reexports::{Bundle, Resource, Tick},
+    game_state::{
+        Persist,
+        serde::{Deserialize, Serialize},
+    },
+    logistics::{self, MiningBudget},
+    mod_reexports::{AdvanceCause, Bundle, Difficulty, Resource, Tick},
+    replay::{self, ActionKind},
     resource,
+    resources::Solid,
+    rng::SeededRng,
+    stats,
 };
 
-use crate::resources::{Copper, Iron};
+use crate::{
+    research::{MiningProductivityBonus, PICKAXE_SPEED, Pickaxe},
+    resources::{Copper, Iron},
+};
 
-/// Ore is mined every MINING_TICK_LENGTH ticks by each miner in a territory.
+/// By default, ore is mined every MINING_TICK_LENGTH ticks by each miner in a territory.
+/// Individual territories may mine at a different speed, see [`Territory::mining_tick_length`].
 pub const MINING_TICK_LENGTH: u64 = 2;
 
-const fn tick_to_mining_tick(tick: u64) -> u64 {
-    tick / MINING_TICK_LENGTH
-}
+/// How often miners mine on [`Difficulty::Easy`], replacing [`MINING_TICK_LENGTH`].
+pub const EASY_MINING_TICK_LENGTH: u64 = 1;
+
+/// How often miners mine on [`Difficulty::Hard`], replacing [`MINING_TICK_LENGTH`].
+pub const HARD_MINING_TICK_LENGTH: u64 = 4;
+
+/// The smallest deposit a territory generated from a [seed](Tick::seed) can have.
+pub const GENERATED_DEPOSIT_MIN: u32 = 50_000;
+
+/// The largest deposit a territory generated from a [seed](Tick::seed) can have.
+pub const GENERATED_DEPOSIT_MAX: u32 = 200_000;
 
 /// A miner that can be added to a territory to mine resources.
 #[derive(Debug)]
@@ -30,8 +51,22 @@ impl Miner {
         let _ = (iron, copper);
         Miner
     }
+
+    /// Deconstructs the miner, refunding [`MINER_IRON_REFUND`] of the 10 [iron](crate::resources::Iron) it cost to build.
+    /// The [copper](crate::resources::Copper) used to build it is lost.
+    pub fn deconstruct(self) -> Bundle<Iron, MINER_IRON_REFUND> {
+        let _ = self;
+        bundle()
+    }
 }
 
+/// The amount of [iron](crate::resources::Iron) refunded when deconstructing a [`Miner`].
+/// This is half the iron it costs to build one.
+pub const MINER_IRON_REFUND: u32 = 5;
+
+/// The iron refunded when deconstructing a [`Miner`].
+pub type MinerRefund = Bundle<Iron, MINER_IRON_REFUND>;
+
 /// Error returned when trying to add a miner to a full territory.
 #[derive(Debug)]
 pub struct TerritoryFullError {
@@ -51,25 +86,165 @@ impl Display for TerritoryFullError {
     }
 }
 
+/// Production statistics for a [`Territory`]. Returned by [`Territory::stats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerritoryStats {
+    /// Total amount of ore mined by miners in the territory since it was created.
+    pub total_mined: u64,
+    /// The current mining rate in ore per tick, based on the number of miners.
+    pub rate: f64,
+    /// Ticks passed since the mined ore was last accessed using [`Territory::resources`].
+    pub ticks_since_collection: u64,
+}
+
+/// A function called when a territory's deposit runs out, set with [`Territory::on_exhausted`].
+struct ExhaustedCallback(Box<dyn FnOnce()>);
+
+impl std::fmt::Debug for ExhaustedCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ExhaustedCallback")
+    }
+}
+
 /// A territory that can hold miners to mine a specific type of ore.
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct Territory<OreType: ResourceType> {
     mining_tick: u64,
+    mining_tick_length: u64,
     /// The maximum number of miners allowed in the territory.
     max_miners: u32,
     miners: u32,
     resources: Resource<OreType>,
+    /// Ore left in the territory's deposit. `None` if the deposit is infinite.
+    deposit: Option<u32>,
+    /// Percentage of extra ore mined by miners.
+    productivity_percent: u32,
+    /// Hundredths of an ore of productivity bonus not yet mined.
+    productivity_progress: u32,
+    /// Whether a [`Pickaxe`] has been equipped, speeding up hand mining.
+    pickaxe: bool,
+    total_mined: u64,
+    last_collection_tick: u64,
+    /// Miner ticks owed by the [mining budget](rustorio_engine::logistics), in units of `1 / budget_progress_miners`.
+    budget_progress: u32,
+    /// How many miners were sharing the mining budget when `budget_progress` was last updated.
+    budget_progress_miners: u32,
+    /// How many of the territory's miners count against the mining budget.
+    /// Restored territories register their miners the first time they are used.
+    registered_miners: u32,
+    on_exhausted: Option<ExhaustedCallback>,
 }
 
 impl<OreType: ResourceType> Territory<OreType> {
-    /// Creates a new territory that can hold up to `max_miners` miners.
-    pub(crate) const fn new(tick: &Tick, max_miners: u32) -> Self {
+    /// Creates a new territory that can hold up to `max_miners` miners and mines every [`MINING_TICK_LENGTH`] ticks,
+    /// or as often as the game's [difficulty](Tick::difficulty) dictates.
+    ///
+    /// For custom game modes to hand out territories in their [starting resources](rustorio_engine::gamemodes::StartingResources).
+    ///
+    /// # Panics
+    /// Panics unless the game [is starting](Tick::is_starting), so territories can't be created out of nothing during play.
+    pub const fn new(tick: &Tick, max_miners: u32) -> Self {
+        assert!(
+            tick.is_starting(),
+            "Territories can only be created while the game starts"
+        );
+        let mining_tick_length = match tick.difficulty() {
+            Difficulty::Easy => EASY_MINING_TICK_LENGTH,
+            Difficulty::Normal => MINING_TICK_LENGTH,
+            Difficulty::Hard => HARD_MINING_TICK_LENGTH,
+        };
+        Self::with_mining_tick_length(tick, max_miners, mining_tick_length)
+    }
+
+    /// Creates a new territory that can hold up to `max_miners` miners and mines every `mining_tick_length` ticks.
+    pub(crate) const fn with_mining_tick_length(
+        tick: &Tick,
+        max_miners: u32,
+        mining_tick_length: u64,
+    ) -> Self {
+        Self::new_inner(tick.cur(), max_miners, mining_tick_length)
+    }
+
+    const fn new_inner(tick: u64, max_miners: u32, mining_tick_length: u64) -> Self {
+        assert!(
+            mining_tick_length > 0,
+            "Mining tick length must be positive"
+        );
         Self {
-            mining_tick: tick_to_mining_tick(tick.cur()),
+            mining_tick: tick / mining_tick_length,
+            mining_tick_length,
             max_miners,
             miners: 0,
             resources: Resource::new_empty(),
+            deposit: None,
+            productivity_percent: 0,
+            productivity_progress: 0,
+            pickaxe: false,
+            total_mined: 0,
+            last_collection_tick: tick,
+            budget_progress: 0,
+            budget_progress_miners: 0,
+            registered_miners: 0,
+            on_exhausted: None,
+        }
+    }
+
+    /// Creates a new territory like [`new`](Territory::new), but with its size and deposit drawn from `rng`.
+    /// It has room for between half and one and a half times `max_miners` miners,
+    /// and a deposit of [`GENERATED_DEPOSIT_MIN`] to [`GENERATED_DEPOSIT_MAX`] ore.
+    pub(crate) fn generate(tick: &Tick, max_miners: u32, rng: &mut SeededRng) -> Self {
+        let (max_miners, deposit) = Self::generated_size(max_miners, rng);
+        Self::new(tick, max_miners).with_deposit(deposit)
+    }
+
+    fn generated_size(max_miners: u32, rng: &mut SeededRng) -> (u32, u32) {
+        let max_miners = rng.range(max_miners / 2..=max_miners * 3 / 2).max(1);
+        (
+            max_miners,
+            rng.range(GENERATED_DEPOSIT_MIN..=GENERATED_DEPOSIT_MAX),
+        )
+    }
+
+    /// Limits the territory's deposit to at most `ore` ore. Once it is depleted, nothing more can be mined.
+    /// If the deposit already has less ore left, it is unchanged.
+    pub const fn with_deposit(mut self, ore: u32) -> Self {
+        self.deposit = match self.deposit {
+            Some(remaining) if remaining < ore => Some(remaining),
+            _ => Some(ore),
+        };
+        self
+    }
+
+    const fn tick_to_mining_tick(&self, tick: u64) -> u64 {
+        tick / self.mining_tick_length
+    }
+
+    /// Returns how many ticks it takes each miner in this territory to mine one ore.
+    /// This is also the number of ticks it takes to mine one ore by hand.
+    pub const fn mining_tick_length(&self) -> u64 {
+        self.mining_tick_length
+    }
+
+    /// Returns how much ore is left in the territory's deposit, or `None` if the deposit is infinite.
+    pub fn remaining_ore(&mut self, tick: &Tick) -> Option<u32> {
+        self.tick(tick);
+        self.deposit
+    }
+
+    /// Takes up to `amount` ore from the deposit, returning how much was actually taken.
+    const fn take_from_deposit(&mut self, amount: u32) -> u32 {
+        match &mut self.deposit {
+            Some(remaining) => {
+                let taken = if *remaining < amount {
+                    *remaining
+                } else {
+                    amount
+                };
+                *remaining -= taken;
+                taken
+            }
+            None => amount,
         }
     }
 
@@ -84,20 +259,237 @@ impl<OreType: ResourceType> Territory<OreType> {
     }
 
     fn tick(&mut self, tick: &Tick) {
-        let mining_tick = tick_to_mining_tick(tick.cur());
+        let mined = self.tick_inner(tick.cur(), tick.mining_budget().as_deref());
+        stats::record_produced(tick, OreType::NAME, mined);
+        self.register_miners(tick);
+        self.check_exhausted();
+    }
+
+    /// Calls the [`on_exhausted`](Territory::on_exhausted) callback if the deposit has run out.
+    fn check_exhausted(&mut self) {
+        if self.deposit == Some(0)
+            && let Some(ExhaustedCallback(on_exhausted)) = self.on_exhausted.take()
+        {
+            on_exhausted();
+        }
+    }
+
+    /// Calls `f` once the territory's deposit has run out, so its miners can be [reclaimed](Territory::reclaim_miners).
+    ///
+    /// Like mining, this happens when the territory is next used after the deposit runs out, e.g. through [`resources`](Territory::resources),
+    /// rather than at the exact tick. Territories with infinite deposits never run out.
+    /// Replaces any earlier callback. The callback is dropped when the game is saved.
+    pub fn on_exhausted(&mut self, f: impl FnOnce() + 'static) {
+        self.on_exhausted = Some(ExhaustedCallback(Box::new(f)));
+    }
+
+    /// Makes the territory's miners count against the game's mining budget, if it has one.
+    fn register_miners(&mut self, tick: &Tick) {
+        logistics::miners_changed(tick, self.registered_miners, self.miners);
+        self.registered_miners = self.miners;
+    }
+
+    /// Mines up to `tick`, returning how much ore was mined.
+    fn tick_inner(&mut self, tick: u64, budget: Option<&MiningBudget>) -> u32 {
+        let mining_tick = self.tick_to_mining_tick(tick);
         assert!(self.mining_tick <= mining_tick, "Tick went backwards");
-        let mining_tick_delta = mining_tick - self.mining_tick;
-        self.resources += resource(
-            u32::try_from(mining_tick_delta).expect("Mining tick delta too large") * self.miners,
-        );
+        let base = match budget {
+            Some(budget) => self.budgeted_miner_ticks(mining_tick, budget),
+            None => (mining_tick - self.mining_tick) * u64::from(self.miners),
+        };
+        let bonus = base
+            .saturating_mul(u64::from(self.productivity_percent))
+            .saturating_add(u64::from(self.productivity_progress));
+        self.productivity_progress = u32::try_from(bonus % 100).expect("Remainder is below 100");
+        let mined = u32::try_from(base.saturating_add(bonus / 100)).unwrap_or(u32::MAX);
+        let mined = self.take_from_deposit(mined);
+        self.resources += resource(mined);
+        self.total_mined += u64::from(mined);
         self.mining_tick = mining_tick;
+        mined
+    }
+
+    /// How many ore the territory's miners mine from now until `mining_tick` before productivity,
+    /// with each miner slowed down whenever more miners share the `budget` than it allows.
+    fn budgeted_miner_ticks(&mut self, mining_tick: u64, budget: &MiningBudget) -> u64 {
+        let mut miner_ticks = 0;
+        let mut from = self.mining_tick;
+        while from < mining_tick {
+            // Mining tick `m` happens before any miners are added or taken at tick `m * mining_tick_length`.
+            let before = (from + 1) * self.mining_tick_length - 1;
+            let to = budget
+                .next_change_after(before)
+                .map_or(mining_tick, |change| {
+                    mining_tick.min(change / self.mining_tick_length)
+                });
+            let full = (to - from) * u64::from(self.miners);
+            let sharing = budget.miners_at(before);
+            if sharing <= budget.limit() {
+                miner_ticks += full;
+            } else {
+                self.rescale_budget_progress(sharing);
+                let owed = full * u64::from(budget.limit()) + u64::from(self.budget_progress);
+                miner_ticks += owed / u64::from(sharing);
+                self.budget_progress =
+                    u32::try_from(owed % u64::from(sharing)).expect("Remainder is below `sharing`");
+            }
+            from = to;
+        }
+        miner_ticks
+    }
+
+    /// Converts `budget_progress` to units of `1 / sharing` miner ticks.
+    const fn rescale_budget_progress(&mut self, sharing: u32) {
+        if self.budget_progress_miners != sharing {
+            if self.budget_progress_miners > 0 {
+                self.budget_progress = (self.budget_progress as u64 * sharing as u64
+                    / self.budget_progress_miners as u64)
+                    as u32;
+            }
+            self.budget_progress_miners = sharing;
+        }
     }
 
-    /// Mines ore by hand, advancing the tick by [`MINING_TICK_LENGTH`] for each unit mined.
-    pub fn hand_mine<const AMOUNT: u32>(&mut self, tick: &mut Tick) -> Bundle<OreType, AMOUNT> {
+    /// The fraction `(numerator, denominator)` of their normal speed miners currently mine at.
+    fn budget_share(&self, budget: Option<&MiningBudget>, now: u64) -> (u32, u32) {
+        match budget {
+            Some(budget) if budget.miners_at(now) > budget.limit() => {
+                (budget.limit(), budget.miners_at(now))
+            }
+            _ => (1, 1),
+        }
+    }
+
+    /// Returns production statistics for the territory.
+    /// The rate accounts for miners being slowed down by the game's [mining budget](crate::gamemodes::Logistics).
+    pub fn stats(&mut self, tick: &Tick) -> TerritoryStats {
         self.tick(tick);
-        tick.advance_by((u64::from(AMOUNT)) * MINING_TICK_LENGTH);
-        bundle()
+        let (share, sharing) = self.budget_share(tick.mining_budget().as_deref(), tick.cur());
+        TerritoryStats {
+            total_mined: self.total_mined,
+            rate: f64::from(self.miners) * f64::from(100 + self.productivity_percent)
+                / 100.0
+                / self.mining_tick_length as f64
+                * f64::from(share)
+                / f64::from(sharing),
+            ticks_since_collection: tick.cur() - self.last_collection_tick,
+        }
+    }
+
+    /// Mines ore by hand, advancing the tick by the territory's [`mining_tick_length`](Territory::mining_tick_length) for each unit mined.
+    /// With a [`Pickaxe`] equipped, only [`PICKAXE_SPEED`] times less time passes.
+    ///
+    /// # Panics
+    /// Panics if the territory's deposit has less than `AMOUNT` ore left.
+    /// Use [`try_hand_mine`](Territory::try_hand_mine) if the territory's deposit may be running low.
+    pub fn hand_mine<const AMOUNT: u32>(&mut self, tick: &mut Tick) -> Bundle<OreType, AMOUNT>
+    where
+        OreType: Solid,
+    {
+        self.try_hand_mine(tick)
+            .expect("Not enough ore left in the territory's deposit")
+    }
+
+    /// Mines ore by hand, advancing the tick by the territory's [`mining_tick_length`](Territory::mining_tick_length) for each unit mined.
+    /// With a [`Pickaxe`] equipped, only [`PICKAXE_SPEED`] times less time passes, rounded up.
+    /// If the territory's deposit has less than `AMOUNT` ore left, mines the rest of the deposit and returns it as an error instead.
+    /// In that case time only passes for the ore actually mined.
+    pub fn try_hand_mine<const AMOUNT: u32>(
+        &mut self,
+        tick: &mut Tick,
+    ) -> Result<Bundle<OreType, AMOUNT>, Resource<OreType>>
+    where
+        OreType: Solid,
+    {
+        self.tick(tick);
+        let mined = self.take_from_deposit(AMOUNT);
+        self.check_exhausted();
+        stats::record_produced(tick, OreType::NAME, mined);
+        replay::record(
+            tick,
+            ActionKind::Mine {
+                ore: OreType::NAME.to_string(),
+                amount: mined,
+            },
+        );
+        tick.advance_because(self.hand_mining_ticks(mined), AdvanceCause::HandMine);
+        if mined == AMOUNT {
+            Ok(bundle())
+        } else {
+            Err(resource(mined))
+        }
+    }
+
+    /// How long it takes to mine `amount` ore by hand.
+    const fn hand_mining_ticks(&self, amount: u32) -> u64 {
+        let ticks = amount as u64 * self.mining_tick_length;
+        if self.pickaxe {
+            ticks.div_ceil(PICKAXE_SPEED)
+        } else {
+            ticks
+        }
+    }
+
+    /// Advances time until the territory holds at least `amount` ore, as mined by its [`Miner`]s.
+    /// The wait is worked out directly rather than by checking every tick, so it is fast however long it is.
+    ///
+    /// Returns `false` without advancing time if the territory will never hold `amount` ore,
+    /// because it has no miners or its deposit is too small.
+    pub fn mine_until(&mut self, tick: &mut Tick, amount: u32) -> bool {
+        self.tick(tick);
+        let target_tick = self.tick_holding(tick.cur(), amount, tick.mining_budget().as_deref());
+        match target_tick {
+            Some(target_tick) => {
+                tick.advance_to_tick(target_tick);
+                self.tick(tick);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The first tick from `now` on at which the territory holds at least `amount` ore, if it ever will.
+    fn tick_holding(&self, now: u64, amount: u32, budget: Option<&MiningBudget>) -> Option<u64> {
+        let needed = amount.saturating_sub(self.resources.amount());
+        if needed == 0 {
+            return Some(now);
+        }
+        if self.miners == 0 || self.deposit.is_some_and(|remaining| remaining < needed) {
+            return None;
+        }
+        // Over `n` mining ticks, miners mine `(n * miners * (100 + productivity) + progress) / 100` ore, rounded down.
+        // With the mining budget shared by more miners than it allows, `n * miners` becomes
+        // `(n * miners * limit + budget_progress) / sharing`, also rounded down.
+        let miner_ticks = (u64::from(needed) * 100 - u64::from(self.productivity_progress))
+            .div_ceil(u64::from(100 + self.productivity_percent));
+        let (share, sharing) = self.budget_share(budget, now);
+        let budget_progress = if sharing > 1 && self.budget_progress_miners > 0 {
+            u128::from(self.budget_progress) * u128::from(sharing)
+                / u128::from(self.budget_progress_miners)
+        } else {
+            0
+        };
+        let mining_ticks = (u128::from(miner_ticks) * u128::from(sharing) - budget_progress)
+            .div_ceil(u128::from(self.miners) * u128::from(share));
+        let mining_ticks = u64::try_from(mining_ticks).expect("Wait too long");
+        Some((self.mining_tick + mining_ticks) * self.mining_tick_length)
+    }
+
+    /// Applies a [mining productivity](crate::research::MiningProductivity) bonus, making miners in this territory mine more ore.
+    /// Applying a bonus with a lower level than one already applied does nothing.
+    pub fn apply_productivity_bonus(&mut self, tick: &Tick, bonus: MiningProductivityBonus) {
+        self.tick(tick);
+        self.apply_productivity_bonus_inner(bonus);
+    }
+
+    fn apply_productivity_bonus_inner(&mut self, bonus: MiningProductivityBonus) {
+        self.productivity_percent = self.productivity_percent.max(bonus.percent());
+    }
+
+    /// Equips a [`Pickaxe`], making [hand mining](Territory::hand_mine) in this territory [`PICKAXE_SPEED`] times faster.
+    pub const fn equip_pickaxe(&mut self, pickaxe: Pickaxe) {
+        let _ = pickaxe;
+        self.pickaxe = true;
     }
 
     /// Adds a miner to the territory.
@@ -106,6 +498,7 @@ impl<OreType: ResourceType> Territory<OreType> {
         self.tick(tick);
         if self.miners < self.max_miners {
             self.miners += 1;
+            self.register_miners(tick);
             Ok(())
         } else {
             Err(TerritoryFullError {
@@ -121,15 +514,320 @@ impl<OreType: ResourceType> Territory<OreType> {
         self.tick(tick);
         if self.miners > 0 {
             self.miners -= 1;
+            self.register_miners(tick);
             Some(Miner)
         } else {
             None
         }
     }
 
+    /// Takes every miner from the territory, e.g. to move them to a fresh territory once this one's deposit has run out.
+    pub fn reclaim_miners(&mut self, tick: &Tick) -> Vec<Miner> {
+        self.tick(tick);
+        let miners = std::mem::take(&mut self.miners);
+        self.register_miners(tick);
+        (0..miners).map(|_| Miner).collect()
+    }
+
+    /// Takes a miner from the territory and [deconstructs](Miner::deconstruct) it, refunding [`MINER_IRON_REFUND`] iron.
+    /// Returns `None` if there are no miners in the territory.
+    ///
+    /// If you intend to add the miner to another territory, use [`take_miner`](Territory::take_miner) instead, which loses nothing.
+    pub fn take_miner_refund(&mut self, tick: &Tick) -> Option<MinerRefund> {
+        self.take_miner(tick).map(Miner::deconstruct)
+    }
+
     /// Access the resources mined in this territory.
     pub fn resources(&mut self, tick: &Tick) -> &mut Resource<OreType> {
         self.tick(tick);
+        self.last_collection_tick = tick.cur();
         &mut self.resources
     }
 }
+
+/// The save file data of a [`Territory`].
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rustorio_engine::game_state::serde")]
+pub struct TerritorySnapshot {
+    mining_tick: u64,
+    mining_tick_length: u64,
+    max_miners: u32,
+    miners: u32,
+    resources: u32,
+    deposit: Option<u32>,
+    productivity_percent: u32,
+    productivity_progress: u32,
+    total_mined: u64,
+    last_collection_tick: u64,
+    #[serde(default)]
+    budget_progress: u32,
+    #[serde(default)]
+    budget_progress_miners: u32,
+    #[serde(default)]
+    pickaxe: bool,
+}
+
+impl<OreType: ResourceType> Persist for Territory<OreType> {
+    type Snapshot = TerritorySnapshot;
+
+    fn snapshot(self) -> TerritorySnapshot {
+        TerritorySnapshot {
+            mining_tick: self.mining_tick,
+            mining_tick_length: self.mining_tick_length,
+            max_miners: self.max_miners,
+            miners: self.miners,
+            resources: self.resources.snapshot(),
+            deposit: self.deposit,
+            productivity_percent: self.productivity_percent,
+            productivity_progress: self.productivity_progress,
+            total_mined: self.total_mined,
+            last_collection_tick: self.last_collection_tick,
+            budget_progress: self.budget_progress,
+            budget_progress_miners: self.budget_progress_miners,
+            pickaxe: self.pickaxe,
+        }
+    }
+
+    fn restore(snapshot: TerritorySnapshot, tick: &Tick) -> Self {
+        Self {
+            mining_tick: snapshot.mining_tick,
+            mining_tick_length: snapshot.mining_tick_length,
+            max_miners: snapshot.max_miners,
+            miners: snapshot.miners,
+            resources: Resource::restore(snapshot.resources, tick),
+            deposit: snapshot.deposit,
+            productivity_percent: snapshot.productivity_percent,
+            productivity_progress: snapshot.productivity_progress,
+            total_mined: snapshot.total_mined,
+            last_collection_tick: snapshot.last_collection_tick,
+            budget_progress: snapshot.budget_progress,
+            budget_progress_miners: snapshot.budget_progress_miners,
+            pickaxe: snapshot.pickaxe,
+            registered_miners: 0,
+            on_exhausted: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use rustorio_engine::research::Technology;
+
+    use super::*;
+    use crate::{
+        gamemodes::{SandboxStartingResources, run_sandbox},
+        research::{MiningProductivity, PickaxeTechnology},
+        resources::IronOre,
+    };
+
+    #[test]
+    fn mining_tick_length_one_mines_twice_as_fast() {
+        let mut default = Territory::<IronOre>::new_inner(0, 1, MINING_TICK_LENGTH);
+        let mut fast = Territory::<IronOre>::new_inner(0, 1, 1);
+        default.miners = 1;
+        fast.miners = 1;
+
+        default.tick_inner(100, None);
+        fast.tick_inner(100, None);
+
+        assert_eq!(default.resources.amount(), 50);
+        assert_eq!(fast.resources.amount(), 100);
+    }
+
+    #[test]
+    fn mining_productivity_increases_each_level() {
+        run_sandbox(|tick, _| {
+            let (level_2, bonus_1) = MiningProductivity::<1>.research(&tick, bundle());
+            let (level_3, bonus_2) = level_2.unlock().research(&tick, bundle());
+            let (_, bonus_3) = level_3.unlock().research(&tick, bundle());
+
+            let mut territory = Territory::<IronOre>::new_inner(0, 1, 1);
+            territory.miners = 1;
+            let mut mined_per_level = Vec::new();
+            for (tick, bonus) in [(100, bonus_1), (200, bonus_2), (300, bonus_3)] {
+                territory.apply_productivity_bonus_inner(bonus);
+                territory.tick_inner(tick, None);
+                mined_per_level.push(territory.resources.empty().amount());
+            }
+            assert_eq!(mined_per_level, [110, 120, 130]);
+
+            // Applying a lower level bonus again does not stack or downgrade.
+            territory.apply_productivity_bonus_inner(bonus_1);
+            territory.tick_inner(400, None);
+            assert_eq!(territory.resources.amount(), 130);
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn productivity_does_not_overflow_long_spans() {
+        run_sandbox(|tick, _| {
+            let (_, bonus) = MiningProductivity::<1>.research(&tick, bundle());
+            let mut territory = Territory::<IronOre>::new_inner(0, 1, 1);
+            territory.miners = 1;
+            territory.apply_productivity_bonus_inner(bonus);
+            // 500M ore at 10% is more bonus percent than fits in a u32.
+            assert_eq!(territory.tick_inner(500_000_000, None), 550_000_000);
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn same_seed_generates_same_territories() {
+        let generate = |seed| {
+            let mut rng = SeededRng::new(seed);
+            [
+                Territory::<IronOre>::generated_size(20, &mut rng),
+                Territory::<IronOre>::generated_size(20, &mut rng),
+            ]
+        };
+        assert_eq!(generate(42), generate(42));
+        assert_ne!(generate(42), generate(43));
+        for (max_miners, deposit) in generate(42) {
+            assert!((10..=30).contains(&max_miners));
+            assert!((GENERATED_DEPOSIT_MIN..=GENERATED_DEPOSIT_MAX).contains(&deposit));
+        }
+    }
+
+    #[test]
+    fn pickaxe_halves_hand_mining_time() {
+        run_sandbox(|tick, _| {
+            let mut territory = Territory::<IronOre>::new_inner(0, 1, MINING_TICK_LENGTH);
+            assert_eq!(territory.hand_mining_ticks(10), 20);
+
+            territory.equip_pickaxe(PickaxeTechnology.research(&tick, bundle()));
+            assert_eq!(territory.hand_mining_ticks(10), 10);
+            assert_eq!(territory.hand_mining_ticks(0), 0);
+
+            // Odd tick counts round up.
+            let mut slow = Territory::<IronOre>::new_inner(0, 1, 1);
+            slow.equip_pickaxe(PickaxeTechnology.research(&tick, bundle()));
+            assert_eq!(slow.hand_mining_ticks(3), 2);
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn tick_holding_matches_stepping() {
+        run_sandbox(|tick, _| {
+            let (_, bonus) = MiningProductivity::<1>.research(&tick, bundle());
+            for miners in 1..=3 {
+                for amount in [1, 7, 50, 333] {
+                    let mut territory = Territory::<IronOre>::new_inner(0, 3, 3);
+                    territory.miners = miners;
+                    territory.apply_productivity_bonus_inner(bonus);
+                    territory.tick_inner(5, None);
+                    let expected = territory.tick_holding(5, amount, None).unwrap();
+
+                    let mut now = 5;
+                    while territory.resources.amount() < amount {
+                        now += 1;
+                        territory.tick_inner(now, None);
+                    }
+                    assert_eq!(expected, now, "{miners} miners, {amount} ore");
+                }
+            }
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn tick_holding_respects_deposit() {
+        let mut territory = Territory::<IronOre>::new_inner(0, 1, 1).with_deposit(10);
+        assert_eq!(territory.tick_holding(0, 5, None), None);
+        territory.miners = 1;
+        assert_eq!(territory.tick_holding(0, 10, None), Some(10));
+        assert_eq!(territory.tick_holding(0, 11, None), None);
+    }
+
+    #[test]
+    fn territories_split_mining_budget() {
+        let mut budget = MiningBudget::new(10);
+        let mut north = Territory::<IronOre>::new_inner(0, 20, 1);
+        let mut south = Territory::<IronOre>::new_inner(0, 20, 1);
+        north.miners = 10;
+        south.miners = 10;
+        budget.miners_changed(0, 0, 20);
+
+        // Twice the budget's miners each mine at half speed.
+        north.tick_inner(100, Some(&budget));
+        assert_eq!(north.resources.empty().amount(), 500);
+
+        // Taking half of one territory's miners speeds everyone up, whichever territory catches up first.
+        south.tick_inner(100, Some(&budget));
+        south.miners = 5;
+        budget.miners_changed(100, 10, 5);
+        south.tick_inner(200, Some(&budget));
+        north.tick_inner(200, Some(&budget));
+        assert_eq!(south.resources.empty().amount(), 500 + 333);
+        assert_eq!(north.resources.empty().amount(), 666);
+
+        // Within the budget, miners mine at full speed.
+        budget.miners_changed(200, 5, 0);
+        north.tick_inner(300, Some(&budget));
+        assert_eq!(north.resources.empty().amount(), 1000);
+    }
+
+    #[test]
+    fn exhausted_territory_hands_back_its_miners() {
+        run_sandbox(|mut tick, starting_resources| {
+            let SandboxStartingResources {
+                mut iron,
+                mut copper,
+                iron_territory,
+                mut copper_territory,
+                ..
+            } = starting_resources;
+            let mut iron_territory = iron_territory.with_deposit(20);
+            for _ in 0..2 {
+                let miner = Miner::build(iron.bundle().unwrap(), copper.bundle().unwrap());
+                iron_territory.add_miner(&tick, miner).unwrap();
+            }
+            let exhausted = Rc::new(Cell::new(false));
+            iron_territory.on_exhausted({
+                let exhausted = Rc::clone(&exhausted);
+                move || exhausted.set(true)
+            });
+
+            // Not yet depleted.
+            tick.advance_by(iron_territory.mining_tick_length() * 5);
+            assert_eq!(iron_territory.remaining_ore(&tick), Some(10));
+            assert!(!exhausted.get());
+
+            tick.advance_by(iron_territory.mining_tick_length() * 100);
+            assert_eq!(iron_territory.resources(&tick).amount(), 20);
+            assert!(exhausted.get());
+
+            // The idle miners move to a territory that still has ore.
+            let miners = iron_territory.reclaim_miners(&tick);
+            assert_eq!(miners.len(), 2);
+            assert_eq!(iron_territory.num_miners(), 0);
+            for miner in miners {
+                copper_territory.add_miner(&tick, miner).unwrap();
+            }
+            assert_eq!(copper_territory.num_miners(), 2);
+            (tick, ())
+        });
+    }
+
+    #[test]
+    fn tick_holding_matches_stepping_with_budget() {
+        let mut budget = MiningBudget::new(4);
+        budget.miners_changed(0, 0, 7);
+        for amount in [1, 7, 50, 333] {
+            let mut territory = Territory::<IronOre>::new_inner(0, 3, 3);
+            territory.miners = 3;
+            territory.tick_inner(5, Some(&budget));
+            let expected = territory.tick_holding(5, amount, Some(&budget)).unwrap();
+
+            let mut now = 5;
+            while territory.resources.amount() < amount {
+                now += 1;
+                territory.tick_inner(now, Some(&budget));
+            }
+            assert_eq!(expected, now, "{amount} ore");
+        }
+    }
+}
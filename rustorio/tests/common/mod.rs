@@ -0,0 +1,15 @@
+//! Shared by the integration tests, each of which plays a game through the public API.
+
+use rustorio::{GameMode, GameResult, Tick};
+use rustorio_engine::run_gamemode;
+
+/// Plays a game of `G` with `main`, and panics unless it was won.
+pub fn win<G: GameMode>(
+    main: fn(Tick, G::StartingResources) -> (Tick, G::VictoryResources),
+) -> GameResult<G> {
+    let result = run_gamemode::<G>(main);
+    if let Err(err) = &result.outcome {
+        panic!("The game was lost: {err}");
+    }
+    result
+}
@@ -0,0 +1,32 @@
+use rustorio::{
+    Bundle, GameResult,
+    resources::{Iron, Steel},
+};
+use rustorio_engine::game_mode;
+
+mod common;
+
+game_mode! {
+    /// A mode that starts with plenty of iron and steel.
+    Stockpile {
+        starting: StockpileStartingResources {
+            iron: Bundle<Iron, 50>,
+            steel: Bundle<Steel, 5>,
+        },
+        victory: (Bundle<Iron, 50>, Bundle<Steel, 5>),
+    }
+}
+
+#[test]
+fn custom_game_mode_starts_with_given_bundles() {
+    let GameResult {
+        ticks,
+        victory_resources: (iron, steel),
+        ..
+    } = common::win::<Stockpile>(|tick, StockpileStartingResources { iron, steel }| {
+        (tick, (iron, steel))
+    });
+    assert_eq!(ticks, 0);
+    assert_eq!(iron.amount(), 50);
+    assert_eq!(steel.amount(), 5);
+}
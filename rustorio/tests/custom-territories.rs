@@ -0,0 +1,57 @@
+use rustorio::{
+    Bundle, GameMode, Tick,
+    resources::{CopperOre, IronOre},
+    territory::Territory,
+};
+use rustorio_engine::gamemodes::StartingResources;
+
+mod common;
+
+/// A mode that starts with a small territory of each ore, and is won by mining some of both.
+struct Prospecting;
+
+struct ProspectingStartingResources {
+    iron_territory: Territory<IronOre>,
+    copper_territory: Territory<CopperOre>,
+}
+
+impl StartingResources for ProspectingStartingResources {
+    fn init(tick: &Tick) -> Self {
+        Self {
+            iron_territory: Territory::new(tick, 2).with_deposit(10),
+            copper_territory: Territory::new(tick, 3),
+        }
+    }
+}
+
+impl GameMode for Prospecting {
+    type StartingResources = ProspectingStartingResources;
+    type VictoryResources = (Bundle<IronOre, 5>, Bundle<CopperOre, 5>);
+}
+
+#[test]
+fn custom_game_mode_hands_out_territories() {
+    common::win::<Prospecting>(|mut tick, starting_resources| {
+        let ProspectingStartingResources {
+            mut iron_territory,
+            mut copper_territory,
+        } = starting_resources;
+        assert_eq!(iron_territory.max_miners(), 2);
+        assert_eq!(copper_territory.max_miners(), 3);
+
+        let iron = iron_territory.hand_mine::<5>(&mut tick);
+        let copper = copper_territory.hand_mine::<5>(&mut tick);
+        assert_eq!(iron_territory.remaining_ore(&tick), Some(5));
+        (tick, (iron, copper))
+    });
+}
+
+#[test]
+#[should_panic = "Territories can only be created while the game starts"]
+fn territories_cannot_be_created_during_play() {
+    common::win::<Prospecting>(|tick, starting_resources| {
+        let _ = starting_resources;
+        let _ = Territory::<IronOre>::new(&tick, 100);
+        unreachable!()
+    });
+}
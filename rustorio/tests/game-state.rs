@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+use rustorio::{
+    Resource, Tick,
+    buildings::Furnace,
+    gamemodes::{Sandbox, SandboxStartingResources},
+    recipes::IronSmelting,
+    resources::{Iron, IronOre},
+    territory::{Miner, Territory},
+};
+use rustorio_engine::game_state::GameState;
+
+mod common;
+
+type Factory = (Furnace<IronSmelting>, Territory<IronOre>, Resource<Iron>);
+
+fn save_path() -> PathBuf {
+    std::env::temp_dir().join(format!("rustorio-game-state-{}.json", std::process::id()))
+}
+
+#[test]
+fn game_state_round_trip() {
+    let uninterrupted = common::win::<Sandbox>(|mut tick, starting_resources| {
+        let factory = build(&mut tick, starting_resources);
+        finish(tick, factory)
+    })
+    .ticks;
+    common::win::<Sandbox>(|mut tick, starting_resources| {
+        let (furnace, territory, iron) = build(&mut tick, starting_resources);
+        let mut state = GameState::new();
+        state.store("furnace", furnace);
+        state.store("iron territory", territory);
+        state.store("iron", iron);
+        state.save(&tick, save_path()).unwrap();
+        (tick, ())
+    });
+    let resumed = common::win::<Sandbox>(|mut tick, _| {
+        // Loaded at the start, the game resumes where the save was made without logging the time as played.
+        tick.start_event_log(4);
+        let mut state = GameState::load(&mut tick, save_path()).unwrap();
+        assert_eq!(tick.event_log().unwrap().events().count(), 0);
+        assert!(state.take::<Resource<Iron>>(&tick, "furnace").is_err());
+        let factory = (
+            state.take(&tick, "furnace").unwrap(),
+            state.take(&tick, "iron territory").unwrap(),
+            state.take(&tick, "iron").unwrap(),
+        );
+        assert!(state.is_empty());
+        finish(tick, factory)
+    })
+    .ticks;
+    std::fs::remove_file(save_path()).unwrap();
+    assert_eq!(resumed, uninterrupted);
+}
+
+/// Builds a small factory that is in the middle of smelting and mining.
+fn build(tick: &mut Tick, starting_resources: SandboxStartingResources) -> Factory {
+    let SandboxStartingResources {
+        mut iron_ore,
+        mut iron,
+        mut copper,
+        mut iron_territory,
+        ..
+    } = starting_resources;
+    let mut furnace = Furnace::build(tick, IronSmelting, iron.bundle().unwrap());
+    furnace.inputs(tick).0 += iron_ore.split_off(10).unwrap();
+    let miner = Miner::build(iron.bundle().unwrap(), copper.bundle().unwrap());
+    iron_territory.add_miner(tick, miner).unwrap();
+    tick.advance_by(15);
+    (furnace, iron_territory, iron.split_off(7).unwrap())
+}
+
+/// Finishes the game, checking that the factory is exactly as it would have been without a save in between.
+fn finish(mut tick: Tick, factory: Factory) -> (Tick, ()) {
+    let (mut furnace, mut territory, iron) = factory;
+    assert_eq!(tick.cur(), 15);
+    assert_eq!(iron.amount(), 7);
+    assert_eq!(furnace.outputs(&tick).0.amount(), 2);
+    assert_eq!(furnace.inputs(&tick).0.amount(), 8);
+
+    tick.advance_by(45);
+    assert_eq!(furnace.outputs(&tick).0.amount(), 10);
+    assert_eq!(territory.resources(&tick).amount(), 30);
+    (tick, ())
+}
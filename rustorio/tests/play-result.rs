@@ -0,0 +1,39 @@
+use rustorio::{
+    AlreadyPlayedError, AtLeast, GameResult, Tick,
+    buildings::Furnace,
+    gamemodes::{Tutorial, TutorialStartingResources},
+    play_result,
+    recipes::CopperSmelting,
+    resources::Copper,
+};
+
+#[test]
+fn second_play_is_refused() {
+    let Ok(GameResult { outcome, .. }) = play_result::<Tutorial>(smelt_copper) else {
+        panic!("The first game should be played");
+    };
+    assert!(outcome.is_ok());
+
+    let Err(err) = play_result::<Tutorial>(smelt_copper) else {
+        panic!("Only one game may be played per process");
+    };
+    assert_eq!(err, AlreadyPlayedError);
+}
+
+fn smelt_copper(
+    mut tick: Tick,
+    starting_resources: TutorialStartingResources,
+) -> (Tick, AtLeast<Copper, 4>) {
+    let TutorialStartingResources {
+        iron,
+        mut copper_territory,
+        ..
+    } = starting_resources;
+
+    let mut furnace = Furnace::build(&tick, CopperSmelting, iron);
+    furnace.inputs(&tick).0 += copper_territory.hand_mine::<4>(&mut tick);
+    tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 4, 100);
+
+    let win_bundle = furnace.outputs(&tick).0.bundle::<4>().unwrap();
+    (tick, win_bundle.into())
+}
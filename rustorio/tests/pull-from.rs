@@ -0,0 +1,50 @@
+use rustorio::{
+    buildings::{Building, Furnace},
+    gamemodes::{Tutorial, TutorialStartingResources},
+    recipes::{CopperSmelting, IronSmelting},
+    territory::Miner,
+};
+
+mod common;
+
+#[test]
+fn pull_from() {
+    common::win::<Tutorial<4>>(|mut tick, starting_resources| {
+        let TutorialStartingResources {
+            iron,
+            mut iron_territory,
+            mut copper_territory,
+            guide: _,
+        } = starting_resources;
+
+        let mut furnace = Furnace::build(&tick, IronSmelting, iron);
+        furnace.inputs(&tick).0 += iron_territory.hand_mine::<10>(&mut tick);
+        tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 10, 100);
+        let iron = furnace.outputs(&tick).0.bundle().unwrap();
+
+        let mut furnace = furnace.change_recipe(CopperSmelting).unwrap();
+        furnace.inputs(&tick).0 += copper_territory.hand_mine::<5>(&mut tick);
+        tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 5, 100);
+        let copper = furnace.outputs(&tick).0.bundle().unwrap();
+
+        copper_territory
+            .add_miner(&tick, Miner::build(iron, copper))
+            .unwrap();
+        let _ = copper_territory.resources(&tick).empty();
+        let before = copper_territory.stats(&tick).total_mined;
+        tick.advance_by(100);
+
+        // Everything mined moves into the furnace, leaving the territory empty.
+        let mined = copper_territory.stats(&tick).total_mined - before;
+        let pulled = furnace.pull_from::<0>(&tick, &mut copper_territory);
+        assert!(pulled > 0);
+        assert_eq!(u64::from(pulled), mined);
+        assert_eq!(copper_territory.resources(&tick).amount(), 0);
+        assert_eq!(furnace.input::<0>(&tick).amount(), pulled);
+        assert_eq!(furnace.pull_from::<0>(&tick, &mut copper_territory), 0);
+
+        tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 4, 100);
+        let win_bundle = furnace.outputs(&tick).0.bundle::<4>().unwrap();
+        (tick, win_bundle.into())
+    });
+}
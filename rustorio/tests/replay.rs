@@ -0,0 +1,141 @@
+use rustorio::{
+    AtLeast, HandRecipe, Technology, Tick,
+    buildings::{Furnace, Lab},
+    gamemodes::{Sandbox, SandboxStartingResources, Tutorial, TutorialStartingResources},
+    recipes::{CopperSmelting, CopperWireRecipe},
+    research::SteelTechnology,
+    resources::Copper,
+};
+use rustorio_engine::{
+    replay::{ActionKind, Replay},
+    verify_replay,
+};
+
+mod common;
+
+#[test]
+fn replay_reproduces_run() {
+    let result =
+        common::win::<Tutorial>(|tick, starting_resources| play(tick, starting_resources, 0));
+    let replay = result.replay.expect("The game was recorded");
+    assert_eq!(replay.final_tick(), result.ticks);
+    let kinds: Vec<_> = replay.actions().iter().map(|action| &action.kind).collect();
+    assert!(kinds.contains(&&ActionKind::Mine {
+        ore: "CopperOre".to_string(),
+        amount: 5,
+    }));
+    assert!(kinds.contains(&&ActionKind::Feed {
+        resource: "CopperOre".to_string(),
+        amount: 5,
+    }));
+    assert!(kinds.contains(&&ActionKind::Craft {
+        recipe: "CopperWireRecipe".to_string(),
+    }));
+    // Feeds are recorded as they happen, even when nothing touches the furnace afterwards.
+    assert_eq!(
+        kinds.last(),
+        Some(&&ActionKind::Feed {
+            resource: "CopperOre".to_string(),
+            amount: 1,
+        })
+    );
+
+    let path = std::env::temp_dir().join(format!("rustorio-replay-{}.json", std::process::id()));
+    replay.save(&path).unwrap();
+    let loaded = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, replay);
+
+    let reproduced = verify_replay::<Tutorial>(
+        |tick, starting_resources| play(tick, starting_resources, 0),
+        &loaded,
+    )
+    .unwrap();
+    assert_eq!(reproduced.ticks, result.ticks);
+
+    let slower = |tick, starting_resources| play(tick, starting_resources, 3);
+    let Err(mismatch) = verify_replay::<Tutorial>(slower, &loaded) else {
+        panic!("A different run should not reproduce the replay");
+    };
+    assert_eq!(mismatch.index, 0);
+}
+
+#[test]
+fn replay_with_research_round_trips() {
+    let result = common::win::<Sandbox>(research_steel);
+    let replay = result.replay.expect("The game was recorded");
+    let research = replay
+        .actions()
+        .iter()
+        .find(|action| {
+            action.kind
+                == ActionKind::Research {
+                    technology: "Steel".to_string(),
+                }
+        })
+        .expect("The research was recorded");
+    assert_eq!(research.tick, result.ticks);
+
+    let path = std::env::temp_dir().join(format!(
+        "rustorio-research-replay-{}.json",
+        std::process::id()
+    ));
+    replay.save(&path).unwrap();
+    let loaded = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, replay);
+
+    let reproduced = verify_replay::<Sandbox>(research_steel, &loaded).unwrap();
+    assert_eq!(reproduced.ticks, result.ticks);
+}
+
+/// Researches steel in the sandbox while recording.
+fn research_steel(mut tick: Tick, starting_resources: SandboxStartingResources) -> (Tick, ()) {
+    tick.start_recording();
+    let SandboxStartingResources {
+        mut iron,
+        mut copper,
+        mut red_science,
+        steel_technology,
+        ..
+    } = starting_resources;
+    let mut lab = Lab::build(
+        &tick,
+        &steel_technology,
+        iron.bundle().unwrap(),
+        copper.bundle().unwrap(),
+    );
+    let points = SteelTechnology::REQUIRED_RESEARCH_POINTS;
+    lab.inputs(&tick).0 += red_science.split_off(points).unwrap();
+    assert!(lab.run_until(&mut tick, points, |_, _| {}, 10_000));
+    let research_points = lab.outputs(&tick).0.bundle().unwrap();
+    let _ = steel_technology.research(&tick, research_points);
+    (tick, ())
+}
+
+/// Wins the tutorial while recording, after idling for `idle` ticks.
+fn play(
+    mut tick: Tick,
+    starting_resources: TutorialStartingResources,
+    idle: u64,
+) -> (Tick, AtLeast<Copper, 4>) {
+    tick.start_recording();
+    let TutorialStartingResources {
+        iron,
+        mut copper_territory,
+        ..
+    } = starting_resources;
+    tick.advance_by(idle);
+
+    let mut furnace = Furnace::build(&tick, CopperSmelting, iron);
+    let ore = copper_territory.hand_mine::<5>(&mut tick);
+    furnace.inputs(&tick).0 += ore;
+    tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 5, 100);
+
+    let copper = furnace.outputs(&tick).0.bundle::<1>().unwrap();
+    let _ = CopperWireRecipe::craft(&mut tick, (copper,));
+    let win_bundle = furnace.outputs(&tick).0.bundle::<4>().unwrap();
+    let ore = copper_territory.hand_mine::<1>(&mut tick);
+    furnace.inputs(&tick).0 += ore;
+    (tick, win_bundle.into())
+}
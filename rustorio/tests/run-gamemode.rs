@@ -0,0 +1,47 @@
+use rustorio::{
+    AtLeast, Tick,
+    buildings::Furnace,
+    gamemodes::{Tutorial, TutorialStartingResources},
+    recipes::CopperSmelting,
+    resources::Copper,
+};
+use rustorio_engine::{GameResult, gamemodes::config_hash, run_gamemode};
+
+#[test]
+fn run_gamemode_returns_result() {
+    let GameResult {
+        ticks,
+        victory_resources,
+        outcome,
+        replay,
+        stats,
+        config_hash: hash,
+    } = run_gamemode::<Tutorial>(smelt_copper);
+    assert_eq!(victory_resources.amount(), 4);
+    assert!(outcome.is_ok());
+    assert!(replay.is_none());
+    assert!(stats.is_none());
+    assert_eq!(hash, config_hash(&()));
+
+    // Unlike `play`, games can be run repeatedly, and are deterministic.
+    let again = run_gamemode::<Tutorial>(smelt_copper);
+    assert_eq!(again.ticks, ticks);
+}
+
+fn smelt_copper(
+    mut tick: Tick,
+    starting_resources: TutorialStartingResources,
+) -> (Tick, AtLeast<Copper, 4>) {
+    let TutorialStartingResources {
+        iron,
+        mut copper_territory,
+        ..
+    } = starting_resources;
+
+    let mut furnace = Furnace::build(&tick, CopperSmelting, iron);
+    furnace.inputs(&tick).0 += copper_territory.hand_mine::<4>(&mut tick);
+    tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 4, 100);
+
+    let win_bundle = furnace.outputs(&tick).0.bundle::<4>().unwrap();
+    (tick, win_bundle.into())
+}
@@ -1,7 +1,7 @@
 #![forbid(unsafe_code)]
 
 use rustorio::{
-    self, Bundle, HandRecipe, ResearchPoint, Resource, Technology, Tick,
+    self, GameMode as _, HandRecipe, ResearchPoint, Resource, Technology, Tick,
     buildings::{Assembler, Furnace, Lab},
     gamemodes::Standard,
     recipes::{
@@ -15,6 +15,7 @@ use rustorio::{
 type GameMode = Standard;
 
 type StartingResources = <GameMode as rustorio::GameMode>::StartingResources;
+type VictoryResources = <GameMode as rustorio::GameMode>::VictoryResources;
 
 #[test]
 fn main() {
@@ -25,12 +26,13 @@ fn craft<R: HandRecipe>(inputs: R::InputBundle, tick: &mut Tick) -> R::OutputBun
     R::craft(tick, inputs)
 }
 
-fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Bundle<Point, 200>) {
+fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, VictoryResources) {
     let StartingResources {
         iron,
         mut iron_territory,
         mut copper_territory,
         steel_technology,
+        guide: _,
     } = starting_resources;
 
     let mut iron_furnace = Furnace::build(&tick, IronSmelting, iron);
@@ -210,7 +212,7 @@ fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Bu
     }
 
     let (steel_smelting, points_technology) =
-        steel_technology.research(research_points.bundle().unwrap());
+        steel_technology.research(&tick, research_points.bundle().unwrap());
 
     println!("Researched steel technology at tick {}", tick.cur());
 
@@ -244,7 +246,7 @@ fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Bu
         tick.advance();
     }
 
-    let point_recipe = points_technology.research(research_points.bundle().unwrap());
+    let point_recipe = points_technology.research(&tick, research_points.bundle().unwrap());
 
     println!("Researched points technology at tick {}", tick.cur());
 
@@ -296,7 +298,7 @@ fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Bu
     println!("Created assembler for points at tick {}", tick.cur());
 
     let mut points: Resource<Point> = Resource::new_empty();
-    while points.amount() < 200 {
+    while points.amount() < GameMode::VICTORY_AMOUNT {
         points += point_assembler.outputs(&tick).0.empty();
 
         point_assembler.inputs(&tick).0 += circuit_assembler.outputs(&tick).0.empty();
@@ -324,7 +326,11 @@ fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Bu
         tick.advance();
     }
 
-    println!("Produced 200 points at tick {}", tick.cur());
+    println!(
+        "Produced {} points at tick {}",
+        GameMode::VICTORY_AMOUNT,
+        tick.cur()
+    );
 
     (tick, points.bundle().unwrap())
 }
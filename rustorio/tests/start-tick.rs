@@ -0,0 +1,62 @@
+use rustorio::{
+    Bundle, GameMode, Tick,
+    resources::{Copper, CopperOre, Iron},
+    territory::{MINING_TICK_LENGTH, Miner, Territory},
+};
+use rustorio_engine::gamemodes::{StartingResources, starting_bundle};
+
+mod common;
+
+/// A scenario picked up at tick 10,000 with a fresh territory and enough for one miner.
+struct Resumed;
+
+struct ResumedStartingResources {
+    copper_territory: Territory<CopperOre>,
+    iron: Bundle<Iron, 10>,
+    copper: Bundle<Copper, 5>,
+}
+
+impl StartingResources for ResumedStartingResources {
+    fn init(tick: &Tick) -> Self {
+        assert_eq!(tick.cur(), 10_000);
+        Self {
+            copper_territory: Territory::new(tick, 1),
+            iron: starting_bundle(tick),
+            copper: starting_bundle(tick),
+        }
+    }
+}
+
+impl GameMode for Resumed {
+    type StartingResources = ResumedStartingResources;
+    type VictoryResources = Bundle<CopperOre, 10>;
+
+    const START_TICK: u64 = 10_000;
+}
+
+#[test]
+fn games_can_start_at_a_later_tick() {
+    let result = common::win::<Resumed>(|mut tick, starting_resources| {
+        let ResumedStartingResources {
+            mut copper_territory,
+            iron,
+            copper,
+        } = starting_resources;
+        assert_eq!(tick.cur(), 10_000);
+
+        // Nothing is mined for the ticks before the game started.
+        copper_territory
+            .add_miner(&tick, Miner::build(iron, copper))
+            .unwrap();
+        assert_eq!(copper_territory.stats(&tick).total_mined, 0);
+        assert_eq!(copper_territory.resources(&tick).amount(), 0);
+
+        tick.advance_by(10 * MINING_TICK_LENGTH);
+        assert_eq!(tick.cur(), 10_000 + 10 * MINING_TICK_LENGTH);
+        assert_eq!(copper_territory.stats(&tick).total_mined, 10);
+        let ore = copper_territory.resources(&tick).bundle().unwrap();
+        (tick, ore)
+    });
+    // Only the ticks played from the start tick count.
+    assert_eq!(result.ticks, 10 * MINING_TICK_LENGTH);
+}
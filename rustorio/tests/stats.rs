@@ -0,0 +1,57 @@
+use rustorio::{
+    buildings::Furnace,
+    gamemodes::{Tutorial, TutorialStartingResources},
+    recipes::CopperSmelting,
+};
+use rustorio_engine::stats::{MachineStats, ResourceStats, STATS_VERSION};
+
+mod common;
+
+#[test]
+fn stats_are_collected() {
+    let result = common::win::<Tutorial>(|mut tick, starting_resources| {
+        tick.collect_stats();
+        let TutorialStartingResources {
+            iron,
+            mut copper_territory,
+            ..
+        } = starting_resources;
+
+        let mut furnace = Furnace::build(&tick, CopperSmelting, iron);
+        let ore = copper_territory.hand_mine::<4>(&mut tick);
+        furnace.inputs(&tick).0 += ore;
+        tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 4, 100);
+        tick.advance_by(10);
+
+        let win_bundle = furnace.outputs(&tick).0.bundle::<4>().unwrap();
+        (tick, win_bundle.into())
+    });
+    let stats = result.stats.expect("The game collected statistics");
+    assert_eq!(stats.version, STATS_VERSION);
+    assert_eq!(stats.ticks, result.ticks);
+    assert_eq!(
+        stats.resources["CopperOre"],
+        ResourceStats {
+            produced: 4,
+            consumed: 4,
+        }
+    );
+    assert_eq!(
+        stats.resources["Copper"],
+        ResourceStats {
+            produced: 4,
+            consumed: 0,
+        }
+    );
+    // The furnace is only idle while the ore is being mined and after it is done.
+    let MachineStats {
+        count,
+        busy_ticks,
+        total_ticks,
+        uptime,
+    } = stats.machines["CopperSmelting"];
+    assert_eq!(count, 1);
+    assert_eq!(busy_ticks, 24);
+    assert_eq!(total_ticks, result.ticks);
+    assert!((uptime - 24.0 / result.ticks as f64).abs() < 1e-9);
+}
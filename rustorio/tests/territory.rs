@@ -0,0 +1,67 @@
+use rustorio::{
+    buildings::Furnace,
+    gamemodes::{Tutorial, TutorialStartingResources},
+    recipes::{CopperSmelting, IronSmelting},
+    territory::{MINER_IRON_REFUND, Miner},
+};
+
+mod common;
+
+#[test]
+fn territory() {
+    common::win::<Tutorial>(|mut tick, starting_resources| {
+        let TutorialStartingResources {
+            iron,
+            mut iron_territory,
+            mut copper_territory,
+            guide: _,
+        } = starting_resources;
+
+        let mut furnace = Furnace::build(&tick, IronSmelting, iron);
+        furnace.inputs(&tick).0 += iron_territory.hand_mine::<10>(&mut tick);
+        tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 10, 100);
+        let iron = furnace.outputs(&tick).0.bundle().unwrap();
+
+        let mut furnace = furnace.change_recipe(CopperSmelting).unwrap();
+        furnace.inputs(&tick).0 += copper_territory.hand_mine::<9>(&mut tick);
+        tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 9, 100);
+        let copper = furnace.outputs(&tick).0.bundle().unwrap();
+
+        copper_territory
+            .add_miner(&tick, Miner::build(iron, copper))
+            .unwrap();
+
+        // The reported mining rate matches what is actually mined over a span of ticks.
+        let _ = copper_territory.resources(&tick).empty();
+        let before = copper_territory.stats(&tick);
+        assert_eq!(before.ticks_since_collection, 0);
+        tick.advance_by(100);
+        let after = copper_territory.stats(&tick);
+        assert_eq!(after.ticks_since_collection, 100);
+        assert_eq!(after.total_mined - before.total_mined, 50);
+        let mined = copper_territory.resources(&tick).empty();
+        assert_eq!(f64::from(mined.amount()), after.rate * 100.0);
+
+        // Deconstructing the miner refunds part of its iron, and the territory stops mining.
+        assert_eq!(copper_territory.num_miners(), 1);
+        let refund = copper_territory.take_miner_refund(&tick).unwrap();
+        assert_eq!(refund.amount(), MINER_IRON_REFUND);
+        assert_eq!(copper_territory.num_miners(), 0);
+        assert!(copper_territory.take_miner_refund(&tick).is_none());
+        tick.advance_by(100);
+        assert_eq!(copper_territory.resources(&tick).amount(), 0);
+
+        // Hand mining a finite deposit only takes time for the ore that was actually there.
+        let mut iron_territory = iron_territory.with_deposit(3);
+        let start = tick.cur();
+        let ore = iron_territory.try_hand_mine::<5>(&mut tick).unwrap_err();
+        assert_eq!(ore.amount(), 3);
+        assert_eq!(tick.cur() - start, 3 * iron_territory.mining_tick_length());
+        assert_eq!(iron_territory.remaining_ore(&tick), Some(0));
+
+        furnace.inputs(&tick).0 += mined;
+        tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 4, 100);
+        let win_bundle = furnace.outputs(&tick).0.bundle::<4>().unwrap();
+        (tick, win_bundle.into())
+    });
+}
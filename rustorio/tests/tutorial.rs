@@ -27,6 +27,7 @@ fn user_main(mut tick: Tick, starting_resources: StartingResources) -> (Tick, Vi
     furnace.inputs(&tick).0 += copper_ore;
     tick.advance_until(|tick| furnace.outputs(tick).0.amount() >= 4, 100);
 
-    let win_bundle = furnace.outputs(&tick).0.bundle().unwrap();
-    (tick, win_bundle)
+    // Any amount of copper from 4 up wins.
+    let copper = furnace.outputs(&tick).0.empty();
+    (tick, copper.into())
 }